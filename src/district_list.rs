@@ -0,0 +1,205 @@
+use fltk::app;
+use fltk::app::Sender;
+use fltk::browser::MultiBrowser;
+use fltk::button::Button;
+use fltk::enums::Align;
+use fltk::enums::CallbackTrigger;
+use fltk::enums::ColorDepth;
+use fltk::frame::Frame;
+use fltk::group::Group;
+use fltk::image::RgbImage;
+use fltk::input::Input;
+use fltk::prelude::BrowserExt;
+use fltk::prelude::GroupExt;
+use fltk::prelude::InputExt;
+use fltk::prelude::WidgetBase;
+use fltk::prelude::WidgetExt;
+use fltk::widget_extends;
+use fltk_theme::widget_themes;
+
+use crate::grouping::Grouping;
+use crate::gui::MenuChoice;
+
+fn get_swatch_size() -> i32 {14}
+fn get_side_button_width() -> i32 {60}
+fn get_row_height() -> i32 {25}
+
+/// # DistrictList
+///
+/// A list of districts showing a color swatch, name and lock state for each one.
+/// Several districts can be selected at once with ctrl or shift clicks, and the selected districts can be moved up and down.
+/// When exactly one district is selected its name is shown in an input below the list, and pressing enter there renames it.
+/// Double clicking a district jumps to that input.
+pub struct DistrictList {
+	/// # group
+	/// The group holding every part of the list
+	pub group: Group,
+	/// the list of districts itself
+	browser: MultiBrowser,
+	/// shows how many districts there are and how many are selected
+	count_frame: Frame,
+	/// shows and edits the name of the selected district
+	name_input: Input,
+	/// the name, color and lock state of each district being shown
+	entries: Vec<(String, (u8,u8,u8), bool)>,
+}//end struct DistrictList
+
+impl DistrictList {
+	/// # new(x, y, w, h, label, sender)
+	///
+	/// constructs an empty district list at the specified position and size.
+	/// Renaming and moving districts are sent through sender as messages for the main loop to handle.
+	pub fn new(x:i32, y:i32, w:i32, h:i32, label:&str, sender:Sender<MenuChoice>) -> DistrictList {
+		let mut group = Group::new(x, y, w, h, None);
+		group.set_label(label);
+		group.set_align(Align::Top);
+		let list_w = w - get_side_button_width() - 5;
+		let list_h = h - 2 * get_row_height() - 10;
+
+		let mut browser = MultiBrowser::new(x, y, list_w, list_h, None);
+		browser.set_column_char('\t');
+		browser.set_column_widths(&[list_w - 80, 70]);
+		// names are shown exactly as typed, without fltk's @ formatting
+		browser.set_format_char('\u{0}');
+
+		let mut up_button = Button::new(x + list_w + 5, y, get_side_button_width(), get_row_height(), "Up");
+		up_button.emit(sender, MenuChoice::MoveDistrictsUp);
+		up_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut down_button = Button::new(x + list_w + 5, y + get_row_height() + 5, get_side_button_width(), get_row_height(), "Down");
+		down_button.emit(sender, MenuChoice::MoveDistrictsDown);
+		down_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+
+		let mut count_frame = Frame::new(x, y + list_h + 5, list_w, get_row_height(), None);
+		count_frame.set_align(Align::Left | Align::Inside);
+
+		let mut name_input = Input::new(x + 45, y + list_h + get_row_height() + 10, list_w - 45, get_row_height(), "Name");
+		name_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+		name_input.emit(sender, MenuChoice::RenameDistrict);
+		name_input.deactivate();
+		group.end();
+
+		// keep the count and name input in sync with the selection
+		let mut callback_count = count_frame.clone();
+		let mut callback_input = name_input.clone();
+		browser.set_callback(move |browser| {
+			update_selection_widgets(browser, &mut callback_count, &mut callback_input);
+			if app::event_clicks() && browser.selected_items().len() == 1 {
+				let _ = callback_input.take_focus();
+			}//end if a single district was double clicked
+		});
+
+		let mut list = DistrictList {
+			group,
+			browser,
+			count_frame,
+			name_input,
+			entries: Vec::new(),
+		};//end struct construction
+		list.set_districts(&[]);
+		return list;
+	}//end new(x, y, w, h, label, sender)
+
+	/// # set_districts(&mut self, districts)
+	///
+	/// replaces the districts being shown, keeping whichever districts were selected by position if they still exist
+	pub fn set_districts(&mut self, districts:&[Grouping]) {
+		let selected = self.selected();
+		self.entries = districts.iter()
+			.map(|district| (district.name.clone(), district.rgb_color, district.locked))
+			.collect();
+		self.rebuild(&selected);
+	}//end set_districts(&mut self, districts)
+
+	/// # selected(&self)
+	///
+	/// returns the indices of every selected district, from first to last
+	pub fn selected(&self) -> Vec<usize> {
+		self.browser.selected_items().iter()
+			.filter(|line| **line > 0)
+			.map(|line| (*line - 1) as usize)
+			.collect()
+	}//end selected(&self)
+
+	/// # set_selected(&mut self, indices)
+	///
+	/// selects exactly the districts at indices, ignoring any which don't exist
+	pub fn set_selected(&mut self, indices:&[usize]) {
+		self.rebuild(indices);
+	}//end set_selected(&mut self, indices)
+
+	/// # rebuild(&mut self, selected)
+	///
+	/// fills the browser from entries, selecting the districts at the indices in selected.
+	/// The browser can't deselect single lines, so this is also how the selection gets changed.
+	fn rebuild(&mut self, selected:&[usize]) {
+		self.browser.clear();
+		for (index, (name, rgb_color, locked)) in self.entries.iter().enumerate() {
+			let lock_note = if *locked {"locked"} else {""};
+			self.browser.add(&format!("{}\t{}", name, lock_note));
+			self.browser.set_icon(index as i32 + 1, Some(make_swatch(*rgb_color)));
+		}//end adding each district to the list
+		for index in selected {
+			if *index < self.entries.len() {
+				self.browser.select(*index as i32 + 1);
+			}//end if this district exists
+		}//end selecting each district
+		update_selection_widgets(&self.browser, &mut self.count_frame, &mut self.name_input);
+		self.browser.redraw();
+	}//end rebuild(&mut self, selected)
+
+	/// # name_input_value(&self)
+	///
+	/// returns whatever is typed in the name input
+	pub fn name_input_value(&self) -> String {
+		self.name_input.value()
+	}//end name_input_value(&self)
+
+	/// # focus_name_input(&mut self)
+	///
+	/// moves keyboard focus to the name input and selects its text, so that typing replaces the name
+	pub fn focus_name_input(&mut self) {
+		if self.name_input.active() {
+			let _ = self.name_input.take_focus();
+			let end = self.name_input.value().len() as i32;
+			let _ = self.name_input.set_position(0);
+			let _ = self.name_input.set_mark(end);
+		}//end if there's a name to edit
+	}//end focus_name_input(&mut self)
+}//end impl for DistrictList
+
+widget_extends!(DistrictList, Group, group);
+
+/// # update_selection_widgets(browser, count_frame, name_input)
+///
+/// updates the district count and the name input to match what's selected in browser
+fn update_selection_widgets(browser:&MultiBrowser, count_frame:&mut Frame, name_input:&mut Input) {
+	let selected = browser.selected_items();
+	let total = browser.size();
+	let district_word = if total == 1 {"district"} else {"districts"};
+	count_frame.set_label(&format!("{} {}, {} selected", total, district_word, selected.len()));
+
+	if selected.len() == 1 {
+		let text = browser.text(selected[0]).unwrap_or_default();
+		let name = text.split('\t').next().unwrap_or_default();
+		name_input.set_value(name);
+		name_input.activate();
+	}//end if there's exactly one district to name
+	else {
+		name_input.set_value("");
+		name_input.deactivate();
+	}//end else renaming doesn't make sense
+}//end update_selection_widgets(browser, count_frame, name_input)
+
+/// # make_swatch(rgb)
+///
+/// creates a small square image filled with rgb, to show next to a district's name
+fn make_swatch(rgb:(u8,u8,u8)) -> RgbImage {
+	let size = get_swatch_size();
+	let mut data = Vec::with_capacity((size * size * 3) as usize);
+	for _ in 0..(size * size) {
+		data.push(rgb.0);
+		data.push(rgb.1);
+		data.push(rgb.2);
+	}//end filling in each pixel
+	RgbImage::new(&data, size, size, ColorDepth::Rgb8).expect("swatch data should match its size")
+}//end make_swatch(rgb)
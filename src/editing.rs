@@ -0,0 +1,316 @@
+use grid::Grid;
+
+use crate::grouping::BuildingType;
+use crate::grouping::Coord;
+use crate::grouping::GroupInstance;
+use crate::grouping::Grouping;
+use crate::grouping::cells_in_district;
+use crate::palette::BuildingPalette;
+
+/// # get_max_district_name_len()
+/// 
+/// The most characters a district name can have
+fn get_max_district_name_len() -> usize {50}
+
+/// # reassign_district_cell(grid, districts, coord, new_district)
+/// 
+/// Moves the district cell at coord into the district at index new_district of districts.
+/// The coord is taken out of the locations of whichever district had it before, added to the locations of the new district, and the GroupInstance in the grid is updated to match.
+/// The neighborhood inside the cell is left alone.
+/// Locked cells, and cells in a locked district, can't be moved.
+/// 
+/// ## Return
+/// Returns true if the cell actually changed districts, or false if it was already in that district, was locked, or coord or new_district were out of range.
+pub fn reassign_district_cell(grid:&mut Grid<GroupInstance>, districts:&mut [Grouping], coord:Coord, new_district:usize) -> bool {
+    if new_district >= districts.len() {return false;}
+    let instance = match grid.get_mut(coord.row, coord.col) {
+        Some(instance) => instance,
+        None => return false,
+    };//end matching whether coord is in the grid
+    if instance.is_locked(districts) {return false;}
+
+    // don't do anything if we're already in the right district
+    if instance.district_id == Some(districts[new_district].id) && districts[new_district].locations.contains(&coord) {
+        return false;
+    }//end if this cell is already in the new district
+
+    // take the cell away from whoever had it
+    for district in districts.iter_mut() {
+        district.locations.retain(|location| *location != coord);
+    }//end removing coord from each district
+
+    // give the cell to the new district
+    let district = &mut districts[new_district];
+    district.locations.push(coord);
+    instance.district_id = Some(district.id);
+    instance.coord = Some(coord);
+    return true;
+}//end reassign_district_cell(grid, districts, coord, new_district)
+
+/// # set_building_type(nhood, coord, build_type, palette)
+/// 
+/// Changes the building at coord in the sub_grid of nhood to build_type, recoloring it with palette.
+/// Locked buildings can't be changed.
+/// 
+/// ## Return
+/// Returns true if the building actually changed, or false if it was already that type, was locked, or coord was out of range.
+pub fn set_building_type(nhood:&mut GroupInstance, coord:Coord, build_type:&BuildingType, palette:&BuildingPalette) -> bool {
+    let building = match nhood.sub_grid.get_mut(coord.row, coord.col) {
+        Some(building) => building,
+        None => return false,
+    };//end matching whether coord is in the neighborhood
+    if building.locked || building.build_type == *build_type {return false;}
+    building.build_type = build_type.clone();
+    palette.color_code_building(building);
+    return true;
+}//end set_building_type(nhood, coord, build_type, palette)
+
+/// # set_building_info(nhood, coord, name, notes)
+/// 
+/// Gives the building at coord in the sub_grid of nhood a name and notes, or clears them if both are empty.
+/// Unlike changing its type, this works on locked buildings too, since it doesn't change the city itself.
+/// 
+/// ## Return
+/// Returns true if the name or notes actually changed, or false if they were already the same or coord was out of range.
+pub fn set_building_info(nhood:&mut GroupInstance, coord:Coord, name:&str, notes:&str) -> bool {
+    let building = match nhood.sub_grid.get_mut(coord.row, coord.col) {
+        Some(building) => building,
+        None => return false,
+    };//end matching whether coord is in the neighborhood
+    let before = building.info.clone();
+    building.set_info(name, notes);
+    return building.info != before;
+}//end set_building_info(nhood, coord, name, notes)
+
+/// # set_cell_locked(grid, coord, locked)
+/// 
+/// Locks or unlocks the district cell at coord, so that it keeps its district when regenerating.
+/// 
+/// ## Return
+/// Returns true if the lock actually changed, or false if it was already set that way or coord was out of range.
+pub fn set_cell_locked(grid:&mut Grid<GroupInstance>, coord:Coord, locked:bool) -> bool {
+    match grid.get_mut(coord.row, coord.col) {
+        Some(instance) if instance.cell_locked != locked => {
+            instance.cell_locked = locked;
+            true
+        },
+        _ => false,
+    }//end matching whether there's something to change
+}//end set_cell_locked(grid, coord, locked)
+
+/// # set_building_locked(nhood, coord, locked)
+/// 
+/// Locks or unlocks the building at coord in the sub_grid of nhood, so that it stays the same when the neighborhood is regenerated.
+/// 
+/// ## Return
+/// Returns true if the lock actually changed, or false if it was already set that way or coord was out of range.
+pub fn set_building_locked(nhood:&mut GroupInstance, coord:Coord, locked:bool) -> bool {
+    match nhood.sub_grid.get_mut(coord.row, coord.col) {
+        Some(building) if building.locked != locked => {
+            building.locked = locked;
+            true
+        },
+        _ => false,
+    }//end matching whether there's something to change
+}//end set_building_locked(nhood, coord, locked)
+
+/// # set_district_locked(grid, districts, district_index, locked)
+/// 
+/// Locks or unlocks a whole district, so that all of its cells keep their district when regenerating.
+/// 
+/// ## Return
+/// Returns the coordinates of every cell in grid belonging to the district, which need to be redrawn, or an empty list if district_index was out of range.
+pub fn set_district_locked(grid:&Grid<GroupInstance>, districts:&mut [Grouping], district_index:usize, locked:bool) -> Vec<Coord> {
+    let district = match districts.get_mut(district_index) {
+        Some(district) => district,
+        None => return Vec::new(),
+    };//end matching whether district_index is valid
+    district.locked = locked;
+    return cells_in_district(grid, district.id);
+}//end set_district_locked(grid, districts, district_index, locked)
+
+/// # rename_district(grid, districts, district_index, new_name)
+/// 
+/// Renames the district at district_index.
+/// 
+/// ## Return
+/// Returns the coordinates of every cell in grid belonging to the district, which need to be redrawn, or an empty list if district_index was out of range.
+pub fn rename_district(grid:&Grid<GroupInstance>, districts:&mut [Grouping], district_index:usize, new_name:&str) -> Vec<Coord> {
+    let district = match districts.get_mut(district_index) {
+        Some(district) => district,
+        None => return Vec::new(),
+    };//end matching whether district_index is valid
+    district.name = new_name.to_string();
+    return cells_in_district(grid, district.id);
+}//end rename_district(grid, districts, district_index, new_name)
+
+/// # move_districts(districts, selected, up)
+/// 
+/// Moves each district at the indices in selected one spot up the list if up is true, or down if it's false.
+/// Districts which are already at the end of the list, or are blocked by another selected district that can't move, stay where they are.
+/// 
+/// ## Return
+/// Returns the new indices of the selected districts, so they can stay selected.
+pub fn move_districts(districts:&mut [Grouping], selected:&[usize], up:bool) -> Vec<usize> {
+    let mut new_selected: Vec<usize> = selected.iter().copied().filter(|index| *index < districts.len()).collect();
+    new_selected.sort_unstable();
+    new_selected.dedup();
+    let num_selected = new_selected.len();
+    if up {
+        for i in 0..num_selected {
+            let index = new_selected[i];
+            let blocked = i > 0 && new_selected[i - 1] + 1 == index;
+            if index > 0 && !blocked {
+                districts.swap(index, index - 1);
+                new_selected[i] = index - 1;
+            }//end if this district can move up
+        }//end moving each selected district, starting from the top
+    }//end if we're moving up
+    else {
+        for i in (0..num_selected).rev() {
+            let index = new_selected[i];
+            let blocked = i + 1 < num_selected && new_selected[i + 1] == index + 1;
+            if index + 1 < districts.len() && !blocked {
+                districts.swap(index, index + 1);
+                new_selected[i] = index + 1;
+            }//end if this district can move down
+        }//end moving each selected district, starting from the bottom
+    }//end else we're moving down
+    return new_selected;
+}//end move_districts(districts, selected, up)
+
+/// # validate_district_name(name, districts, ignore_index)
+/// 
+/// Checks whether name can be used for a district, ignoring the district at ignore_index so that a district can keep its own name.
+/// Names can't be empty, can't be "empty" since that's how unclaimed cells are labelled, can't contain tabs or line breaks, can't be longer than get_max_district_name_len() characters, and can't match another district's name, ignoring case.
+/// 
+/// ## Return
+/// Returns the name with surrounding whitespace trimmed off, or a message explaining what's wrong with it.
+pub fn validate_district_name(name:&str, districts:&[Grouping], ignore_index:Option<usize>) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("District names can't be empty.".to_string());
+    }//end if name is empty
+    if name.eq_ignore_ascii_case("empty") {
+        return Err("\"empty\" is used for cells without a district, so it can't be a district name.".to_string());
+    }//end if name would look like an unclaimed cell
+    if name.chars().any(|c| c.is_control()) {
+        return Err("District names can't contain tabs or line breaks.".to_string());
+    }//end if name has control characters
+    if name.chars().count() > get_max_district_name_len() {
+        return Err(format!("District names can't be longer than {} characters.", get_max_district_name_len()));
+    }//end if name is too long
+    let taken = districts.iter().enumerate()
+        .any(|(index, district)| Some(index) != ignore_index && district.name.to_lowercase() == name.to_lowercase());
+    if taken {
+        return Err(format!("There's already a district named {}.", name));
+    }//end if another district has this name
+    return Ok(name.to_string());
+}//end validate_district_name(name, districts, ignore_index)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouping::two_district_city;
+
+    #[test]
+    fn reassign_moves_cell_between_districts() {
+        let (mut grid, mut districts) = two_district_city();
+        let coord = Coord::new(0, 0);
+        assert!(reassign_district_cell(&mut grid, &mut districts, coord, 1));
+        assert_eq!(grid.get(0, 0).unwrap().district_id, Some(2));
+        assert!(!districts[0].locations.contains(&coord));
+        assert_eq!(districts[1].locations.iter().filter(|location| **location == coord).count(), 1);
+        assert_eq!(districts[0].locations.len() + districts[1].locations.len(), 4);
+    }//end reassign_moves_cell_between_districts()
+
+    #[test]
+    fn reassign_to_same_district_does_nothing() {
+        let (mut grid, mut districts) = two_district_city();
+        let before = districts.clone();
+        assert!(!reassign_district_cell(&mut grid, &mut districts, Coord::new(1, 1), 1));
+        assert_eq!(districts, before);
+    }//end reassign_to_same_district_does_nothing()
+
+    #[test]
+    fn reassign_ignores_out_of_range_and_locked_cells() {
+        let (mut grid, mut districts) = two_district_city();
+        assert!(!reassign_district_cell(&mut grid, &mut districts, Coord::new(5, 0), 1));
+        assert!(!reassign_district_cell(&mut grid, &mut districts, Coord::new(0, 0), 2));
+        grid.get_mut(0, 0).unwrap().cell_locked = true;
+        assert!(!reassign_district_cell(&mut grid, &mut districts, Coord::new(0, 0), 1));
+        districts[0].locked = true;
+        assert!(!reassign_district_cell(&mut grid, &mut districts, Coord::new(1, 0), 1));
+        assert_eq!(grid.get(0, 0).unwrap().district_id, Some(1));
+        assert_eq!(grid.get(1, 0).unwrap().district_id, Some(1));
+    }//end reassign_ignores_out_of_range_and_locked_cells()
+
+    /// # named_districts(names)
+    /// 
+    /// Makes a district for each of names, with no locations
+    fn named_districts(names:&[&str]) -> Vec<Grouping> {
+        names.iter().map(|name| Grouping::new(name.to_string())).collect()
+    }//end named_districts(names)
+
+    /// # names(districts)
+    fn names(districts:&[Grouping]) -> Vec<&str> {
+        districts.iter().map(|district| district.name.as_str()).collect()
+    }//end names(districts)
+
+    #[test]
+    fn move_single_district() {
+        let mut districts = named_districts(&["a", "b", "c"]);
+        assert_eq!(move_districts(&mut districts, &[1], true), vec![0]);
+        assert_eq!(names(&districts), vec!["b", "a", "c"]);
+        assert_eq!(move_districts(&mut districts, &[0], false), vec![1]);
+        assert_eq!(names(&districts), vec!["a", "b", "c"]);
+    }//end move_single_district()
+
+    #[test]
+    fn move_stops_at_the_ends() {
+        let mut districts = named_districts(&["a", "b", "c"]);
+        assert_eq!(move_districts(&mut districts, &[0], true), vec![0]);
+        assert_eq!(move_districts(&mut districts, &[2], false), vec![2]);
+        assert_eq!(names(&districts), vec!["a", "b", "c"]);
+    }//end move_stops_at_the_ends()
+
+    #[test]
+    fn move_blocked_group_stays_together() {
+        let mut districts = named_districts(&["a", "b", "c", "d"]);
+        // a is stuck at the top, so b can't move past it either, but d can still move
+        assert_eq!(move_districts(&mut districts, &[0, 1, 3], true), vec![0, 1, 2]);
+        assert_eq!(names(&districts), vec!["a", "b", "d", "c"]);
+        assert_eq!(move_districts(&mut districts, &[1, 2], false), vec![2, 3]);
+        assert_eq!(names(&districts), vec!["a", "c", "b", "d"]);
+    }//end move_blocked_group_stays_together()
+
+    #[test]
+    fn move_ignores_bad_and_repeated_indices() {
+        let mut districts = named_districts(&["a", "b"]);
+        assert_eq!(move_districts(&mut districts, &[1, 1, 7], true), vec![0]);
+        assert_eq!(names(&districts), vec!["b", "a"]);
+    }//end move_ignores_bad_and_repeated_indices()
+
+    #[test]
+    fn valid_names_are_trimmed() {
+        let districts = named_districts(&["north"]);
+        assert_eq!(validate_district_name("  south side ", &districts, None), Ok("south side".to_string()));
+        assert_eq!(validate_district_name(&"a".repeat(get_max_district_name_len()), &districts, None).map(|name| name.len()), Ok(get_max_district_name_len()));
+    }//end valid_names_are_trimmed()
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        let districts = named_districts(&["north"]);
+        for name in ["", "   ", "empty", "EMPTY", "tab\there", "two\nlines", "North", " north "] {
+            assert!(validate_district_name(name, &districts, None).is_err(), "{:?} should be rejected", name);
+        }//end checking each bad name
+        assert!(validate_district_name(&"a".repeat(get_max_district_name_len() + 1), &districts, None).is_err());
+    }//end invalid_names_are_rejected()
+
+    #[test]
+    fn district_can_keep_its_own_name() {
+        let districts = named_districts(&["north", "south"]);
+        assert_eq!(validate_district_name("NORTH", &districts, Some(0)), Ok("NORTH".to_string()));
+        assert!(validate_district_name("south", &districts, Some(0)).is_err());
+    }//end district_can_keep_its_own_name()
+}//end mod tests
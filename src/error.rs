@@ -0,0 +1,115 @@
+use std::fmt::Display;
+
+/// # get_min_neighborhood_len()
+///
+/// The fewest rows or columns a neighborhood can have, since smaller neighborhoods don't have room for roads
+pub fn get_min_neighborhood_len() -> usize {3}
+
+/// # get_max_neighborhood_len()
+///
+/// The most rows or columns a neighborhood can have
+pub fn get_max_neighborhood_len() -> usize {256}
+
+/// # get_max_district_len()
+///
+/// The most rows or columns the district grid can have, so a typo or a broken save file can't ask for more memory than any computer has
+pub fn get_max_district_len() -> usize {1024}
+
+/// # CityGenError
+///
+/// The ways generating a city, or reading the settings or files it's generated from, can go wrong.
+/// Each error displays as a message meant to be shown to the user as is.
+#[derive(Debug)]
+pub enum CityGenError {
+    /// A row or column count was typed in that isn't a usable number.
+    InvalidDimensions {
+        /// what the dimension is for, such as "district rows"
+        what: &'static str,
+        /// exactly what was typed in
+        value: String,
+        /// the smallest number allowed
+        minimum: usize,
+        /// the largest number allowed
+        maximum: usize,
+    },
+    /// The city seed that was typed in isn't a usable number.
+    InvalidSeed(String),
+    /// There are more districts than cells in the district grid, so some couldn't be placed.
+    TooManyDistricts {
+        /// number of districts to place
+        num_districts: usize,
+        /// number of cells in the district grid
+        num_cells: usize,
+    },
+    /// There aren't any districts to generate a city from.
+    NoDistricts,
+    /// A save file or other configuration couldn't be understood.
+    BadConfig {
+        /// the line the problem was found on, starting from 1
+        line: usize,
+        /// what was wrong with that line
+        message: String,
+    },
+    /// A file couldn't be read or written.
+    Io(std::io::Error),
+}//end enum CityGenError
+
+impl Display for CityGenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CityGenError::InvalidDimensions { what, value, minimum, maximum } if value.trim().is_empty() => write!(f, "Please enter the number of {} ({} to {}).", what, minimum, maximum),
+            CityGenError::InvalidDimensions { what, value, minimum, maximum } => write!(f, "\"{}\" isn't a valid number of {}. Please enter a whole number from {} to {}.", value, what, minimum, maximum),
+            CityGenError::InvalidSeed(value) => write!(f, "\"{}\" isn't a valid city seed. Please enter a whole number that isn't negative, or leave it empty for a random city.", value),
+            CityGenError::TooManyDistricts { num_districts, num_cells } => write!(f, "There are {} districts, but the district grid only has room for {}. Please make the grid bigger or remove some districts.", num_districts, num_cells),
+            CityGenError::NoDistricts => write!(f, "There aren't any districts to generate. Please add at least one district first."),
+            CityGenError::BadConfig { line, message } => write!(f, "The file couldn't be read. Problem on line {}: {}", line, message),
+            CityGenError::Io(e) => write!(f, "{}", e),
+        }//end matching error kind
+    }//end fmt(&self, f)
+}//end Display impl for CityGenError
+
+impl std::error::Error for CityGenError {}
+
+impl From<std::io::Error> for CityGenError {
+    fn from(e: std::io::Error) -> Self {
+        CityGenError::Io(e)
+    }//end from(e)
+}//end From<std::io::Error> impl for CityGenError
+
+/// # parse_dimension(what, value, minimum, maximum)
+///
+/// Reads a row or column count typed in by the user, ignoring surrounding whitespace.
+///
+/// ## Return
+/// Returns the number, or an InvalidDimensions error if value isn't a whole number from minimum to maximum.
+pub fn parse_dimension(what:&'static str, value:&str, minimum:usize, maximum:usize) -> Result<usize, CityGenError> {
+    match value.trim().parse::<usize>() {
+        Ok(number) if (minimum..=maximum).contains(&number) => Ok(number),
+        _ => Err(CityGenError::InvalidDimensions { what, value: value.to_string(), minimum, maximum }),
+    }//end matching whether value is a usable number
+}//end parse_dimension(what, value, minimum, maximum)
+
+/// # validate_generation(num_districts, distr_dims, neigh_dims)
+///
+/// Checks that a city can be generated with num_districts districts in a grid of distr_dims, with neighborhoods of neigh_dims.
+/// Both dims are (rows, cols).
+pub fn validate_generation(num_districts:usize, distr_dims:(usize,usize), neigh_dims:(usize,usize)) -> Result<(), CityGenError> {
+    if num_districts == 0 {
+        return Err(CityGenError::NoDistricts);
+    }//end if there's nothing to generate
+    for (what, value, minimum, maximum) in [
+        ("district rows", distr_dims.0, 1, get_max_district_len()),
+        ("district columns", distr_dims.1, 1, get_max_district_len()),
+        ("neighborhood rows", neigh_dims.0, get_min_neighborhood_len(), get_max_neighborhood_len()),
+        ("neighborhood columns", neigh_dims.1, get_min_neighborhood_len(), get_max_neighborhood_len()),
+    ] {
+        if !(minimum..=maximum).contains(&value) {
+            return Err(CityGenError::InvalidDimensions { what, value: value.to_string(), minimum, maximum });
+        }//end if this dimension is too small or too big
+    }//end checking each dimension
+    let num_cells = distr_dims.0 * distr_dims.1;
+    if num_districts > num_cells {
+        return Err(CityGenError::TooManyDistricts { num_districts, num_cells });
+    }//end if not every district fits
+    return Ok(());
+}//end validate_generation(num_districts, distr_dims, neigh_dims)
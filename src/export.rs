@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use grid::Grid;
+
+use crate::generation::GrowthRecording;
+use crate::generation::LazyNeighborhoods;
+use crate::grouping::Building;
+use crate::grouping::Coord;
+use crate::grouping::DistrictLookup;
+use crate::grouping::GroupInstance;
+use crate::grouping::Grouping;
+use crate::palette::BuildingPalette;
+
+/// # get_border_color()
+///
+/// The color used to outline districts in exported images
+fn get_border_color() -> (u8,u8,u8) {(0,0,0)}
+
+/// # get_max_gif_frames()
+///
+/// The most frames an exported growth animation can have, so recordings with lots of steps don't make huge files.
+/// Several steps are put in each frame when there are more steps than this.
+fn get_max_gif_frames() -> usize {300}
+
+/// # get_max_png_pixels()
+///
+/// The most pixels an exported city image can have, since the whole image is built in memory before it's written.
+/// At three bytes a pixel, this keeps the image data under about 300MB.
+fn get_max_png_pixels() -> usize {100_000_000}
+
+/// # StitchedCity
+///
+/// Every neighborhood of a city placed side by side in one building-resolution grid.
+pub struct StitchedCity {
+	/// # buildings
+	///
+	/// every building in the city, where the neighborhood at district row r and column c starts at row r * nhood_rows and column c * nhood_cols
+	pub buildings: Grid<Building>,
+	/// # regions
+	///
+	/// for each building, the id of the district it belongs to, or 0 if it isn't in a district
+	pub regions: Grid<u32>,
+	/// # nhood_rows
+	///
+	/// the number of building rows in each neighborhood
+	pub nhood_rows: usize,
+	/// # nhood_cols
+	///
+	/// the number of building columns in each neighborhood
+	pub nhood_cols: usize,
+}//end struct StitchedCity
+
+/// # stitch_city(city_grid)
+///
+/// Copies the sub_grid of every GroupInstance into one big grid of buildings, keeping track of which district each building belongs to.
+/// Neighborhoods smaller than the largest neighborhood are padded with empty buildings.
+pub fn stitch_city(city_grid:&Grid<GroupInstance>) -> StitchedCity {
+	stitch_neighborhoods(city_grid, 0..city_grid.rows(), 0..city_grid.cols())
+}//end stitch_city(city_grid)
+
+/// # stitch_neighborhoods(city_grid, rows, cols)
+///
+/// Like stitch_city(), but only for the rectangle of neighborhoods in the specified rows and cols of city_grid.
+/// The neighborhood at rows.start and cols.start ends up in the top left corner. Any part of the rectangle outside city_grid is left out.
+pub fn stitch_neighborhoods(city_grid:&Grid<GroupInstance>, rows:Range<usize>, cols:Range<usize>) -> StitchedCity {
+	let rows = rows.start.min(city_grid.rows())..rows.end.min(city_grid.rows());
+	let cols = cols.start.min(city_grid.cols())..cols.end.min(city_grid.cols());
+	let (nhood_rows, nhood_cols) = largest_neighborhood(city_grid, rows.clone(), cols.clone());
+
+	let total_rows = rows.len() * nhood_rows;
+	let total_cols = cols.len() * nhood_cols;
+	let mut buildings: Grid<Building> = Grid::new(total_rows, total_cols);
+	let mut regions: Grid<u32> = Grid::new(total_rows, total_cols);
+
+	for (out_row, row) in rows.clone().enumerate() {
+		for (out_col, col) in cols.clone().enumerate() {
+			let instance = city_grid.get(row, col).unwrap();
+			// district ids start at 1, leaving 0 for no district
+			let region = instance.district_id.unwrap_or(0);
+
+			for sub_row in 0..nhood_rows {
+				for sub_col in 0..nhood_cols {
+					let total_row = out_row * nhood_rows + sub_row;
+					let total_col = out_col * nhood_cols + sub_col;
+					if let Some(building) = instance.sub_grid.get(sub_row, sub_col) {
+						*buildings.get_mut(total_row, total_col).unwrap() = building.clone();
+					}//end if this neighborhood has a building here
+					*regions.get_mut(total_row, total_col).unwrap() = region;
+				}//end looping over neighborhood columns
+			}//end looping over neighborhood rows
+		}//end looping over district columns
+	}//end looping over district rows
+
+	StitchedCity {
+		buildings,
+		regions,
+		nhood_rows,
+		nhood_cols,
+	}//end struct construction
+}//end stitch_neighborhoods(city_grid, rows, cols)
+
+/// # largest_neighborhood(city_grid, rows, cols)
+///
+/// Returns the rows and columns of the largest neighborhood in the specified rows and cols of city_grid, which is the space each neighborhood gets when they're placed side by side.
+/// Every neighborhood gets at least one row and column, even if none of them have been generated.
+pub fn largest_neighborhood(city_grid:&Grid<GroupInstance>, rows:Range<usize>, cols:Range<usize>) -> (usize, usize) {
+	let mut nhood_rows = 1;
+	let mut nhood_cols = 1;
+	for row in rows {
+		for col in cols.clone() {
+			if let Some(instance) = city_grid.get(row, col) {
+				nhood_rows = nhood_rows.max(instance.sub_grid.rows());
+				nhood_cols = nhood_cols.max(instance.sub_grid.cols());
+			}//end if this neighborhood is in the grid
+		}//end looping over district columns
+	}//end finding the largest neighborhood
+	(nhood_rows, nhood_cols)
+}//end largest_neighborhood(city_grid, rows, cols)
+
+/// # check_png_size(rows, cols, scale)
+///
+/// Makes sure an image of rows by cols buildings, with each building drawn as a square scale pixels wide, isn't too big to export.
+/// This is cheap, so it can be checked before stitching the city together.
+///
+/// ## Return
+/// Returns the (width, height) of the image in pixels, or an error if it has more than get_max_png_pixels() pixels.
+pub fn check_png_size(rows:usize, cols:usize, scale:usize) -> std::io::Result<(usize, usize)> {
+	let width = cols.checked_mul(scale.max(1));
+	let height = rows.checked_mul(scale.max(1));
+	match (width, height) {
+		(Some(width), Some(height)) if width.checked_mul(height).is_some_and(|pixels| pixels <= get_max_png_pixels()) => Ok((width, height)),
+		_ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the image would be too big to export, so try a smaller scale")),
+	}//end matching whether the image is small enough
+}//end check_png_size(rows, cols, scale)
+
+/// # export_city_png(path, stitched, scale, draw_borders)
+///
+/// Writes the stitched city to a png image at path, with each building drawn as a square scale pixels wide.
+/// If draw_borders is true, pixels along the edge between two districts are drawn in the border color.
+pub fn export_city_png(path:&Path, stitched:&StitchedCity, scale:usize, draw_borders:bool) -> std::io::Result<()> {
+	let scale = scale.max(1);
+	let rows = stitched.buildings.rows();
+	let cols = stitched.buildings.cols();
+	let (width, height) = check_png_size(rows, cols, scale)?;
+
+	// fill in the pixel data one row of pixels at a time
+	let mut data: Vec<u8> = Vec::with_capacity(width * height * 3);
+	for pixel_row in 0..height {
+		let row = pixel_row / scale;
+		let at_bottom_edge = pixel_row % scale == scale - 1;
+		for pixel_col in 0..width {
+			let col = pixel_col / scale;
+			let at_right_edge = pixel_col % scale == scale - 1;
+			let mut color = stitched.buildings.get(row, col).unwrap().rgb_color;
+			if draw_borders {
+				let region = stitched.regions.get(row, col).unwrap();
+				let border_right = at_right_edge && col + 1 < cols && stitched.regions.get(row, col + 1).unwrap() != region;
+				let border_below = at_bottom_edge && row + 1 < rows && stitched.regions.get(row + 1, col).unwrap() != region;
+				if border_right || border_below {
+					color = get_border_color();
+				}//end if this pixel is on a district border
+			}//end if we should draw borders
+			data.push(color.0);
+			data.push(color.1);
+			data.push(color.2);
+		}//end looping over pixel columns
+	}//end looping over pixel rows
+
+	// actually write the image
+	let file = File::create(path)?;
+	let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+	encoder.set_color(png::ColorType::Rgb);
+	encoder.set_depth(png::BitDepth::Eight);
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(&data)?;
+	return Ok(());
+}//end export_city_png(path, stitched, scale, draw_borders)
+
+/// # export_growth_gif(path, recording, districts, scale, steps_per_second)
+///
+/// Writes an animated gif to path showing the districts in recording growing step by step, with each district cell drawn as a square scale pixels wide.
+/// Districts are colored by looking up their ids in districts, and cells without a district are drawn in the border color.
+/// The animation plays at steps_per_second, putting several steps in each frame if there would be more than get_max_gif_frames() frames, and loops forever.
+pub fn export_growth_gif(path:&Path, recording:&GrowthRecording, districts:&[Grouping], scale:usize, steps_per_second:f64) -> std::io::Result<()> {
+	let scale = scale.max(1);
+	let width = recording.cols * scale;
+	let height = recording.rows * scale;
+	if width > u16::MAX as usize || height > u16::MAX as usize {
+		return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the animation would be too big for a gif, so try a smaller scale"));
+	}//end if the image is too big for the format
+
+	// gifs only have 256 colors, so any districts past that share the closest color already in the table
+	let mut color_table: Vec<(u8,u8,u8)> = vec![get_border_color()];
+	let mut color_indices: HashMap<u32, u8> = HashMap::with_capacity(districts.len());
+	for district in districts {
+		let index = match color_table.iter().position(|color| *color == district.rgb_color) {
+			Some(index) => index,
+			None if color_table.len() < 256 => {
+				color_table.push(district.rgb_color);
+				color_table.len() - 1
+			},
+			None => closest_color_index(&color_table, district.rgb_color),
+		};//end matching whether this color needs a spot in the table
+		color_indices.entry(district.id).or_insert(index as u8);
+	}//end finding a color for each district
+	let color_of = |district_id:u32| -> u8 {
+		color_indices.get(&district_id).copied().unwrap_or(0)
+	};
+
+	let steps_per_frame = recording.steps.len().div_ceil(get_max_gif_frames()).max(1);
+	// gif delays are in hundredths of a second, and most viewers don't handle delays shorter than 2 well
+	let delay = ((100.0 * steps_per_frame as f64 / steps_per_second.max(0.01)).round() as u16).max(2);
+
+	// the gif encoder reports its own errors, which are passed on as io errors like the png encoder's
+	let palette: Vec<u8> = color_table.iter().flat_map(|(r, g, b)| [*r, *g, *b]).collect();
+	let file = File::create(path)?;
+	let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &palette).map_err(std::io::Error::other)?;
+	encoder.set_repeat(gif::Repeat::Infinite).map_err(std::io::Error::other)?;
+
+	let mut frame = recording.start.clone();
+	let mut num_steps_shown = 0;
+	loop {
+		let mut pixels: Vec<u8> = Vec::with_capacity(width * height);
+		for pixel_row in 0..height {
+			let row = pixel_row / scale;
+			for pixel_col in 0..width {
+				pixels.push(color_of(frame[row * recording.cols + pixel_col / scale]));
+			}//end looping over pixel columns
+		}//end looping over pixel rows
+		// the last frame stays up a while before the animation starts over
+		let is_last = num_steps_shown >= recording.steps.len();
+		let mut gif_frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+		gif_frame.delay = if is_last {delay.max(200)} else {delay};
+		encoder.write_frame(&gif_frame).map_err(std::io::Error::other)?;
+		if is_last {break;}
+
+		for _ in 0..steps_per_frame {
+			recording.apply_step(&mut frame, num_steps_shown);
+			num_steps_shown += 1;
+		}//end applying the steps for the next frame
+	}//end writing each frame
+	encoder.into_inner()?.flush()?;
+	return Ok(());
+}//end export_growth_gif(path, recording, districts, scale, steps_per_second)
+
+/// # closest_color_index(colors, color)
+///
+/// returns the index of whichever of colors is closest to color
+fn closest_color_index(colors:&[(u8,u8,u8)], color:(u8,u8,u8)) -> usize {
+	let distance = |other:&(u8,u8,u8)| {
+		let (dr, dg, db) = (other.0 as i32 - color.0 as i32, other.1 as i32 - color.1 as i32, other.2 as i32 - color.2 as i32);
+		dr * dr + dg * dg + db * db
+	};
+	colors.iter().enumerate().min_by_key(|(_, other)| distance(other)).map(|(index, _)| index).unwrap_or(0)
+}//end closest_color_index(colors, color)
+
+/// # export_city_csv(path, city_grid, districts, lazy_nhoods)
+///
+/// Writes every building in city_grid to a csv file at path, one building per line.
+/// If lazy_nhoods is given, neighborhoods which haven't been generated yet are generated one at a time just to be written out, so they don't all have to be kept in memory, and city_grid is left as it was.
+/// Each line has the row and column of the district cell, the id and name of its district, the row and column of the building inside the neighborhood, and the building type, name and notes.
+/// Cells without a district have an id of 0 and an empty name, and district names are looked up in districts.
+pub fn export_city_csv(path:&Path, city_grid:&Grid<GroupInstance>, districts:&[Grouping], lazy_nhoods:Option<(LazyNeighborhoods, &BuildingPalette)>) -> std::io::Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+	// holds each neighborhood that's only generated for writing it out
+	let mut scratch = GroupInstance::default();
+	let lookup = DistrictLookup::new(districts);
+	writeln!(writer, "district_row,district_col,district_id,district_name,building_row,building_col,building_type,building_name,building_notes")?;
+	for row in 0..city_grid.rows() {
+		for col in 0..city_grid.cols() {
+			let mut instance = city_grid.get(row, col).unwrap();
+			if let (false, Some((lazy, palette))) = (instance.has_neighborhood(), lazy_nhoods) {
+				scratch.clone_from(instance);
+				lazy.generate_into(&mut scratch, Coord::new(row, col), palette);
+				instance = &scratch;
+			}//end if this neighborhood needs generating first
+			let district_id = instance.district_id.unwrap_or(0);
+			let district_name = match lookup.district_of(instance) {
+				Some(district) => csv_field(&district.name),
+				None => String::new(),
+			};//end matching whether this instance has a district
+			for sub_row in 0..instance.sub_grid.rows() {
+				for sub_col in 0..instance.sub_grid.cols() {
+					let building = instance.sub_grid.get(sub_row, sub_col).unwrap();
+					writeln!(writer, "{},{},{},{},{},{},{},{},{}", row, col, district_id, district_name, sub_row, sub_col, building.build_type, csv_field(building.name()), csv_field(building.notes()))?;
+				}//end looping over neighborhood columns
+			}//end looping over neighborhood rows
+		}//end looping over district columns
+	}//end looping over district rows
+	writer.flush()?;
+	return Ok(());
+}//end export_city_csv(path, city_grid, districts, lazy_nhoods)
+
+/// # csv_field(text)
+///
+/// quotes text for a csv file if it has any commas, quotes or line breaks in it
+fn csv_field(text:&str) -> String {
+	if text.contains(',') || text.contains('"') || text.contains('\n') {
+		format!("\"{}\"", text.replace('"', "\"\""))
+	}//end if text needs quoting
+	else { text.to_string() }
+}//end csv_field(text)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::generation::GrowthRecording;
+	use crate::generation::create_empty_grid;
+
+	/// # temp_path(name)
+	///
+	/// returns a path in the temp directory for a test file, which won't clash with other tests running at the same time
+	fn temp_path(name:&str) -> std::path::PathBuf {
+		std::env::temp_dir().join(format!("city-generator-test-{}-{}.csv", std::process::id(), name))
+	}//end temp_path(name)
+
+	#[test]
+	fn lazy_export_matches_generating_first() {
+		let districts = vec![Grouping::new("west".to_string()).with_id(1), Grouping::new("east".to_string()).with_id(2)];
+		let mut grid = create_empty_grid(3, 4);
+		for row in 0..3 {
+			for col in 0..4 {
+				*grid.get_mut(row, col).unwrap() = GroupInstance::new(if col < 2 {1} else {2}, Coord::new(row, col));
+			}//end looping over columns
+		}//end looping over rows
+		let palette = BuildingPalette::default_preset();
+		let lazy = LazyNeighborhoods { seed: 99, dims: (3, 2) };
+		// generate one neighborhood ahead of time, like one that's been viewed
+		lazy.materialize(&mut grid, Coord::new(1, 2), &palette);
+		let before = grid.clone();
+
+		let lazy_path = temp_path("lazy-export");
+		export_city_csv(&lazy_path, &grid, &districts, Some((lazy, &palette))).unwrap();
+		assert_eq!(grid, before);
+
+		lazy.materialize_all(&mut grid, &palette);
+		let eager_path = temp_path("eager-export");
+		export_city_csv(&eager_path, &grid, &districts, None).unwrap();
+		let lazy_csv = std::fs::read_to_string(&lazy_path).unwrap();
+		let eager_csv = std::fs::read_to_string(&eager_path).unwrap();
+		let _ = std::fs::remove_file(&lazy_path);
+		let _ = std::fs::remove_file(&eager_path);
+		assert_eq!(lazy_csv, eager_csv);
+		assert_eq!(eager_csv.lines().count(), 1 + 3 * 4 * 3 * 2);
+	}//end lazy_export_matches_generating_first()
+
+	#[test]
+	fn huge_images_are_refused_up_front() {
+		let mut grid = create_empty_grid(2, 2);
+		for instance in grid.iter_mut() {
+			instance.initialize_sub_grid(3, 3);
+		}//end giving each cell a neighborhood
+		let stitched = stitch_city(&grid);
+		let path = temp_path("huge").with_extension("png");
+		let error = export_city_png(&path, &stitched, 100_000, true).unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+		assert!(!path.exists());
+		assert!(check_png_size(usize::MAX, 2, 2).is_err());
+		assert_eq!(check_png_size(6, 6, 4).unwrap(), (24, 24));
+	}//end huge_images_are_refused_up_front()
+
+	#[test]
+	fn growth_gif_decodes_to_the_recording() {
+		let districts = vec![
+			Grouping::new("north".to_string()).with_id(1).with_color((200,30,30)),
+			Grouping::new("south".to_string()).with_id(2).with_color((20,40,220)),
+		];
+		let recording = GrowthRecording {
+			rows: 2,
+			cols: 3,
+			start: vec![0, 0, 0, 0, 0, 2],
+			steps: vec![
+				vec![(Coord::new(0, 0), 1)],
+				vec![(Coord::new(0, 1), 1), (Coord::new(1, 1), 2)],
+				vec![(Coord::new(0, 2), 1), (Coord::new(1, 0), 2)],
+			],
+		};
+		let scale = 2;
+		let path = temp_path("growth").with_extension("gif");
+		export_growth_gif(&path, &recording, &districts, scale, 10.0).unwrap();
+
+		let mut options = gif::DecodeOptions::new();
+		options.set_color_output(gif::ColorOutput::RGBA);
+		let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+		assert_eq!((decoder.width(), decoder.height()), (6, 4));
+		assert_eq!(decoder.repeat(), gif::Repeat::Infinite);
+		let color_of = |district_id:u32| match districts.iter().find(|district| district.id == district_id) {
+			Some(district) => district.rgb_color,
+			None => get_border_color(),
+		};
+		let mut expected = recording.start.clone();
+		let mut num_frames = 0;
+		while let Some(frame) = decoder.read_next_frame().unwrap() {
+			for (index, pixel) in frame.buffer.chunks(4).enumerate() {
+				let (row, col) = (index / 6 / scale, index % 6 / scale);
+				let (r, g, b) = color_of(expected[row * 3 + col]);
+				assert_eq!(pixel, [r, g, b, 255], "pixel {} of frame {}", index, num_frames);
+			}//end checking each pixel
+			assert_eq!(frame.delay, if num_frames == 3 {200} else {10});
+			recording.apply_step(&mut expected, num_frames);
+			num_frames += 1;
+		}//end checking each frame
+		let _ = std::fs::remove_file(&path);
+		assert_eq!(num_frames, 4);
+	}//end growth_gif_decodes_to_the_recording()
+}//end mod tests
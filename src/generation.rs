@@ -0,0 +1,1034 @@
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use fltk::app;
+use grid::Grid;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::error::CityGenError;
+use crate::error::validate_generation;
+use crate::grouping::BuildingType;
+use crate::grouping::Coord;
+use crate::grouping::DistrictLookup;
+use crate::grouping::GroupInstance;
+use crate::grouping::Grouping;
+use crate::grouping::district_indices;
+use crate::palette::BuildingPalette;
+use crate::palette::get_road_color;
+
+/// # get_progress_interval()
+/// 
+/// The minimum time between progress reports sent from the generation thread, so the gui doesn't get flooded
+fn get_progress_interval() -> Duration {Duration::from_millis(50)}
+
+/// # get_neighborhood_seed(city_seed, row, col)
+/// 
+/// Mixes the city seed with the position of a neighborhood to get the seed for that neighborhood's rng, using the splitmix64 finalizer.
+/// Every neighborhood gets its own stream of random numbers this way, so it doesn't matter which thread generates it or in what order.
+fn get_neighborhood_seed(city_seed:u64, row:usize, col:usize) -> u64 {
+    let mut z = city_seed
+        .wrapping_add((row as u64).wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add((col as u64).wrapping_mul(0xC2B2AE3D27D4EB4F));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return z ^ (z >> 31);
+}//end get_neighborhood_seed(city_seed, row, col)
+
+/// # GenProgress
+/// 
+/// Messages sent from the generation thread back to the gui
+pub enum GenProgress {
+    /// The district grid is being filled in.
+    Districts {
+        /// number of districts which can't expand any further
+        num_enclosed: usize,
+        /// total number of districts
+        num_districts: usize,
+        /// number of district cells which have been claimed
+        cells_claimed: usize,
+        /// total number of district cells
+        cells_total: usize,
+        /// number of times every district has been given a chance to expand
+        iterations: usize,
+    },
+    /// The neighborhoods inside each district cell are being generated.
+    Neighborhoods {
+        /// number of neighborhoods finished so far
+        done: usize,
+        /// total number of neighborhoods
+        total: usize,
+    },
+    /// Generation finished, carrying the new city grid, the districts with their locations filled in, and a recording of how the districts grew
+    Finished(Grid<GroupInstance>, Vec<Grouping>, GrowthRecording),
+    /// Generation was cancelled before it finished
+    Cancelled,
+}//end enum GenProgress
+
+impl GenProgress {
+    /// # fraction(&self)
+    /// 
+    /// Returns how far along generation is, from 0.0 to 1.0.
+    /// District expansion counts as the first half and neighborhoods as the second half.
+    pub fn fraction(&self) -> f64 {
+        match self {
+            GenProgress::Districts { cells_claimed, cells_total, .. } => 0.5 * *cells_claimed as f64 / (*cells_total).max(1) as f64,
+            GenProgress::Neighborhoods { done, total } => 0.5 + 0.5 * *done as f64 / (*total).max(1) as f64,
+            GenProgress::Finished(..) => 1.0,
+            GenProgress::Cancelled => 0.0,
+        }//end matching progress stage
+    }//end fraction(&self)
+
+    /// # description(&self)
+    /// 
+    /// Returns a short description of the progress, suitable for showing to the user.
+    pub fn description(&self) -> String {
+        match self {
+            GenProgress::Districts { num_enclosed, num_districts, iterations, .. } => format!("Expanding districts: {}/{} enclosed, iteration {}", num_enclosed, num_districts, iterations),
+            GenProgress::Neighborhoods { done, total } => format!("Generating neighborhoods: {}/{}", done, total),
+            GenProgress::Finished(..) => "Finished generating".to_string(),
+            GenProgress::Cancelled => "Generation cancelled".to_string(),
+        }//end matching progress stage
+    }//end description(&self)
+}//end impl for GenProgress
+
+/// # GrowthRecording
+/// 
+/// Every step taken while districts grew to fill the district grid, so the growth can be played back or exported afterwards.
+/// Cells are stored as district ids, with 0 for cells that don't belong to a district.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrowthRecording {
+    /// rows in the district grid
+    pub rows: usize,
+    /// columns in the district grid
+    pub cols: usize,
+    /// the district id of each cell before any districts were placed, in row major order
+    pub start: Vec<u32>,
+    /// for each step, the cells claimed during that step and the id of the district that claimed them
+    pub steps: Vec<Vec<(Coord, u32)>>,
+}//end struct GrowthRecording
+
+impl GrowthRecording {
+    /// # new(grid)
+    /// 
+    /// Starts a recording with no steps from the districts already in grid, such as locked cells.
+    pub fn new(grid:&Grid<GroupInstance>) -> GrowthRecording {
+        GrowthRecording {
+            rows: grid.rows(),
+            cols: grid.cols(),
+            start: grid.iter().map(|instance| instance.district_id.unwrap_or(0)).collect(),
+            steps: Vec::new(),
+        }//end struct construction
+    }//end new(grid)
+
+    /// # record_changes(&mut self, grid)
+    /// 
+    /// Adds a step holding every cell of grid whose district is different from how the recording left it, if there are any.
+    pub fn record_changes(&mut self, grid:&Grid<GroupInstance>) {
+        let frame = self.frame_at(self.steps.len());
+        let changes: Vec<(Coord, u32)> = grid.iter().zip(frame).enumerate()
+            .filter(|(_, (instance, recorded))| instance.district_id.unwrap_or(0) != *recorded)
+            .map(|(index, (instance, _))| (Coord::new(index / self.cols, index % self.cols), instance.district_id.unwrap_or(0)))
+            .collect();
+        if !changes.is_empty() {self.steps.push(changes);}
+    }//end record_changes(&mut self, grid)
+
+    /// # apply_step(&self, frame, step)
+    /// 
+    /// Changes frame, holding the district id of each cell in row major order, to how it looked after the step at index step.
+    /// Does nothing if there's no such step.
+    pub fn apply_step(&self, frame:&mut [u32], step:usize) {
+        if let Some(changes) = self.steps.get(step) {
+            for (coord, district_id) in changes {
+                frame[coord.row * self.cols + coord.col] = *district_id;
+            }//end applying each change
+        }//end if there's such a step
+    }//end apply_step(&self, frame, step)
+
+    /// # frame_at(&self, num_steps)
+    /// 
+    /// returns the district id of each cell in row major order after the first num_steps steps
+    pub fn frame_at(&self, num_steps:usize) -> Vec<u32> {
+        let mut frame = self.start.clone();
+        for step in 0..num_steps.min(self.steps.len()) {
+            self.apply_step(&mut frame, step);
+        }//end applying each step
+        return frame;
+    }//end frame_at(&self, num_steps)
+}//end impl for GrowthRecording
+
+/// # GenerationJob
+/// 
+/// A handle to city generation running on a background thread.
+/// Progress reports can be read from receiver, and the job can be stopped early with cancel().
+pub struct GenerationJob {
+    /// receives progress reports from the generation thread
+    pub receiver: Receiver<GenProgress>,
+    /// the seed the city is being generated from, so the same city can be generated again
+    pub seed: u64,
+    /// how to generate the neighborhoods left for later, if the job was asked to leave them
+    pub lazy_neighborhoods: Option<LazyNeighborhoods>,
+    /// set to true to ask the generation thread to stop
+    cancel_flag: Arc<AtomicBool>,
+}//end struct GenerationJob
+
+impl GenerationJob {
+    /// # spawn(districts, distr_dims, neigh_dims, palette, seed, lazy)
+    /// 
+    /// Starts generating a city on a new thread, with districts as the groupings to place.
+    /// distr_dims and neigh_dims are the (rows, cols) of the district grid and of each neighborhood.
+    /// The same seed, districts and dims always give the same city.
+    /// If lazy is true, neighborhoods aren't generated at all, and lazy_neighborhoods can generate each one when it's first needed.
+    /// The locations of districts are cleared before generating.
+    /// 
+    /// ## Return
+    /// Returns an error without starting anything if there aren't any districts, the dims are too small, or the districts don't all fit.
+    pub fn spawn(mut districts:Vec<Grouping>, distr_dims:(usize,usize), neigh_dims:(usize,usize), palette:BuildingPalette, seed:u64, lazy:bool) -> Result<GenerationJob, CityGenError> {
+        validate_generation(districts.len(), distr_dims, neigh_dims)?;
+        for district in districts.iter_mut() {
+            district.locations.clear();
+        }//end clearing each district's locations
+        let city_grid = create_empty_grid(distr_dims.0, distr_dims.1);
+        return Ok(GenerationJob::spawn_from(city_grid, districts, neigh_dims, palette, seed, lazy));
+    }//end spawn(districts, distr_dims, neigh_dims, palette, seed, lazy)
+
+    /// # spawn_partial(city_grid, districts, neigh_dims, palette, seed, lazy)
+    /// 
+    /// Starts regenerating the unlocked parts of city_grid on a new thread.
+    /// Locked cells and the cells of locked districts keep their district and their whole neighborhood, locked neighborhoods keep all their buildings, and locked buildings are kept inside regenerated neighborhoods.
+    /// Everything else is thrown away and generated again, using neigh_dims for the size of regenerated neighborhoods.
+    /// If lazy is true, only neighborhoods which were already generated get regenerated, and the rest are left for later.
+    /// 
+    /// ## Return
+    /// Returns an error without starting anything for the same reasons as spawn().
+    pub fn spawn_partial(mut city_grid:Grid<GroupInstance>, mut districts:Vec<Grouping>, neigh_dims:(usize,usize), palette:BuildingPalette, seed:u64, lazy:bool) -> Result<GenerationJob, CityGenError> {
+        validate_generation(districts.len(), (city_grid.rows(), city_grid.cols()), neigh_dims)?;
+        strip_unlocked_cells(&mut city_grid, &mut districts);
+        return Ok(GenerationJob::spawn_from(city_grid, districts, neigh_dims, palette, seed, lazy));
+    }//end spawn_partial(city_grid, districts, neigh_dims, palette, seed, lazy)
+
+    /// # spawn_from(city_grid, districts, neigh_dims, palette, seed, lazy)
+    /// 
+    /// Starts generating on a new thread, filling in every unclaimed cell of city_grid and regenerating every unlocked neighborhood, or only the ones already generated if lazy is true.
+    /// Neighborhoods in locked cells or locked districts count as locked.
+    fn spawn_from(city_grid:Grid<GroupInstance>, mut districts:Vec<Grouping>, neigh_dims:(usize,usize), palette:BuildingPalette, seed:u64, lazy:bool) -> GenerationJob {
+        let (sender, receiver) = mpsc::channel();
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let thread_cancel_flag = cancel_flag.clone();
+        let locked_ids: HashSet<u32> = districts.iter().filter(|district| district.locked).map(|district| district.id).collect();
+        thread::spawn(move || {
+            // generate all neighborhoods, or just the ones that already exist if the rest are being left for later
+            let should_generate = |instance:&GroupInstance| !keeps_neighborhood(instance, &locked_ids) && (!lazy || instance.has_neighborhood());
+            let result = generate_city(city_grid, &mut districts, seed, &sender, &thread_cancel_flag)
+                .and_then(|(mut city_grid, recording)| {
+                    if generate_neighborhoods(&mut city_grid, neigh_dims, &palette, seed, &should_generate, get_num_threads(), Some(&sender), &thread_cancel_flag) {Some((city_grid, recording))}
+                    else {None}
+                });
+            let final_msg = match result {
+                Some((city_grid, recording)) => GenProgress::Finished(city_grid, districts, recording),
+                None => GenProgress::Cancelled,
+            };//end matching whether we finished
+            // the gui might have gone away, in which case there's nobody to tell
+            let _ = sender.send(final_msg);
+            app::awake();
+        });
+        GenerationJob {
+            receiver,
+            seed,
+            lazy_neighborhoods: if lazy {Some(LazyNeighborhoods { seed, dims: neigh_dims })} else {None},
+            cancel_flag,
+        }//end struct construction
+    }//end spawn_from(city_grid, districts, neigh_dims, palette, seed, lazy)
+
+    /// # cancel(&self)
+    /// 
+    /// Asks the generation thread to stop. A GenProgress::Cancelled message will be sent once it has.
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+    }//end cancel(&self)
+}//end impl for GenerationJob
+
+/// # generate_city(city_grid, districts, seed, sender, cancel_flag)
+/// 
+/// Generates the districts of a city starting from city_grid, placing districts in every unclaimed cell.
+/// Neighborhoods are left alone, for generate_neighborhoods() to fill in afterwards.
+/// Districts which already have locations expand from there, and districts without any get placed in a random spot first.
+/// All the randomness comes from seed, so the same seed and starting grid always give the same city.
+/// Progress is reported through sender, at most once every get_progress_interval().
+/// 
+/// ## Return
+/// Returns the new city grid and a recording of every step the districts took to fill it, or None if cancel_flag was set before generation finished.
+fn generate_city(mut city_grid:Grid<GroupInstance>, districts:&mut Vec<Grouping>, seed:u64, sender:&Sender<GenProgress>, cancel_flag:&AtomicBool) -> Option<(Grid<GroupInstance>, GrowthRecording)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let cells_total = city_grid.rows() * city_grid.cols();
+    let mut last_report = Instant::now();
+    let mut recording = GrowthRecording::new(&city_grid);
+
+    // add group starts in random spots, which counts as the first step of the recording
+    prime_grid_with_groups(&mut city_grid, districts, &mut rng);
+    recording.record_changes(&city_grid);
+    let mut cells_claimed = city_grid.iter().filter(|instance| instance.district_id.is_some()).count();
+
+    // advance groups until enclosed
+    let mut expansion = DistrictExpansion::new(&city_grid, districts);
+    let mut num_iterations: usize = 0;
+    loop {
+        if cancel_flag.load(Ordering::Relaxed) {return None;}
+        let mut claimed = Vec::new();
+        let num_enclosed = expansion.advance(&mut city_grid, districts, &mut rng, &mut claimed);
+        cells_claimed += claimed.len();
+        if !claimed.is_empty() {recording.steps.push(claimed);}
+        num_iterations += 1;
+        let all_enclosed = num_enclosed.eq(&districts.len());
+        if all_enclosed || last_report.elapsed() >= get_progress_interval() {
+            send_progress(sender, GenProgress::Districts {
+                num_enclosed,
+                num_districts: districts.len(),
+                cells_claimed,
+                cells_total,
+                iterations: num_iterations,
+            });
+            last_report = Instant::now();
+        }//end if we should report progress
+        if all_enclosed {break;}
+    }//end looping while some groupings are still able to expand
+
+    return Some((city_grid, recording));
+}//end generate_city(city_grid, districts, seed, sender, cancel_flag)
+
+/// # get_num_threads()
+/// 
+/// The number of threads to generate neighborhoods with, which is one per cpu core
+fn get_num_threads() -> usize {
+    thread::available_parallelism().map(|num| num.get()).unwrap_or(1)
+}//end get_num_threads()
+
+/// # generate_neighborhoods(city_grid, neigh_dims, palette, seed, should_generate, num_threads, sender, cancel_flag)
+/// 
+/// Regenerates every neighborhood in city_grid that should_generate returns true for, splitting the cells up between num_threads threads.
+/// Each neighborhood gets its own rng from get_neighborhood_seed(), so the results don't depend on how many threads there are.
+/// Progress is reported through sender, if there is one, every get_progress_interval() while the threads work.
+/// Without a sender, this thread just waits for the others to finish instead of waking up to check on them.
+/// 
+/// ## Return
+/// Returns false if cancel_flag was set before every neighborhood was finished.
+#[allow(clippy::too_many_arguments)]
+fn generate_neighborhoods(city_grid:&mut Grid<GroupInstance>, neigh_dims:(usize,usize), palette:&BuildingPalette, seed:u64, should_generate:&(dyn Fn(&GroupInstance) -> bool + Sync), num_threads:usize, sender:Option<&Sender<GenProgress>>, cancel_flag:&AtomicBool) -> bool {
+    let cols = city_grid.cols();
+    let total = city_grid.rows() * cols;
+    let chunk_len = total.div_ceil(num_threads.max(1)).max(1);
+    let done = AtomicUsize::new(0);
+    let mut cells: Vec<(usize, &mut GroupInstance)> = city_grid.iter_mut().enumerate().collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = cells.chunks_mut(chunk_len).map(|chunk| {
+            let done = &done;
+            scope.spawn(move || {
+                for (index, instance) in chunk.iter_mut() {
+                    if cancel_flag.load(Ordering::Relaxed) {return;}
+                    if should_generate(instance) {
+                        let mut rng = StdRng::seed_from_u64(get_neighborhood_seed(seed, *index / cols, *index % cols));
+                        regenerate_neighborhood(instance, &mut rng, palette, neigh_dims.0, neigh_dims.1);
+                    }//end if we're supposed to change this neighborhood
+                    done.fetch_add(1, Ordering::Relaxed);
+                }//end generating each neighborhood in this chunk
+            })
+        }).collect();
+        // report progress from this thread while the workers run
+        if let Some(sender) = sender {
+            while !workers.iter().all(|worker| worker.is_finished()) {
+                thread::sleep(get_progress_interval());
+                send_progress(sender, GenProgress::Neighborhoods { done: done.load(Ordering::Relaxed), total });
+            }//end waiting for every worker to finish
+        }//end if anyone wants to know
+        // otherwise the end of the scope just waits for the workers to finish
+    });
+    if cancel_flag.load(Ordering::Relaxed) {return false;}
+    if let Some(sender) = sender {
+        send_progress(sender, GenProgress::Neighborhoods { done: total, total });
+    }//end if anyone wants to know
+    return true;
+}//end generate_neighborhoods(city_grid, neigh_dims, palette, seed, should_generate, num_threads, sender, cancel_flag)
+
+/// # LazyNeighborhoods
+/// 
+/// Remembers how to generate the neighborhoods of a city which were left for later, so that each one can be generated the first time it's viewed or exported.
+/// Neighborhoods come out exactly the same as they would have if they were generated along with the rest of the city.
+/// The city keeps track of which ones have been generated already, since only those have a sub_grid (see GroupInstance::has_neighborhood()).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LazyNeighborhoods {
+    /// the seed the city was generated from
+    pub seed: u64,
+    /// the (rows, cols) of each neighborhood
+    pub dims: (usize, usize),
+}//end struct LazyNeighborhoods
+
+impl LazyNeighborhoods {
+    /// # materialize(&self, grid, coord, palette)
+    /// 
+    /// Generates the neighborhood at coord in grid, unless it's already been generated.
+    /// 
+    /// ## Return
+    /// Returns true if the neighborhood was generated just now.
+    pub fn materialize(&self, grid:&mut Grid<GroupInstance>, coord:Coord, palette:&BuildingPalette) -> bool {
+        match grid.get_mut(coord.row, coord.col) {
+            Some(nhood) if !nhood.has_neighborhood() => {
+                self.generate_into(nhood, coord, palette);
+                true
+            },
+            _ => false,
+        }//end matching whether there's a neighborhood to generate
+    }//end materialize(&self, grid, coord, palette)
+
+    /// # generate_into(&self, nhood, coord, palette)
+    /// 
+    /// Generates the neighborhood that belongs in the cell at coord into nhood, replacing any buildings already there.
+    /// This is useful for looking at a neighborhood without keeping it, such as when exporting.
+    pub fn generate_into(&self, nhood:&mut GroupInstance, coord:Coord, palette:&BuildingPalette) {
+        let mut rng = StdRng::seed_from_u64(get_neighborhood_seed(self.seed, coord.row, coord.col));
+        regenerate_neighborhood(nhood, &mut rng, palette, self.dims.0, self.dims.1);
+    }//end generate_into(&self, nhood, coord, palette)
+
+    /// # materialize_all(&self, grid, palette)
+    /// 
+    /// Generates every neighborhood in grid that hasn't been generated yet, such as before exporting the whole city.
+    /// 
+    /// ## Return
+    /// Returns the number of neighborhoods generated just now.
+    pub fn materialize_all(&self, grid:&mut Grid<GroupInstance>, palette:&BuildingPalette) -> usize {
+        let num_missing = grid.iter().filter(|instance| !instance.has_neighborhood()).count();
+        if num_missing > 0 {
+            let never_cancelled = AtomicBool::new(false);
+            generate_neighborhoods(grid, self.dims, palette, self.seed, &|instance:&GroupInstance| !instance.has_neighborhood(), get_num_threads(), None, &never_cancelled);
+        }//end if there's anything to generate
+        return num_missing;
+    }//end materialize_all(&self, grid, palette)
+}//end impl for LazyNeighborhoods
+
+/// # keeps_neighborhood(instance, locked_ids)
+/// 
+/// Returns true if the neighborhood in instance should be left alone when regenerating, because it's locked itself, or because its cell or its district is locked.
+/// locked_ids holds the ids of the locked districts.
+/// Locked cells without a neighborhood yet still get one.
+fn keeps_neighborhood(instance:&GroupInstance, locked_ids:&HashSet<u32>) -> bool {
+    let cell_locked = instance.cell_locked || instance.district_id.is_some_and(|id| locked_ids.contains(&id));
+    instance.nhood_locked || (cell_locked && instance.has_neighborhood())
+}//end keeps_neighborhood(instance, locked_ids)
+
+/// # strip_unlocked_cells(grid, districts)
+/// 
+/// Unclaims every cell of grid which isn't locked, either by itself or by its district, so that it can be claimed again by generation.
+/// The locations of districts are rebuilt to only hold the locked cells. Neighborhoods are left in place, so locked cells and buildings keep them.
+/// Unclaimed cells lose their neighborhood lock as well, since the neighborhood belonged to a district the cell may not end up in, but locked buildings in them are still carried over.
+/// Cells whose district is no longer in districts are unclaimed even if they were locked.
+pub fn strip_unlocked_cells(grid:&mut Grid<GroupInstance>, districts:&mut [Grouping]) {
+    for district in districts.iter_mut() {
+        district.locations.clear();
+    }//end clearing each district's locations
+
+    let indices = district_indices(districts);
+    for row in 0..grid.rows() {
+        for col in 0..grid.cols() {
+            let instance = grid.get_mut(row, col).expect("valid index");
+            match instance.district_id.and_then(|id| indices.get(&id).copied()) {
+                Some(index) if instance.cell_locked || districts[index].locked => {
+                    let coord = Coord::new(row, col);
+                    districts[index].locations.push(coord);
+                    instance.coord = Some(coord);
+                },
+                _ => {
+                    instance.district_id = None;
+                    instance.coord = None;
+                    instance.cell_locked = false;
+                    instance.nhood_locked = false;
+                },
+            }//end matching whether this cell keeps its district
+        }//end looping over columns
+    }//end looping over rows
+}//end strip_unlocked_cells(grid, districts)
+
+/// # send_progress(sender, progress)
+/// 
+/// Sends a progress report and wakes up the gui thread so it gets handled.
+fn send_progress(sender:&Sender<GenProgress>, progress:GenProgress) {
+    // if the gui stopped listening, generation just keeps going quietly
+    let _ = sender.send(progress);
+    app::awake();
+}//end send_progress(sender, progress)
+
+/// # DistrictExpansion
+/// 
+/// Keeps track of which district owns each cell while districts expand, along with the frontier of each district.
+/// A district's frontier is the unclaimed cells next to it, each with the weight used to pick it.
+/// Frontiers are updated a cell at a time as cells get claimed, instead of being rebuilt from every location each round, so big grids don't slow to a crawl.
+/// Cells claimed by another district are only dropped from a frontier when they're picked, so each pick only takes O(log n) time.
+pub struct DistrictExpansion {
+    /// number of columns in the grid, for turning coords into indices
+    cols: usize,
+    /// number of rows in the grid
+    rows: usize,
+    /// the index of the district which owns each cell, in row major order.
+    /// Cells owned by a district that isn't being expanded hold the number of districts instead.
+    owners: Vec<Option<usize>>,
+    /// for each district, the cells it might be able to claim next, along with their weights
+    frontiers: Vec<Frontier>,
+    /// for each district, the indices of every cell ever added to its frontier, so cells aren't added twice
+    in_frontier: Vec<HashSet<usize>>,
+}//end struct DistrictExpansion
+
+impl DistrictExpansion {
+    /// # new(grid, groups)
+    /// 
+    /// Builds the ownership index from the claimed cells of grid, and the frontier of each group from its locations.
+    /// Cells claimed by a district that isn't in groups stay claimed, so nothing expands into them.
+    pub fn new(grid:&Grid<GroupInstance>, groups:&[Grouping]) -> DistrictExpansion {
+        let mut expansion = DistrictExpansion {
+            cols: grid.cols(),
+            rows: grid.rows(),
+            owners: vec![None; grid.rows() * grid.cols()],
+            frontiers: (0..groups.len()).map(|_| Frontier::default()).collect(),
+            in_frontier: vec![HashSet::new(); groups.len()],
+        };//end struct construction
+        let lookup = DistrictLookup::new(groups);
+        for row in 0..grid.rows() {
+            for col in 0..grid.cols() {
+                let instance = grid.get(row, col).unwrap();
+                if instance.district_id.is_some() {
+                    let owner = lookup.index_of(instance).unwrap_or(groups.len());
+                    expansion.owners[row * grid.cols() + col] = Some(owner);
+                }//end if this cell is claimed
+            }//end looping over columns
+        }//end looping over rows
+        for (group_index, group) in groups.iter().enumerate() {
+            for location in &group.locations {
+                expansion.add_neighbors_to_frontier(group_index, group, *location);
+            }//end adding around each location
+        }//end building each frontier
+        return expansion;
+    }//end new(grid, groups)
+
+    /// # index(&self, coord)
+    /// 
+    /// returns the index of coord within owners
+    fn index(&self, coord:Coord) -> usize {
+        coord.row * self.cols + coord.col
+    }//end index(&self, coord)
+
+    /// # add_neighbors_to_frontier(&mut self, group_index, group, coord)
+    /// 
+    /// adds the unclaimed cells above, below, left and right of coord to the frontier of the group at group_index
+    fn add_neighbors_to_frontier(&mut self, group_index:usize, group:&Grouping, coord:Coord) {
+        let mut neighbors = Vec::with_capacity(4);
+        if coord.row > 0 {neighbors.push(Coord::new(coord.row - 1, coord.col));}
+        if coord.row + 1 < self.rows {neighbors.push(Coord::new(coord.row + 1, coord.col));}
+        if coord.col > 0 {neighbors.push(Coord::new(coord.row, coord.col - 1));}
+        if coord.col + 1 < self.cols {neighbors.push(Coord::new(coord.row, coord.col + 1));}
+        for neighbor in neighbors {
+            let index = self.index(neighbor);
+            if self.owners[index].is_some() {continue;}
+            if self.in_frontier[group_index].insert(index) {
+                // every cell needs some weight, or it could never be picked
+                let weight = (group.dist_from_center(&neighbor).ceil() as u64).max(1);
+                self.frontiers[group_index].push(neighbor, weight);
+            }//end if this cell wasn't already in the frontier
+        }//end checking each neighbor
+    }//end add_neighbors_to_frontier(&mut self, group_index, group, coord)
+
+    /// # pick_from_frontier(&mut self, group_index, rng)
+    /// 
+    /// Picks an unclaimed cell from the frontier of the group at group_index, with cells farther from the district's first location more likely to be picked.
+    /// Cells claimed by another district since they were added are thrown away as they're picked, and another pick is made instead.
+    /// 
+    /// ## Return
+    /// Returns the picked cell, which is removed from the frontier, or None if there are no unclaimed cells left in the frontier.
+    fn pick_from_frontier(&mut self, group_index:usize, rng:&mut impl Rng) -> Option<Coord> {
+        let frontier = &mut self.frontiers[group_index];
+        loop {
+            let coord = frontier.take_weighted(rng)?;
+            if self.owners[coord.row * self.cols + coord.col].is_none() {return Some(coord);}
+        }//end looping until we find a cell nobody has claimed
+    }//end pick_from_frontier(&mut self, group_index, rng)
+
+    /// # advance(&mut self, grid, groups, rng, claimed)
+    /// 
+    /// For each group, an adjacent, unclaimed tile will be claimed. If no tiles can be claimed, the group will not expand.
+    /// Locked groups never expand, so they're counted as enclosed.
+    /// The neighborhood already in a claimed tile is left alone, so it can be regenerated afterwards without losing locked buildings.
+    /// Each claimed tile is added to claimed along with the id of the district that claimed it.
+    /// 
+    /// ## Return
+    /// This function returns the number of groups which could not be expanded because they were completely enclosed
+    pub fn advance(&mut self, grid:&mut Grid<GroupInstance>, groups:&mut [Grouping], rng:&mut impl Rng, claimed:&mut Vec<(Coord, u32)>) -> usize {
+        let mut num_enclosed: usize = 0;
+        for (group_index, group) in groups.iter_mut().enumerate() {
+            // locked groups keep exactly the locations they have
+            if group.locked {
+                num_enclosed += 1;
+                continue;
+            }//end if this group isn't allowed to grow
+            let coord = match self.pick_from_frontier(group_index, rng) {
+                Some(coord) => coord,
+                None => {
+                    num_enclosed += 1;
+                    continue;
+                },
+            };//end matching whether there's anywhere left to expand
+            let index = self.index(coord);
+            self.owners[index] = Some(group_index);
+            group.locations.push(coord);
+            let grid_spot = grid.get_mut(coord.row, coord.col).unwrap();
+            grid_spot.district_id = Some(group.id);
+            grid_spot.coord = Some(coord);
+            claimed.push((coord, group.id));
+            self.add_neighbors_to_frontier(group_index, group, coord);
+        }//end looping over each group to advance
+        return num_enclosed;
+    }//end advance(&mut self, grid, groups, rng, claimed)
+}//end impl for DistrictExpansion
+
+/// # Frontier
+/// 
+/// The cells a district might claim next, with their weights kept in a fenwick tree so a weighted pick doesn't have to look at every cell.
+/// Cells are only ever added to the end. Taking a cell sets its weight to 0 instead of removing it, so the positions of the other cells don't change.
+#[derive(Clone, Debug, Default)]
+struct Frontier {
+    /// every cell added, in the order they were added
+    cells: Vec<Coord>,
+    /// the weight of each cell in cells, or 0 once it's been taken
+    weights: Vec<u64>,
+    /// the fenwick tree over weights, where tree[i - 1] holds the sum of the lowbit(i) weights ending at weights[i - 1]
+    tree: Vec<u64>,
+    /// the sum of every weight
+    total: u64,
+}//end struct Frontier
+
+impl Frontier {
+    /// # push(&mut self, coord, weight)
+    /// 
+    /// adds coord to the end of the frontier with weight
+    fn push(&mut self, coord:Coord, weight:u64) {
+        // the new node covers the weights before it up to its lowest bit, which are the sum of the nodes it steps down through
+        let position = self.cells.len() + 1;
+        let lowest_bit = position & position.wrapping_neg();
+        let mut node_sum = weight;
+        let mut step = 1;
+        while step < lowest_bit {
+            node_sum += self.tree[position - step - 1];
+            step <<= 1;
+        }//end adding up the nodes this one covers
+        self.cells.push(coord);
+        self.weights.push(weight);
+        self.tree.push(node_sum);
+        self.total += weight;
+    }//end push(&mut self, coord, weight)
+
+    /// # find(&self, target)
+    /// 
+    /// returns the index of the cell whose share of the total weight includes target, which must be less than the total
+    fn find(&self, mut target:u64) -> usize {
+        let mut position = 0;
+        let mut step = if self.tree.is_empty() {0} else {1 << self.tree.len().ilog2()};
+        while step > 0 {
+            let next = position + step;
+            if next <= self.tree.len() && self.tree[next - 1] <= target {
+                position = next;
+                target -= self.tree[next - 1];
+            }//end if the target is past this node
+            step >>= 1;
+        }//end narrowing down one bit at a time
+        position
+    }//end find(&self, target)
+
+    /// # take_weighted(&mut self, rng)
+    /// 
+    /// Picks a cell that hasn't been taken yet, weighted by the weight it was added with, and takes it out of the frontier.
+    /// Returns None if every cell has been taken.
+    fn take_weighted(&mut self, rng:&mut impl Rng) -> Option<Coord> {
+        if self.total == 0 {return None;}
+        let index = self.find(rng.gen_range(0..self.total));
+        let weight = std::mem::take(&mut self.weights[index]);
+        self.total -= weight;
+        let mut position = index + 1;
+        while position <= self.tree.len() {
+            self.tree[position - 1] -= weight;
+            position += position & position.wrapping_neg();
+        }//end updating every node that covers this cell
+        Some(self.cells[index])
+    }//end take_weighted(&mut self, rng)
+}//end impl for Frontier
+
+/// # prime_grid_with_groups()
+/// 
+/// Adds single instance of each group in random spots in the grid.
+/// Groups which already have locations, such as ones kept by locks, are left alone.
+/// If the grid runs out of unclaimed spots, the remaining groups aren't placed.
+pub fn prime_grid_with_groups(grid:&mut Grid<GroupInstance>, groups:&mut Vec<Grouping>, rng:&mut impl Rng){
+    let mut num_unclaimed = grid.iter().filter(|instance| instance.district_id.is_none()).count();
+    // start looping through groups to actually do stuff
+    for group in groups {
+        if !group.locations.is_empty() {continue;}
+        if num_unclaimed == 0 {break;}
+        num_unclaimed -= 1;
+        loop {
+            // generate random location
+            let row = rng.gen_range(0..grid.rows());
+            let col = rng.gen_range(0..grid.cols());
+            // check that we're not overlapping
+            if grid.get(row, col).unwrap().district_id.is_none() {
+                // actually put the group in
+                let spot = grid.get_mut(row, col).unwrap();
+                // get the Coord for this new group instance
+                let this_coord = Coord::new(row, col);
+                // update the grouping
+                group.locations.push(this_coord);
+                // put the right references into this GroupInstance
+                spot.district_id = Some(group.id);
+                spot.coord = Some(this_coord);
+                break;
+            }//end if we can continue
+            else {continue;}
+        }//end while we need to do check to not overwrite other group
+    }//end generating something for each group
+}//end prime_grid_with_groups
+
+/// # generate_neighborhood
+/// 
+/// Generates a neighborhood grid inside nhood's sub_grid field.
+/// This method will use the rows and columns of the provided nhood object.
+/// The rng parameter is used for random number generation.
+/// Buildings are colored from palette, so that each building type is the same color across the whole city.
+pub fn generate_neighborhood(nhood:&mut GroupInstance, rng:&mut impl Rng, palette:&BuildingPalette) {
+    // save some handy reference variables for later
+    let rows = nhood.sub_grid.rows();
+    let cols = nhood.sub_grid.cols();
+    
+    // add roads to our neighborhood
+    let _num_colors = add_roads_to_neighborhood(nhood, rng);
+
+    // loop through the whole grid
+    for row in 0..rows {
+        for col in 0..cols {
+            // pull out the building we want to edit
+            let this_build = nhood.sub_grid.get_mut(row, col).expect("Index should have been safe.");
+            
+            // make sure we aren't overwriting a road or a locked building
+            if this_build.build_type.eq(&BuildingType::Road) || this_build.locked {continue;}
+
+            // generate new type for building
+            this_build.build_type = gen_build_type(rng);
+            // use category-based color picking from the city palette
+            palette.color_code_building(this_build);
+        }//end looping through columns
+    }//end looping through rows
+}//end generate_neighborhood(nhood, rng, palette)
+
+/// # regenerate_neighborhood(nhood, rng, palette, rows, cols)
+/// 
+/// Throws away the buildings in nhood and generates a fresh neighborhood with the specified number of rows and columns.
+/// Locked buildings are kept where they are, as long as the neighborhood stays the same size.
+pub fn regenerate_neighborhood(nhood:&mut GroupInstance, rng:&mut impl Rng, palette:&BuildingPalette, rows:usize, cols:usize) {
+    let old_grid = std::mem::replace(&mut nhood.sub_grid, Grid::new(0,0));
+    nhood.initialize_sub_grid(rows, cols);
+    if old_grid.rows() == rows && old_grid.cols() == cols {
+        for (new_building, old_building) in nhood.sub_grid.iter_mut().zip(old_grid.iter()) {
+            if old_building.locked {
+                *new_building = old_building.clone();
+            }//end if we need to keep this building
+        }//end copying over each locked building
+    }//end if locked buildings still fit
+    generate_neighborhood(nhood, rng, palette);
+}//end regenerate_neighborhood(nhood, rng, palette, rows, cols)
+
+/// # add_roads_to_neighborhood(nhood, rng)
+/// 
+/// This function could be seen as a helper function for generate_neighborhood().
+/// It will generate roads and place them in the nhood parameter.
+/// 
+/// Returns the recommended number of colors to use
+fn add_roads_to_neighborhood(nhood:&mut GroupInstance, rng:&mut impl Rng) -> usize{
+    // save some handy reference variables for later
+    let rows = nhood.sub_grid.rows();
+    let cols = nhood.sub_grid.cols();
+    
+    // figure out number of roads to slam in there
+    let num_roads_low_bound = ((rows + cols) as f32 * 1.2).sqrt().ceil() as usize;
+    let num_roads_upp_bound = ((rows + cols) as f32 * 2.2).sqrt().ceil() as usize;
+    let num_roads_total = rng.gen_range(num_roads_low_bound..num_roads_upp_bound);
+    let num_roads_horizontal = rng.gen_range(1.min(num_roads_total / 2)..num_roads_total.min((num_roads_total as f32 * 0.7).ceil() as usize));
+    let num_roads_vertical = num_roads_total - num_roads_horizontal;
+
+    // determine number of colors from roads
+    let num_colors = 3.max((num_roads_upp_bound - num_roads_total) * 2);
+    
+    // slap some horizontal roads in there
+    let mut roads_hor_idxs = Vec::new();
+    while roads_hor_idxs.len() < num_roads_horizontal.min(rows) {
+        let road_hor_idx = rng.gen_range(0..rows);
+        if !roads_hor_idxs.contains(&road_hor_idx) {
+            roads_hor_idxs.push(road_hor_idx);
+        }//end if we haven't already generated this index
+    }//end looping while we can fit some more horizontal roads in there
+
+    // slap some vertical roads in there
+    let mut roads_ver_idxs = Vec::new();
+    while roads_ver_idxs.len() < num_roads_vertical.min(cols) {
+        let road_ver_idx = rng.gen_range(0..cols);
+        if !roads_ver_idxs.contains(&road_ver_idx) {
+            roads_ver_idxs.push(road_ver_idx);
+        }//end if we haven't already generated this index
+    }//end looping while we can fit some more vertical roads int there
+
+    let road_color:(u8,u8,u8) = get_road_color();
+
+    // actually edit nhood with horizontal roads
+    for row_idx in roads_hor_idxs {
+        for col_idx in 0..cols {
+            let this_building = nhood.sub_grid.get_mut(row_idx, col_idx).expect("Those indices seemed pretty valid to me... Should be in bounds and everything.");
+            // roads go around locked buildings
+            if this_building.locked {continue;}
+            // set type to road and color the roads
+            this_building.build_type = BuildingType::Road;
+            this_building.rgb_color = road_color;
+        }//end looping over each column on our way horizontal
+    }//end looping over each horizontal road index to add
+
+    // actually edit nhood with vertical roads
+    for col_idx in roads_ver_idxs {
+        for row_idx in 0..rows {
+            let this_building = nhood.sub_grid.get_mut(row_idx, col_idx).expect("Those indices seemed pretty valid to me... Should be in bounds and everything.");
+            // roads go around locked buildings
+            if this_building.locked {continue;}
+            // set type to road and color the roads
+            this_building.build_type = BuildingType::Road;
+            this_building.rgb_color = road_color;
+        }//end looping over reach row on our way vertical
+    }//end looping over each vertical road index
+
+    return num_colors;
+}//end add_roads_to_neighborhood(nhood, rng)
+
+/// # gen_build_type(rng)
+/// 
+/// This function could be seen as a helper function for generate_neighborhoods().
+/// It will randomly generate a building type. That's it, just useful to keep generate_neighborhoods a little bit cleaner.
+/// Colors are handled separately by the city's building palette.
+/// 
+/// returns the generated BuildingType
+fn gen_build_type(rng:&mut impl Rng) -> BuildingType {
+    let build_type_index = rng.gen_range(0..112);
+    let build_type = match build_type_index {
+        0 => BuildingType::Road,
+        1 => BuildingType::FireDept,
+        2 => BuildingType::Prison,
+        3..=5 => BuildingType::Factory,
+        6 => BuildingType::Landfill,
+        7..=10 => BuildingType::Mansion,
+        11..=12 => BuildingType::Spa,
+        13..=15 => BuildingType::Shrine,
+        16..=18 => BuildingType::Church,
+        19..=21 => BuildingType::Temple,
+        22..=24 => BuildingType::Market,
+        25 => BuildingType::Police,
+        26..=29 => BuildingType::ChainStore,
+        30..=33 =>BuildingType::CorpOffice,
+        34..=35 => BuildingType::Cafe,
+        36..=37 => BuildingType::GovOffice,
+        38 => BuildingType::Pound,
+        39..=40 => BuildingType::Arcade,
+        41..=50 => BuildingType::Park,
+        51..=53 => BuildingType::Pharmacy,
+        54 => BuildingType::Hospital,
+        55 => BuildingType::Museum,
+        56..=57 => BuildingType::School,
+        58..=70 => BuildingType::Shop,
+        71..=110 => BuildingType::Residence,
+        111 => BuildingType::Road,
+        _ => BuildingType::Empty,
+    };
+    return build_type;
+}//end gen_build_type(rng)
+
+/// # create_empty_grid()
+/// 
+/// This function creates an empty grid of the specified dimensions, filled with the string "empty".
+pub fn create_empty_grid(rows:usize, cols:usize) -> Grid<GroupInstance> {
+    let mut empty = Grid::new(rows, cols);
+    empty.fill(GroupInstance::default());
+    return empty;
+}//end createEmptyGrid
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouping::two_district_city;
+
+    /// # locked_city()
+    /// 
+    /// Makes the two_district_city() with generated neighborhoods, where only the top cell of district 1 is locked, and district 2 is locked as a whole
+    fn locked_city() -> (Grid<GroupInstance>, Vec<Grouping>) {
+        let (mut grid, mut districts) = two_district_city();
+        districts[1].locked = true;
+        grid.get_mut(0, 0).unwrap().cell_locked = true;
+        let never_cancelled = AtomicBool::new(false);
+        generate_neighborhoods(&mut grid, (4, 4), &BuildingPalette::default_preset(), 1, &|_:&GroupInstance| true, 2, None, &never_cancelled);
+        (grid, districts)
+    }//end locked_city()
+
+    #[test]
+    fn strip_keeps_only_locked_cells() {
+        let (mut grid, mut districts) = locked_city();
+        let before = grid.clone();
+        strip_unlocked_cells(&mut grid, &mut districts);
+        assert_eq!(districts[0].locations, vec![Coord::new(0, 0)]);
+        assert_eq!(districts[1].locations, vec![Coord::new(0, 1), Coord::new(1, 1)]);
+        assert_eq!(grid.get(1, 0).unwrap().district_id, None);
+        assert_eq!(grid.get(0, 0).unwrap().district_id, Some(1));
+        // neighborhoods are left for generate_neighborhoods() to decide about
+        for (stripped, original) in grid.iter().zip(before.iter()) {
+            assert_eq!(stripped.sub_grid, original.sub_grid);
+        }//end checking each neighborhood
+    }//end strip_keeps_only_locked_cells()
+
+    #[test]
+    fn locked_cells_and_districts_keep_their_neighborhoods() {
+        let (mut grid, mut districts) = locked_city();
+        let before = grid.clone();
+        let locked_ids: HashSet<u32> = districts.iter().filter(|district| district.locked).map(|district| district.id).collect();
+        strip_unlocked_cells(&mut grid, &mut districts);
+        assert!(keeps_neighborhood(grid.get(0, 0).unwrap(), &locked_ids));
+        assert!(keeps_neighborhood(grid.get(0, 1).unwrap(), &locked_ids));
+        assert!(!keeps_neighborhood(grid.get(1, 0).unwrap(), &locked_ids));
+
+        let never_cancelled = AtomicBool::new(false);
+        let should_generate = |instance:&GroupInstance| !keeps_neighborhood(instance, &locked_ids);
+        generate_neighborhoods(&mut grid, (4, 4), &BuildingPalette::default_preset(), 2, &should_generate, 2, None, &never_cancelled);
+        for coord in [Coord::new(0, 0), Coord::new(0, 1), Coord::new(1, 1)] {
+            assert_eq!(grid.get(coord.row, coord.col).unwrap().sub_grid, before.get(coord.row, coord.col).unwrap().sub_grid);
+        }//end checking each locked cell
+        assert_ne!(grid.get(1, 0).unwrap().sub_grid, before.get(1, 0).unwrap().sub_grid);
+    }//end locked_cells_and_districts_keep_their_neighborhoods()
+
+    #[test]
+    fn locked_cells_without_neighborhoods_still_get_one() {
+        let mut instance = GroupInstance::new(1, Coord::new(0, 0));
+        instance.cell_locked = true;
+        assert!(!keeps_neighborhood(&instance, &HashSet::new()));
+        instance.nhood_locked = true;
+        assert!(keeps_neighborhood(&instance, &HashSet::new()));
+    }//end locked_cells_without_neighborhoods_still_get_one()
+
+    #[test]
+    fn unclaimed_cells_lose_their_neighborhood_lock() {
+        let (mut grid, mut districts) = locked_city();
+        grid.get_mut(1, 0).unwrap().nhood_locked = true;
+        grid.get_mut(0, 0).unwrap().nhood_locked = true;
+        let locked_ids: HashSet<u32> = districts.iter().filter(|district| district.locked).map(|district| district.id).collect();
+        strip_unlocked_cells(&mut grid, &mut districts);
+        // the unlocked cell goes back up for grabs, so its neighborhood gets regenerated for whichever district claims it
+        let unclaimed = grid.get(1, 0).unwrap();
+        assert_eq!(unclaimed.district_id, None);
+        assert!(!unclaimed.nhood_locked);
+        assert!(!keeps_neighborhood(unclaimed, &locked_ids));
+        // a locked neighborhood in a cell that keeps its district stays locked
+        assert!(grid.get(0, 0).unwrap().nhood_locked);
+    }//end unclaimed_cells_lose_their_neighborhood_lock()
+
+    #[test]
+    fn frontier_picks_by_weight() {
+        let mut frontier = Frontier::default();
+        for (index, weight) in [3, 1, 4, 1, 5, 9, 2].iter().enumerate() {
+            frontier.push(Coord::new(0, index), *weight);
+        }//end adding each cell
+        assert_eq!(frontier.total, 25);
+        let expected = [0, 0, 0, 1, 2, 2, 2, 2, 3, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 6, 6];
+        for (target, index) in expected.iter().enumerate() {
+            assert_eq!(frontier.find(target as u64), *index);
+        }//end checking where each target lands
+
+        // taken cells can't be picked again, and every cell comes out exactly once
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut taken: Vec<usize> = std::iter::from_fn(|| frontier.take_weighted(&mut rng)).map(|coord| coord.col).collect();
+        taken.sort_unstable();
+        assert_eq!(taken, (0..7).collect::<Vec<usize>>());
+        assert_eq!(frontier.total, 0);
+    }//end frontier_picks_by_weight()
+
+    #[test]
+    fn districts_fill_a_large_grid() {
+        // big enough that scanning the whole frontier for every pick would take a very long time
+        let (rows, cols) = (500, 500);
+        let mut grid = create_empty_grid(rows, cols);
+        let mut groups: Vec<Grouping> = (1..=6).map(|id| Grouping::new(format!("district {}", id)).with_id(id)).collect();
+        let mut rng = StdRng::seed_from_u64(39);
+        prime_grid_with_groups(&mut grid, &mut groups, &mut rng);
+        let mut expansion = DistrictExpansion::new(&grid, &groups);
+        let mut claimed = Vec::new();
+        let start = Instant::now();
+        while expansion.advance(&mut grid, &mut groups, &mut rng, &mut claimed) < groups.len() {}
+        assert!(start.elapsed() < Duration::from_secs(60), "expanding took {:?}", start.elapsed());
+
+        assert!(grid.iter().all(|instance| instance.district_id.is_some()));
+        assert_eq!(claimed.len() + groups.len(), rows * cols);
+        assert_eq!(groups.iter().map(|group| group.locations.len()).sum::<usize>(), rows * cols);
+        for group in &groups {
+            assert!(group.locations.iter().all(|coord| grid.get(coord.row, coord.col).unwrap().district_id == Some(group.id)));
+        }//end checking each group's locations
+    }//end districts_fill_a_large_grid()
+
+    #[test]
+    fn neighborhoods_dont_depend_on_thread_count() {
+        // an odd number of cells, so the chunks don't divide evenly
+        let mut single = create_empty_grid(7, 9);
+        let palette = BuildingPalette::default_preset();
+        let never_cancelled = AtomicBool::new(false);
+        assert!(generate_neighborhoods(&mut single, (5, 6), &palette, 41, &|_:&GroupInstance| true, 1, None, &never_cancelled));
+        for num_threads in [2, 3, 8, 64, 100] {
+            let mut multi = create_empty_grid(7, 9);
+            assert!(generate_neighborhoods(&mut multi, (5, 6), &palette, 41, &|_:&GroupInstance| true, num_threads, None, &never_cancelled));
+            assert!(multi == single, "{} threads gave a different city", num_threads);
+        }//end trying each number of threads
+    }//end neighborhoods_dont_depend_on_thread_count()
+
+    #[test]
+    fn cancelled_generation_stops() {
+        let mut grid = create_empty_grid(4, 4);
+        let cancelled = AtomicBool::new(true);
+        assert!(!generate_neighborhoods(&mut grid, (5, 5), &BuildingPalette::default_preset(), 1, &|_:&GroupInstance| true, 2, None, &cancelled));
+        assert!(grid.iter().all(|instance| !instance.has_neighborhood()));
+    }//end cancelled_generation_stops()
+
+    #[test]
+    fn lazy_neighborhoods_match_eager_ones() {
+        let palette = BuildingPalette::default_preset();
+        let lazy = LazyNeighborhoods { seed: 42, dims: (6, 5) };
+        let mut eager = create_empty_grid(4, 3);
+        let never_cancelled = AtomicBool::new(false);
+        generate_neighborhoods(&mut eager, lazy.dims, &palette, lazy.seed, &|_:&GroupInstance| true, 2, None, &never_cancelled);
+
+        // viewing neighborhoods one at a time, in any order, gives the same ones
+        let mut viewed = create_empty_grid(4, 3);
+        for row in (0..4).rev() {
+            for col in (0..3).rev() {
+                assert!(lazy.materialize(&mut viewed, Coord::new(row, col), &palette));
+                assert!(!lazy.materialize(&mut viewed, Coord::new(row, col), &palette));
+            }//end looping over columns
+        }//end looping over rows
+        assert!(viewed == eager);
+
+        // so does generating whatever is left all at once
+        let mut partly_viewed = create_empty_grid(4, 3);
+        lazy.materialize(&mut partly_viewed, Coord::new(2, 1), &palette);
+        assert_eq!(lazy.materialize_all(&mut partly_viewed, &palette), 11);
+        assert_eq!(lazy.materialize_all(&mut partly_viewed, &palette), 0);
+        assert!(partly_viewed == eager);
+    }//end lazy_neighborhoods_match_eager_ones()
+}//end mod tests
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
 use grid::Grid;
@@ -30,19 +31,51 @@ impl Coord {
 		}//end struct construction
 	}//end new()
 	
-	/// # to_string(&self)
+	/// # step(&self, direction, rows, cols)
 	/// 
-	/// returns a string with the labelled row and column index
-	pub fn to_string(&self) -> String {
-		format!("row: {}, col: {}", self.row, self.col)
-	}//end to_string()
+	/// returns the coordinate next to this one in direction, or None if that would be outside a grid of rows by cols
+	pub fn step(&self, direction:Direction, rows:usize, cols:usize) -> Option<Coord> {
+		let next = match direction {
+			Direction::Up => Coord::new(self.row.checked_sub(1)?, self.col),
+			Direction::Down => Coord::new(self.row + 1, self.col),
+			Direction::Left => Coord::new(self.row, self.col.checked_sub(1)?),
+			Direction::Right => Coord::new(self.row, self.col + 1),
+		};//end matching direction
+		if next.row >= rows || next.col >= cols {return None;}
+		return Some(next);
+	}//end step(&self, direction, rows, cols)
 }//end impl for Coord
 
+impl Display for Coord {
+	/// # fmt(&self, f)
+	/// 
+	/// writes the labelled row and column index
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "row: {}, col: {}", self.row, self.col)
+	}//end fmt(&self, f)
+}//end impl Display for Coord
+
+/// # Direction
+/// 
+/// One of the four ways to move from a cell of a grid to the one beside it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+	Up,
+	Down,
+	Left,
+	Right,
+}//end enum Direction
+
 /// # Grouping
 /// 
 /// A struct to keep track of the instances a group is located within a grid
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Grouping {
+	/// # id
+	/// 
+	/// a number identifying this grouping, which stays the same even if it gets renamed.
+	/// Ids start at 1, so 0 means no id has been given out yet.
+	pub id: u32,
 	/// # group
 	/// 
 	/// the name of this grouping
@@ -55,6 +88,10 @@ pub struct Grouping {
 	/// 
 	/// the color to display for the group, in 3-number rgb values
 	pub rgb_color: (u8,u8,u8),
+	/// # locked
+	/// 
+	/// whether this group should keep all of its locations when regenerating
+	pub locked: bool,
 }//end struct grouping
 
 #[allow(dead_code)]
@@ -64,9 +101,11 @@ impl Grouping {
 	/// Creates a new grouping with the specified name.
 	pub fn new(name:String) -> Grouping {
 		Grouping {
+			id: 0,
 			name,
 			locations: Vec::new(),
 			rgb_color: (0,0,0),
+			locked: false,
 		}//end struct construction
 	}//end new()
 
@@ -75,23 +114,14 @@ impl Grouping {
 	/// Creates a new grouping with name "default"
 	pub fn default() -> Grouping {
 		Grouping {
+			id: 0,
 			name: "empty".to_string(),
 			locations: Vec::new(),
 			rgb_color: (0,0,0),
+			locked: false,
 		}//end struct construction
 	}//end default()
 
-	/// # to_string(&self)
-	/// 
-	/// This method creates a string consisting of this grouping's name and all locations, complete with labelled indices.
-	pub fn to_string(&self) -> String {
-		let mut result = format!("Group Printout:\nName: {}\n", self.name);
-		for location in &self.locations {
-			result = format!("{}{}\n", result, location.to_string());
-		}//end adding location info to string
-		return result;
-	}//end to_string(&self)
-
 	/// # with_color(self, color)
 	/// 
 	/// sets rgb color without needing a separate assignment
@@ -100,6 +130,14 @@ impl Grouping {
 		self.to_owned()
 	}//end with_color(self, color)
 
+	/// # with_id(self, id)
+	/// 
+	/// sets id without needing a separate assignment
+	pub fn with_id(&mut self, id:u32) -> Grouping {
+		self.id = id;
+		self.to_owned()
+	}//end with_id(self, id)
+
 	/// # get_adjacent_coords(&self, max_row, max_col)
 	/// 
 	/// This function generates a list of coordinates that are adjacent to this grouping. The maximum row and column index are required in parameters. 
@@ -172,37 +210,65 @@ impl Grouping {
 
 	/// # dist_from_center(&self, coord)
 	/// 
-	/// Returns the distance as a float from the first location added to this grouping.
+	/// Returns the distance as a float from the first location added to this grouping, or 0.0 if this grouping doesn't have any locations yet.
 	pub fn dist_from_center(&self, coord: &Coord) -> f32 {
 		// pull out first location as a variable for easy reference
-		let first = self.locations.first().unwrap();
+		let first = match self.locations.first() {
+			Some(first) => first,
+			None => return 0.0,
+		};//end matching whether there's a center to measure from
 		// do a little pythag theorem
-		let x_diff = f32::abs((first.row as f32 - coord.row as f32) as f32);
-		let y_diff = f32::abs((first.col as f32 - coord.col as f32) as f32);
+		let x_diff = f32::abs(first.row as f32 - coord.row as f32);
+		let y_diff = f32::abs(first.col as f32 - coord.col as f32);
 		let x_diff_squared = x_diff * x_diff;
 		let y_diff_squared = y_diff * y_diff;
 		let x_y_squared_sum = x_diff_squared + y_diff_squared;
-		let distance = f32::sqrt(x_y_squared_sum as f32);
+		let distance = f32::sqrt(x_y_squared_sum);
 		return distance;
 	}//end dist_from_center(&self, coord)
 }//end impl for Grouping
 
-#[derive(Clone)]
+impl Display for Grouping {
+	/// # fmt(&self, f)
+	/// 
+	/// writes this grouping's name and all locations, complete with labelled indices
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Group Printout:\nName: {}", self.name)?;
+		for location in &self.locations {
+			writeln!(f, "{}", location)?;
+		}//end adding location info to string
+		return Ok(());
+	}//end fmt(&self, f)
+}//end impl Display for Grouping
+
+/// # GroupInstance
+/// 
+/// A single cell of the city grid, holding the neighborhood of buildings at that spot.
+/// The cell only refers to its district by id, so use district() or district_at() to look up the district itself, or a DistrictLookup when going over every cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GroupInstance {
-	pub group:Option<Grouping>,
+	/// the id of the district this cell belongs to, if any
+	pub district_id:Option<u32>,
 	pub coord:Option<Coord>,
+	/// the buildings in this cell's neighborhood, which stays empty until the neighborhood is generated
 	pub sub_grid: Grid<Building>,
+	/// whether this cell should stay in the same group when regenerating
+	pub cell_locked: bool,
+	/// whether the buildings in sub_grid should stay the same when regenerating
+	pub nhood_locked: bool,
 }
 
 impl Default for GroupInstance {
 	/// # default()
 	/// 
-	/// Sets the group and coord references as None without really setting up the sub_grid yet.
+	/// Sets the district and coord references as None, leaving the sub_grid empty so it doesn't take any memory yet.
 	fn default() -> GroupInstance {
 		GroupInstance {
-			group: None,
+			district_id: None,
 			coord: None,
-			sub_grid: Grid::new(1,1),
+			sub_grid: Grid::new(0,0),
+			cell_locked: false,
+			nhood_locked: false,
 		}//end struct construction
 	}//end default(group, coord)
 }//end Default impl for GroupInstance
@@ -217,25 +283,174 @@ impl GroupInstance {
 		self.sub_grid.fill(Building::default());
 	}//end initialize_sub_grid(self, rows, cols)
 
-	/// # new(group, coord, rows, cols)
+	/// # new(district_id, coord)
 	/// 
-	/// Sets references for grouping and coord, while also initializing sub_grid as in initialize_sub_grid() function
-	pub fn new(group:Grouping, coord:Coord, rows:usize, cols:usize) -> GroupInstance {
-		let mut temp_grid = Grid::new(rows, cols);
-		temp_grid.fill(Building::default());
-
+	/// Sets references for district and coord, leaving sub_grid empty until the neighborhood is generated
+	pub fn new(district_id:u32, coord:Coord) -> GroupInstance {
 		GroupInstance {
-			group: Some(group),
+			district_id: Some(district_id),
 			coord: Some(coord),
-			sub_grid: temp_grid,
+			..GroupInstance::default()
 		}//end struct constructions
-	}//end new(group, coord, rows, cols)
+	}//end new(district_id, coord)
+
+	/// # has_neighborhood(&self)
+	/// 
+	/// Returns true if the neighborhood in this cell has been generated
+	pub fn has_neighborhood(&self) -> bool {
+		!self.sub_grid.is_empty()
+	}//end has_neighborhood(&self)
+
+	/// # district(&self, districts)
+	/// 
+	/// Looks up the district this cell belongs to in districts.
+	/// Returns None if the cell doesn't have a district, or its district isn't in districts anymore.
+	pub fn district<'a>(&self, districts:&'a [Grouping]) -> Option<&'a Grouping> {
+		let id = self.district_id?;
+		districts.iter().find(|district| district.id == id)
+	}//end district(&self, districts)
+
+	/// # district_index(&self, districts)
+	/// 
+	/// Like district(), but returns the position of the district in districts
+	pub fn district_index(&self, districts:&[Grouping]) -> Option<usize> {
+		let id = self.district_id?;
+		districts.iter().position(|district| district.id == id)
+	}//end district_index(&self, districts)
+
+	/// # is_locked(&self, districts)
+	/// 
+	/// Returns true if this cell should keep its district when regenerating, either because the cell itself or its whole district is locked
+	pub fn is_locked(&self, districts:&[Grouping]) -> bool {
+		self.cell_locked || self.district(districts).is_some_and(|district| district.locked)
+	}//end is_locked(&self, districts)
+
+	/// # building_counts(&self)
+	/// 
+	/// Counts how many of each building type are in this cell's neighborhood.
+	/// Returns each type that shows up along with its count, from most to least common.
+	pub fn building_counts(&self) -> Vec<(BuildingType, usize)> {
+		let mut counts: Vec<(BuildingType, usize)> = Vec::new();
+		for building in self.sub_grid.iter() {
+			match counts.iter_mut().find(|(build_type, _)| *build_type == building.build_type) {
+				Some((_, count)) => *count += 1,
+				None => counts.push((building.build_type.clone(), 1)),
+			}//end matching whether we've seen this type yet
+		}//end counting each building
+		counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		return counts;
+	}//end building_counts(&self)
 }//end GroupInstance
 
+/// # DistrictLookup
+/// 
+/// Finds districts by id without searching the whole list, for passes that look up the district of every cell in a city.
+/// It borrows the district list, so make a new one for each pass rather than keeping it around.
+pub struct DistrictLookup<'a> {
+	/// the districts being looked up
+	districts: &'a [Grouping],
+	/// the index in districts of each district id
+	indices: HashMap<u32, usize>,
+}//end struct DistrictLookup
+
+impl<'a> DistrictLookup<'a> {
+	/// # new(districts)
+	/// 
+	/// Indexes districts by id. If two districts share an id, the first one is found, like GroupInstance::district().
+	pub fn new(districts:&'a [Grouping]) -> DistrictLookup<'a> {
+		DistrictLookup {
+			districts,
+			indices: district_indices(districts),
+		}//end struct construction
+	}//end new(districts)
+
+	/// # by_id(&self, district_id)
+	/// 
+	/// Returns the district with district_id, or None if there isn't one
+	pub fn by_id(&self, district_id:u32) -> Option<&'a Grouping> {
+		self.indices.get(&district_id).map(|index| &self.districts[*index])
+	}//end by_id(&self, district_id)
+
+	/// # district_of(&self, instance)
+	/// 
+	/// Like GroupInstance::district(), but without searching the list
+	pub fn district_of(&self, instance:&GroupInstance) -> Option<&'a Grouping> {
+		self.by_id(instance.district_id?)
+	}//end district_of(&self, instance)
+
+	/// # index_of(&self, instance)
+	/// 
+	/// Like GroupInstance::district_index(), but without searching the list
+	pub fn index_of(&self, instance:&GroupInstance) -> Option<usize> {
+		self.indices.get(&instance.district_id?).copied()
+	}//end index_of(&self, instance)
+
+	/// # is_locked(&self, instance)
+	/// 
+	/// Like GroupInstance::is_locked(), but without searching the list
+	pub fn is_locked(&self, instance:&GroupInstance) -> bool {
+		instance.cell_locked || self.district_of(instance).is_some_and(|district| district.locked)
+	}//end is_locked(&self, instance)
+}//end impl for DistrictLookup
+
+/// # district_indices(districts)
+/// 
+/// Returns the index in districts of each district id, keeping the first index if two districts share an id.
+/// This is for passes that change districts while looking them up, where a DistrictLookup can't borrow them.
+pub fn district_indices(districts:&[Grouping]) -> HashMap<u32, usize> {
+	let mut indices = HashMap::with_capacity(districts.len());
+	for (index, district) in districts.iter().enumerate() {
+		indices.entry(district.id).or_insert(index);
+	}//end indexing each district
+	return indices;
+}//end district_indices(districts)
+
+/// # district_at(grid, districts, coord)
+/// 
+/// Looks up the district of the cell at coord in grid.
+/// Returns None if coord is outside the grid, the cell doesn't have a district, or its district isn't in districts.
+pub fn district_at<'a>(grid:&Grid<GroupInstance>, districts:&'a [Grouping], coord:Coord) -> Option<&'a Grouping> {
+	grid.get(coord.row, coord.col)?.district(districts)
+}//end district_at(grid, districts, coord)
+
+/// # cells_in_district(grid, district_id)
+/// 
+/// Returns the coordinates of every cell in grid belonging to the district with district_id, from the top left
+pub fn cells_in_district(grid:&Grid<GroupInstance>, district_id:u32) -> Vec<Coord> {
+	let mut cells = Vec::new();
+	for row in 0..grid.rows() {
+		for col in 0..grid.cols() {
+			if grid.get(row, col).is_some_and(|instance| instance.district_id == Some(district_id)) {
+				cells.push(Coord::new(row, col));
+			}//end if this cell is in the district
+		}//end looping over columns
+	}//end looping over rows
+	return cells;
+}//end cells_in_district(grid, district_id)
+
+/// # two_district_city()
+/// 
+/// Makes a 2x2 city for tests, where the left column is in district 1 named "left" and the right column is in district 2 named "right".
+/// No neighborhoods are generated and nothing is locked, so each test can set up just what it needs.
+#[cfg(test)]
+pub fn two_district_city() -> (Grid<GroupInstance>, Vec<Grouping>) {
+	let mut districts = vec![Grouping::new("left".to_string()).with_id(1), Grouping::new("right".to_string()).with_id(2)];
+	let mut grid: Grid<GroupInstance> = Grid::new(2, 2);
+	for (col, district) in districts.iter_mut().enumerate() {
+		for row in 0..2 {
+			let coord = Coord::new(row, col);
+			district.locations.push(coord);
+			*grid.get_mut(row, col).unwrap() = GroupInstance::new(district.id, coord);
+		}//end looping over rows
+	}//end looping over districts
+	(grid, districts)
+}//end two_district_city()
+
 #[allow(dead_code)]
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum BuildingType {
 	/// building type hasn't been determined yet
+	#[default]
 	Empty,
 	/// road, long and narrow, max width 1
 	Road,
@@ -267,11 +482,58 @@ pub enum BuildingType {
 	FireDept,
 }//end enum BuildingType
 
-impl Default for BuildingType {
-    fn default() -> Self {
-        BuildingType::Empty
-    }//end default()
-}//end enum BuildingType
+#[allow(dead_code)]
+impl BuildingType {
+	/// # editable_types()
+	/// 
+	/// Returns every building type that a user can place by hand, which is Road followed by the palette types.
+	pub fn editable_types() -> Vec<BuildingType> {
+		let mut types = vec![BuildingType::Road];
+		types.extend(BuildingType::palette_types());
+		types
+	}//end editable_types()
+
+	/// # palette_types()
+	/// 
+	/// Returns every building type that gets its color from a building palette, in palette order.
+	/// Empty and Road are left out, since they always use fixed colors.
+	pub fn palette_types() -> Vec<BuildingType> {
+		vec![
+			BuildingType::Residence,
+			BuildingType::Shop,
+			BuildingType::School,
+			BuildingType::Museum,
+			BuildingType::Hospital,
+			BuildingType::Pharmacy,
+			BuildingType::Park,
+			BuildingType::Arcade,
+			BuildingType::Pound,
+			BuildingType::GovOffice,
+			BuildingType::Cafe,
+			BuildingType::CorpOffice,
+			BuildingType::ChainStore,
+			BuildingType::Police,
+			BuildingType::Market,
+			BuildingType::Temple,
+			BuildingType::Church,
+			BuildingType::Shrine,
+			BuildingType::Spa,
+			BuildingType::Mansion,
+			BuildingType::Landfill,
+			BuildingType::Factory,
+			BuildingType::Prison,
+			BuildingType::FireDept,
+		]
+	}//end palette_types()
+
+	/// # palette_index(&self)
+	/// 
+	/// Returns the index of this building type within palette_types(), or None for Empty and Road.
+	pub fn palette_index(&self) -> Option<usize> {
+		BuildingType::palette_types().iter().position(|build_type| build_type.eq(self))
+	}//end palette_index(&self)
+}//end impl for BuildingType
+
 
 impl Display for BuildingType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -310,9 +572,70 @@ impl Display for BuildingType {
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Building {
 	pub build_type: BuildingType,
-	pub rgb_color: (u8, u8, u8)
+	pub rgb_color: (u8, u8, u8),
+	/// whether this building should stay the same when its neighborhood is regenerated
+	pub locked: bool,
+	/// the name and notes the user gave this building, if any.
+	/// Boxed so that the many buildings without any only take up the space of a pointer.
+	pub info: Option<Box<BuildingInfo>>,
 }//end struct Building
 
+/// # BuildingInfo
+/// 
+/// The name and notes a user can give a single building
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BuildingInfo {
+	/// what the building is called, such as the name of a shop
+	pub name: String,
+	/// anything else the user wants to remember about the building
+	pub notes: String,
+}//end struct BuildingInfo
+
 impl Building {
-	
+	/// # name(&self)
+	/// 
+	/// returns the name given to this building, or an empty string if it doesn't have one
+	pub fn name(&self) -> &str {
+		self.info.as_ref().map(|info| info.name.as_str()).unwrap_or("")
+	}//end name(&self)
+
+	/// # notes(&self)
+	/// 
+	/// returns the notes written about this building, or an empty string if there aren't any
+	pub fn notes(&self) -> &str {
+		self.info.as_ref().map(|info| info.notes.as_str()).unwrap_or("")
+	}//end notes(&self)
+
+	/// # set_info(&mut self, name, notes)
+	/// 
+	/// Gives this building a name and notes, ignoring whitespace around each.
+	/// If both are empty, the building goes back to not having any info.
+	pub fn set_info(&mut self, name:&str, notes:&str) {
+		let (name, notes) = (name.trim(), notes.trim());
+		self.info = if name.is_empty() && notes.is_empty() {None}
+		else {Some(Box::new(BuildingInfo { name: name.to_string(), notes: notes.to_string() }))};
+	}//end set_info(&mut self, name, notes)
 }//end impl for Building
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lookup_matches_searching_the_list() {
+		let (mut grid, mut districts) = two_district_city();
+		// a repeated id should find the first district, and a missing one should find nothing
+		districts.push(Grouping::new("copy".to_string()).with_id(2));
+		grid.get_mut(1, 1).unwrap().district_id = Some(9);
+		grid.get_mut(1, 0).unwrap().district_id = None;
+		districts[0].locked = true;
+		let lookup = DistrictLookup::new(&districts);
+		for instance in grid.iter() {
+			assert_eq!(lookup.district_of(instance), instance.district(&districts));
+			assert_eq!(lookup.index_of(instance), instance.district_index(&districts));
+			assert_eq!(lookup.is_locked(instance), instance.is_locked(&districts));
+		}//end checking each cell
+		assert_eq!(lookup.index_of(grid.get(0, 1).unwrap()), Some(1));
+		assert_eq!(lookup.by_id(9), None);
+	}//end lookup_matches_searching_the_list()
+}//end mod tests
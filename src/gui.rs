@@ -3,75 +3,218 @@ use fltk::app::App;
 use fltk::app::Receiver;
 use fltk::app::Sender;
 use fltk::button::Button;
+use fltk::button::CheckButton;
 use fltk::dialog;
 use fltk::enums::Align;
 use fltk::enums::Color;
 use fltk::enums::FrameType;
+use fltk::enums::Key;
 use fltk::enums::Shortcut;
+use fltk::frame::Frame;
 use fltk::group;
-use fltk::group::Flex;
 use fltk::group::Group;
+use fltk::group::Pack;
+use fltk::group::Scroll;
 use fltk::group::Tabs;
 use fltk::input::IntInput;
 use fltk::menu;
+use fltk::menu::Choice;
 use fltk::menu::SysMenuBar;
-use fltk::prelude::DisplayExt;
+use fltk::misc::Progress;
+use fltk::valuator::HorNiceSlider;
+use fltk::prelude::ButtonExt;
 use fltk::prelude::GroupExt;
 use fltk::prelude::InputExt;
 use fltk::prelude::MenuExt;
+use fltk::prelude::ValuatorExt;
 use fltk::prelude::WidgetBase;
 use fltk::prelude::WidgetExt;
-use fltk::text::TextBuffer;
-use fltk::text::TextDisplay;
+use fltk::prelude::WindowExt;
 use fltk::widget_extends;
 use fltk::window::Window;
 use fltk_theme::widget_themes;
 use grid::Grid;
+use std::path::Path;
+use std::path::PathBuf;
 
+use crate::district_list::DistrictList;
+use crate::export::StitchedCity;
+use crate::export::largest_neighborhood;
+use crate::export::stitch_neighborhoods;
+use crate::error::CityGenError;
+use crate::error::get_max_district_len;
+use crate::error::get_max_neighborhood_len;
+use crate::error::get_min_neighborhood_len;
+use crate::error::parse_dimension;
+use crate::grouping::Building;
+use crate::grouping::BuildingType;
+use crate::grouping::Coord;
+use crate::grouping::Direction;
+use crate::grouping::DistrictLookup;
 use crate::grouping::GroupInstance;
 use crate::grouping::Grouping;
+use crate::palette::BuildingPalette;
+use crate::map_view::MapCell;
+use crate::map_view::MapView;
+use crate::map_view::OverviewMap;
+use crate::map_view::get_label_color;
+use crate::palette::get_palette_presets;
+use crate::settings::AppSettings;
+use crate::theme::get_color_theme_names;
+use crate::theme::get_schemes;
 
+/// # MenuChoice
+/// 
+/// The messages sent from the gui to the main loop whenever the user does something.
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MenuChoice {
-	Choice1,
-	Choice2,
+	/// load a city from a save file
+	OpenCity,
+	/// save the city to a file
+	SaveCity,
+	/// open the city at the index of the recent files
+	OpenRecent(usize),
+	/// the main window changed size
 	Resize,
+	/// switch to the widget scheme at the index of get_schemes()
+	SetScheme(usize),
+	/// switch to the color theme at the index of get_color_theme_names()
+	SetColorTheme(usize),
+	/// change the color of a district in the list
 	SetColor,
+	/// add a district to the list
 	AddDistrict,
+	/// remove a district from the list
 	RemoveDistrict,
+	/// recolor the district list from a preset
+	DistrictPreset,
+	/// start generating a new city
 	GenerateDistricts,
+	/// stop the generation running in the background
+	CancelGeneration,
+	/// change the color of a building type
+	SetBuildingColor,
+	/// generate building colors from a new random seed
+	RandomizeBuildingColors,
+	/// generate building colors from the seed the user typed in
+	ApplyPaletteSeed,
+	/// go back to the default building colors
+	DefaultBuildingColors,
+	/// use a preset for building colors
+	BuildingPreset,
+	/// save an image of the whole city
+	ExportCityImage,
+	/// save every building in the city to a csv file
+	ExportCityCsv,
+	/// save an animation of the districts growing
+	ExportGrowthGif,
+	/// start or pause playing back how the districts grew
+	PlayGrowth,
+	/// show the next step of how the districts grew
+	StepGrowth,
+	/// time to show the next step while playing back district growth
+	GrowthTick,
+	/// show the neighborhood of the district cell at the coordinate
+	ShowNeighborhood(Coord),
+	/// show the neighborhood next to the shown one in the direction
+	ShowAdjacentNeighborhood(Direction),
+	/// paint the district cell at the coordinate with the chosen district
+	PaintDistrictCell(Coord),
+	/// the mouse was released after painting district cells
+	PaintDistrictsFinished,
+	/// stretch the selection of district cells to include the coordinate
+	SelectDistrictCell(Coord),
+	/// the mouse was released after selecting district cells
+	SelectDistrictsFinished,
+	/// show the neighborhoods of the selected district cells together in one window
+	ViewSelectedTogether,
+	/// show the neighborhoods of the selected district cells each in their own window
+	OpenSelectedSeparately,
+	/// change the building at the coordinate of the shown neighborhood to the chosen building type
+	PaintBuilding(Coord),
+	/// the mouse was released after changing buildings
+	PaintBuildingsFinished,
+	/// rename the selected district to whatever is typed in the district list
+	RenameDistrict,
+	/// move the selected districts up the list
+	MoveDistrictsUp,
+	/// move the selected districts down the list
+	MoveDistrictsDown,
+	/// toggle the lock on the district cell at the coordinate
+	LockDistrictCell(Coord),
+	/// toggle the lock on the building at the coordinate of the shown neighborhood
+	LockBuilding(Coord),
+	/// lock or unlock the shown neighborhood to match the checkbox
+	ToggleNeighborhoodLock,
+	/// lock or unlock the selected districts
+	LockDistrict,
+	/// regenerate everything that isn't locked
+	RegenerateUnlocked,
+	/// undo the most recent edit
+	Undo,
+	/// redo the most recently undone edit
+	Redo,
+	/// the mouse moved onto a different district cell, or off the district map if None
+	InspectDistrictCell(Option<Coord>),
+	/// the mouse moved onto a different building of the shown neighborhood, or off the neighborhood map if None
+	InspectBuilding(Option<Coord>),
+	/// the mouse moved onto a different building of the whole city map, or off the map if None
+	InspectCityBuilding(Option<Coord>),
+	/// give the building at the coordinate of the shown neighborhood a name and notes
+	EditBuildingInfo(Coord),
 }//end enum MenuChoice
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct GUI<'a> {
-	/// reference to application object that everything fits inside
+	/// reference to application object that everything fits inside.
+	/// Nothing reads it yet, but holding it keeps the gui from outliving the app.
+	#[allow(dead_code)]
 	pub application:&'a App,
 	/// the main window of the application
 	pub main_window:Window,
 	/// send messages for menu events
-	pub menu_msg_sender:Sender<String>,
+	pub menu_msg_sender:Sender<MenuChoice>,
 	// receive messages for menu events
-	pub menu_msg_receiver:Receiver<String>,
+	pub menu_msg_receiver:Receiver<MenuChoice>,
 	/// the menu bar at the top
 	pub top_menu:SysMenuBar,
-	/// the struct handling the 2d array of buttons for district representation
-	pub grid_buttons:Grid<Button>,
-	/// the struct handling the grid of buttons for districts
-	pub grid_flex: FlexGrid,
-	/// the struct handling the grid of buttons for neighborhoods
-	pub neighborhood_flex: FlexGrid,
+	/// the zoomable map showing the districts
+	pub district_map: MapView,
+	/// the zoomable map showing the buildings of a neighborhood
+	pub neighborhood_map: MapView,
+	/// the zoomable map showing every building in the city at once
+	pub city_map: MapView,
+	/// the color key shown next to the district grid
+	pub district_legend: Legend,
+	/// the color key shown next to the neighborhood grid
+	pub building_legend: Legend,
+	/// the color key shown next to the whole city map
+	pub city_legend: Legend,
+	/// the details of the district cell under the mouse, shown under the district key
+	pub district_inspector: Inspector,
+	/// the details of the building under the mouse, shown under the building key
+	pub building_inspector: Inspector,
+	/// the details of the building under the mouse on the whole city map
+	pub city_inspector: Inspector,
 	/// group holding the various tabs
 	pub tabs:Tabs,
 	/// group holding the settings for generation
 	pub settings_tab:Group,
+	/// group inside the settings tab holding every setting, which keeps its size and gets centered when the window is resized
+	settings_panel:Group,
 	/// group holding the display of generated districts
 	pub districts_tab:Group,
 	/// group holding the display of generated districts
 	pub neighborhood_tab:Group,
+	/// group holding the display of every building in the city
+	pub city_tab:Group,
 	/// the list of groupings that we'll generate districts from, each grouping is a district
 	pub districts:Vec<Grouping>,
-	/// The text buffer for displaying our list of the districts
-	pub districts_list_buffer:TextBuffer,
+	/// the id to give the next new district, so that no two districts ever share an id
+	next_district_id:u32,
+	/// the list of districts on the settings tab
+	district_list:DistrictList,
 	/// the input for number of rows of districts to generate
 	districts_rows_input:IntInput,
 	/// The input for number of columns of districts to generate
@@ -80,17 +223,157 @@ pub struct GUI<'a> {
 	neighborhood_rows_input:IntInput,
 	/// The input for number of columsn of neighborhoods to generate
 	neighborhood_cols_input:IntInput,
+	/// The input for the seed used to generate building colors
+	palette_seed_input:IntInput,
+	/// The input for the seed used to generate the city, which is random if left empty
+	city_seed_input:IntInput,
+	/// Whether to leave neighborhoods ungenerated until they're viewed or exported
+	lazy_nhoods_check:CheckButton,
+	/// The (rows, cols) of each neighborhood on the city map, so single neighborhoods can be redrawn in place
+	city_nhood_dims:Option<(usize,usize)>,
+	/// The button that starts generating a city
+	gen_districts_button:Button,
+	/// The button that cancels generation running in the background
+	cancel_gen_button:Button,
+	/// The bar showing how far along generation is
+	gen_progress:Progress,
+	/// Chooses what clicking on the district map does
+	district_tool_choice:Choice,
+	/// Chooses which district to paint with
+	paint_district_choice:Choice,
+	/// Whether to regenerate the neighborhood of each painted cell
+	regen_on_paint_check:CheckButton,
+	/// Chooses what clicking on the neighborhood map does
+	building_tool_choice:Choice,
+	/// Chooses which building type to place
+	paint_building_choice:Choice,
+	/// Whether the shown neighborhood is locked
+	nhood_lock_check:CheckButton,
+	/// The buttons that move to the neighborhood next to the shown one, along with the direction each moves in
+	nhood_nav_buttons:Vec<(Direction, Button)>,
+	/// Shows which district and cell the shown neighborhood is in
+	nhood_breadcrumb:Frame,
+	/// Shows the whole district grid with the shown neighborhood outlined
+	nhood_overview:OverviewMap,
+	/// The button that regenerates everything that isn't locked
+	regen_unlocked_button:Button,
+	/// The button that plays or pauses district growth playback
+	growth_play_button:Button,
+	/// The button that shows the next step of district growth
+	growth_step_button:Button,
+	/// How many steps of district growth to show each second
+	growth_speed_slider:HorNiceSlider,
+	/// The timer showing each step while growth is playing back
+	growth_timer:Option<app::TimeoutHandle>,
 }//end struct gui
 
 fn get_default_win_width() -> i32 {900}
 fn get_default_win_height() -> i32 {480}
 fn get_default_menu_height() -> i32 {20}
 fn get_default_tab_padding() -> i32 {20}
-fn get_default_grid_width() -> i32 {get_default_win_width()}
+fn get_default_toolbar_height() -> i32 {30}
+/// the index of the Lock option in the tool choices above the maps
+fn get_lock_tool_index() -> i32 {2}
+/// the index of the Select option in the tool choices above the district map
+fn get_select_tool_index() -> i32 {3}
+/// the size of each window opened to compare neighborhoods
+fn get_default_compare_window_size() -> (i32,i32) {(500,500)}
+/// how far each window opened to compare neighborhoods is moved from the last one, so they don't stack exactly
+fn get_compare_window_cascade() -> i32 {25}
+fn get_default_legend_width() -> i32 {150}
+fn get_default_grid_width() -> i32 {get_default_win_width() - get_default_legend_width()}
 fn get_default_grid_height() -> i32 {get_default_win_height()-get_default_menu_height() - get_default_tab_padding()}
-fn get_max_grid_button_width() -> i32 {30}
-fn get_max_grid_button_height() -> i32 {15}
-fn get_max_luminance_for_white_label() -> f32 {100.0}
+/// the height of the inspector panel under each legend
+fn get_default_inspector_height() -> i32 {150}
+/// the height of the overview of the district grid on the neighborhood tab, between the legend and inspector
+fn get_default_overview_height() -> i32 {110}
+
+/// # escape_menu_label(label)
+/// 
+/// escapes the characters that fltk menus would otherwise treat specially, such as / making a submenu
+fn escape_menu_label(label:&str) -> String {
+	label.replace('\\', "\\\\").replace('/', "\\/").replace('&', "&&")
+}//end escape_menu_label(label)
+
+/// # get_scheme_menu_path(name)
+/// 
+/// the path of the View menu item for the widget scheme called name
+fn get_scheme_menu_path(name:&str) -> String {
+	format!("&View/&Scheme/{}", name)
+}//end get_scheme_menu_path(name)
+
+/// # get_color_theme_menu_path(name)
+/// 
+/// the path of the View menu item for the color theme called name
+fn get_color_theme_menu_path(name:&str) -> String {
+	format!("&View/&Colors/{}", name)
+}//end get_color_theme_menu_path(name)
+
+/// # district_map_cell(instance, lookup)
+/// 
+/// returns how the cell instance should look on the district map, using the color and name of its district found with lookup
+fn district_map_cell(instance:&GroupInstance, lookup:&DistrictLookup) -> MapCell {
+	let cell = district_id_map_cell(instance.district_id.unwrap_or(0), lookup);
+	return cell.with_locked(lookup.is_locked(instance));
+}//end district_map_cell(instance, lookup)
+
+/// # district_id_map_cell(district_id, lookup)
+/// 
+/// returns how a cell belonging to the district with district_id, or to no district if it's 0, should look on the district map
+fn district_id_map_cell(district_id:u32, lookup:&DistrictLookup) -> MapCell {
+	match lookup.by_id(district_id).filter(|_| district_id != 0) {
+		Some(district) => MapCell::new(district.rgb_color, district.name.clone()),
+		None => MapCell::new((0,0,0), "empty".to_string()),
+	}//end matching whether the cell has a district
+}//end district_id_map_cell(district_id, lookup)
+
+/// # neighborhood_title(grid, districts, coord)
+/// 
+/// returns a short description of where the neighborhood at coord in grid is, such as "Docks › Row 2, Column 3"
+fn neighborhood_title(grid:&Grid<GroupInstance>, districts:&[Grouping], coord:Coord) -> String {
+	let district_name = match grid.get(coord.row, coord.col).and_then(|instance| instance.district(districts)) {
+		Some(district) => district.name.as_str(),
+		None => "No district",
+	};//end matching whether the cell has a district
+	return format!("{} \u{203A} Row {}, Column {}", district_name, coord.row + 1, coord.col + 1);
+}//end neighborhood_title(grid, districts, coord)
+
+/// # describe_district(instance, districts)
+/// 
+/// returns lines for an inspector naming the district of instance, its color, and whether the cell is locked
+fn describe_district(instance:&GroupInstance, districts:&[Grouping]) -> Vec<String> {
+	let mut lines = match instance.district(districts) {
+		Some(district) => {
+			let (r, g, b) = district.rgb_color;
+			vec![
+				format!("District: {}", district.name),
+				format!("Color: #{:02X}{:02X}{:02X} ({}, {}, {})", r, g, b, r, g, b),
+			]
+		},
+		None => vec!["District: none".to_string()],
+	};//end matching whether the cell has a district
+	if instance.is_locked(districts) {lines.push("Locked".to_string());}
+	return lines;
+}//end describe_district(instance, districts)
+
+/// # describe_building(building)
+/// 
+/// returns lines for an inspector with the type of building, along with its name, notes, and lock if it has them
+fn describe_building(building:&Building) -> Vec<String> {
+	let mut lines = vec![format!("Type: {}", building.build_type)];
+	if !building.name().is_empty() {lines.push(format!("Name: {}", building.name()));}
+	if !building.notes().is_empty() {lines.push(format!("Notes: {}", building.notes()));}
+	if building.locked {lines.push("Locked".to_string());}
+	return lines;
+}//end describe_building(building)
+
+/// # building_tooltip(building)
+/// 
+/// returns the short text shown next to the mouse over building, which is its type followed by its name if it has one
+fn building_tooltip(building:&Building) -> String {
+	if building.name().is_empty() {return building.build_type.to_string();}
+	return format!("{}: {}", building.build_type, building.name());
+}//end building_tooltip(building)
 
 impl GUI<'_> {
 	/// # default()
@@ -104,19 +387,49 @@ impl GUI<'_> {
 			menu_msg_sender: s1,
 			menu_msg_receiver: r1,
 			top_menu: SysMenuBar::default(),
-			grid_buttons: Grid::new(0,0),
-			grid_flex: FlexGrid::default(),
-			neighborhood_flex: FlexGrid::default(),
+			district_map: MapView::new(0, get_default_menu_height() + get_default_tab_padding() + get_default_toolbar_height(), get_default_grid_width(), get_default_grid_height() - get_default_toolbar_height()),
+			neighborhood_map: MapView::new(0, get_default_menu_height() + get_default_tab_padding() + get_default_toolbar_height(), get_default_grid_width(), get_default_grid_height() - get_default_toolbar_height()),
+			city_map: MapView::new(0, get_default_menu_height() + get_default_tab_padding(), get_default_grid_width(), get_default_grid_height()),
+			district_legend: Legend::default(),
+			building_legend: Legend::default(),
+			city_legend: Legend::default(),
+			district_inspector: Inspector::default(),
+			building_inspector: Inspector::default(),
+			city_inspector: Inspector::default(),
 			tabs: Tabs::default(),
 			settings_tab: Group::default(),
+			settings_panel: Group::default(),
 			districts_tab: Group::default(),
 			neighborhood_tab: Group::default(),
+			city_tab: Group::default(),
 			districts: Vec::new(),
-			districts_list_buffer: TextBuffer::default(),
+			next_district_id: 1,
+			district_list: DistrictList::new(0, 0, 100, 100, "", s1),
 			districts_rows_input: IntInput::default(),
 			districts_cols_input: IntInput::default(),
 			neighborhood_rows_input: IntInput::default(),
 			neighborhood_cols_input: IntInput::default(),
+			palette_seed_input: IntInput::default(),
+			city_seed_input: IntInput::default(),
+			lazy_nhoods_check: CheckButton::default(),
+			city_nhood_dims: None,
+			gen_districts_button: Button::default(),
+			cancel_gen_button: Button::default(),
+			gen_progress: Progress::default(),
+			district_tool_choice: Choice::default(),
+			paint_district_choice: Choice::default(),
+			regen_on_paint_check: CheckButton::default(),
+			building_tool_choice: Choice::default(),
+			paint_building_choice: Choice::default(),
+			nhood_lock_check: CheckButton::default(),
+			nhood_nav_buttons: Vec::new(),
+			nhood_breadcrumb: Frame::default(),
+			nhood_overview: OverviewMap::new(0, 0, 0, 0),
+			regen_unlocked_button: Button::default(),
+			growth_play_button: Button::default(),
+			growth_step_button: Button::default(),
+			growth_speed_slider: HorNiceSlider::default(),
+			growth_timer: None,
 		};//end struct construction
 		gui.set_default_properties();
 		return gui;
@@ -131,14 +444,26 @@ impl GUI<'_> {
 			.with_size(get_default_win_width(), get_default_win_height())
 			.with_label("CIS 536 City Generator");
 		self.main_window.make_resizable(true);
+		// the toolbars are laid out for the default size, so don't let the window get any smaller
+		self.main_window.size_range(get_default_win_width(), get_default_win_height(), 0, 0);
 		self.main_window.end();
+		// fltk stretches everything in proportion on its own, so lay things out properly once the resize is over
+		let sender = self.menu_msg_sender;
+		self.main_window.resize_callback(move |_, _, _, _, _| {
+			sender.send(MenuChoice::Resize);
+		});
 
 		// set default groupings
-		self.districts.push(Grouping::new("slum".to_string()).with_color((222,42,195)));
-		self.districts.push(Grouping::new("suburb".to_string()).with_color((114,222,42)));
-		self.districts.push(Grouping::new("adventuring".to_string()).with_color((227,0,0)));
-		self.districts.push(Grouping::new("financial".to_string()).with_color((255,250,105)));
-		self.districts.push(Grouping::new("business".to_string()).with_color((74,132,232)));
+		let id = self.take_district_id();
+		self.districts.push(Grouping::new("slum".to_string()).with_id(id).with_color((222,42,195)));
+		let id = self.take_district_id();
+		self.districts.push(Grouping::new("suburb".to_string()).with_id(id).with_color((114,222,42)));
+		let id = self.take_district_id();
+		self.districts.push(Grouping::new("adventuring".to_string()).with_id(id).with_color((227,0,0)));
+		let id = self.take_district_id();
+		self.districts.push(Grouping::new("financial".to_string()).with_id(id).with_color((255,250,105)));
+		let id = self.take_district_id();
+		self.districts.push(Grouping::new("business".to_string()).with_id(id).with_color((74,132,232)));
 
 		// top menu settings
 		self.top_menu = self.top_menu.clone()
@@ -156,6 +481,11 @@ impl GUI<'_> {
 		// settings tab
 		self.settings_tab = Group::new(0, self.tabs.y() + get_default_tab_padding(), self.tabs.width(), self.tabs.height(), "Settings");
 		self.settings_tab.end();
+		self.settings_tab.make_resizable(false);
+		self.settings_panel = Group::new(0, self.settings_tab.y(), get_default_win_width(), get_default_grid_height(), None);
+		self.settings_panel.end();
+		self.settings_panel.make_resizable(false);
+		self.settings_tab.add(&self.settings_panel);
 		self.tabs.add(&self.settings_tab);
 
 		// district tab
@@ -165,6 +495,14 @@ impl GUI<'_> {
 			.with_label("Districts")
 			.with_type(group::FlexType::Row);
 		self.districts_tab.end();
+		// update_layout() places everything on this tab, so fltk shouldn't stretch any of it
+		self.districts_tab.make_resizable(false);
+		self.initialize_district_toolbar();
+		self.districts_tab.add(&*self.district_map);
+		self.district_legend = Legend::default();
+		self.districts_tab.add(&*self.district_legend);
+		self.district_inspector = Inspector::default();
+		self.districts_tab.add(&*self.district_inspector);
 		self.tabs.add(&self.districts_tab);
 
 		// neighborhood tab
@@ -173,10 +511,166 @@ impl GUI<'_> {
 			.with_size(self.tabs.width(), self.tabs.height())
 			.with_label("Neighborhood");
 		self.neighborhood_tab.end();
-		self.neighborhood_flex = FlexGrid::default();
-		self.neighborhood_tab.add(&*self.neighborhood_flex);
+		self.neighborhood_tab.make_resizable(false);
+		self.initialize_neighborhood_toolbar();
+		self.neighborhood_tab.add(&*self.neighborhood_map);
+		self.building_legend = Legend::new(get_default_grid_height() - get_default_inspector_height() - get_default_overview_height());
+		self.neighborhood_tab.add(&*self.building_legend);
+		self.nhood_overview = OverviewMap::new(get_default_grid_width(), self.building_legend.y() + self.building_legend.h(), get_default_legend_width(), get_default_overview_height());
+		self.nhood_overview.set_tooltip("Where the shown neighborhood is in the city. Click a cell to go there.");
+		self.neighborhood_tab.add(&*self.nhood_overview);
+		self.building_inspector = Inspector::default();
+		self.neighborhood_tab.add(&*self.building_inspector);
 		self.tabs.add(&self.neighborhood_tab);
+
+		// whole city tab
+		self.city_tab = Group::default()
+			.with_pos(0, self.tabs.y() + get_default_tab_padding())
+			.with_size(self.tabs.width(), self.tabs.height())
+			.with_label("Whole City");
+		self.city_tab.end();
+		self.city_tab.make_resizable(false);
+		self.city_tab.add(&*self.city_map);
+		self.city_map.set_show_borders(true);
+		self.city_legend = Legend::default();
+		self.city_tab.add(&*self.city_legend);
+		self.city_inspector = Inspector::default();
+		self.city_tab.add(&*self.city_inspector);
+		self.tabs.add(&self.city_tab);
+
+		// clicking a district cell asks to see its neighborhood
+		let sender = self.menu_msg_sender;
+		self.district_map.set_click_callback(move |coord:Coord| {
+			sender.send(MenuChoice::ShowNeighborhood(coord));
+		});
+		// painting or locking district cells gets handled in the main loop
+		let paint_sender = self.menu_msg_sender;
+		let done_sender = self.menu_msg_sender;
+		let district_tool = self.district_tool_choice.clone();
+		let done_district_tool = self.district_tool_choice.clone();
+		self.district_map.set_paint_callbacks(
+			move |coord:Coord| {
+				if district_tool.value() == get_lock_tool_index() {paint_sender.send(MenuChoice::LockDistrictCell(coord));}
+				else if district_tool.value() == get_select_tool_index() {paint_sender.send(MenuChoice::SelectDistrictCell(coord));}
+				else {paint_sender.send(MenuChoice::PaintDistrictCell(coord));}
+			},
+			move || {
+				if done_district_tool.value() == get_select_tool_index() {done_sender.send(MenuChoice::SelectDistrictsFinished);}
+				else {done_sender.send(MenuChoice::PaintDistrictsFinished);}
+			},
+		);
+		// same for changing or locking buildings in the neighborhood
+		let paint_sender = self.menu_msg_sender;
+		let done_sender = self.menu_msg_sender;
+		let building_tool = self.building_tool_choice.clone();
+		self.neighborhood_map.set_paint_callbacks(
+			move |coord:Coord| {
+				if building_tool.value() == get_lock_tool_index() {paint_sender.send(MenuChoice::LockBuilding(coord));}
+				else {paint_sender.send(MenuChoice::PaintBuilding(coord));}
+			},
+			move || done_sender.send(MenuChoice::PaintBuildingsFinished),
+		);
+		// clicking a cell of the overview jumps to its neighborhood
+		let sender = self.menu_msg_sender;
+		self.nhood_overview.set_click_callback(move |coord:Coord| {
+			sender.send(MenuChoice::ShowNeighborhood(coord));
+		});
+		// clicking a building while viewing asks for its name and notes
+		let sender = self.menu_msg_sender;
+		self.neighborhood_map.set_click_callback(move |coord:Coord| {
+			sender.send(MenuChoice::EditBuildingInfo(coord));
+		});
+		// hovering over any of the maps fills in the inspector next to it
+		let sender = self.menu_msg_sender;
+		self.district_map.set_hover_callback(move |coord:Option<Coord>| {
+			sender.send(MenuChoice::InspectDistrictCell(coord));
+		});
+		let sender = self.menu_msg_sender;
+		self.neighborhood_map.set_hover_callback(move |coord:Option<Coord>| {
+			sender.send(MenuChoice::InspectBuilding(coord));
+		});
+		let sender = self.menu_msg_sender;
+		self.city_map.set_hover_callback(move |coord:Option<Coord>| {
+			sender.send(MenuChoice::InspectCityBuilding(coord));
+		});
 	}//end set_default_properties
+
+	/// # update_layout(&mut self)
+	/// 
+	/// fits everything to the current size of the main window.
+	/// The maps grow and shrink to fill the window, while the toolbars, keys, and inspectors keep their size, and the settings stay centered.
+	pub fn update_layout(&mut self) {
+		let (win_w, win_h) = (self.main_window.w(), self.main_window.h());
+		self.top_menu.resize(0, 0, win_w, get_default_menu_height());
+		self.tabs.resize(0, get_default_menu_height(), win_w, win_h - get_default_menu_height());
+		let tab_y = get_default_menu_height() + get_default_tab_padding();
+		let tab_h = win_h - tab_y;
+		for tab in [&mut self.settings_tab, &mut self.districts_tab, &mut self.neighborhood_tab, &mut self.city_tab] {
+			tab.resize(0, tab_y, win_w, tab_h);
+		}//end fitting each tab to the window
+		let (panel_w, panel_h) = (self.settings_panel.w(), self.settings_panel.h());
+		self.settings_panel.resize(((win_w - panel_w) / 2).max(0), tab_y, panel_w, panel_h);
+
+		// maps fill whatever the legend column and toolbars don't use
+		let legend_x = win_w - get_default_legend_width();
+		let toolbar_h = get_default_toolbar_height();
+		self.district_map.set_area(0, tab_y + toolbar_h, legend_x, tab_h - toolbar_h);
+		self.neighborhood_map.set_area(0, tab_y + toolbar_h, legend_x, tab_h - toolbar_h);
+		self.city_map.set_area(0, tab_y, legend_x, tab_h);
+
+		// legends stretch down to the inspectors, which stay at the bottom
+		let legend_w = get_default_legend_width();
+		let inspector_h = get_default_inspector_height();
+		let inspector_y = tab_y + tab_h - inspector_h;
+		let overview_h = get_default_overview_height();
+		self.district_legend.resize(legend_x, tab_y, legend_w, tab_h - inspector_h);
+		self.city_legend.resize(legend_x, tab_y, legend_w, tab_h - inspector_h);
+		self.building_legend.resize(legend_x, tab_y, legend_w, tab_h - inspector_h - overview_h);
+		self.nhood_overview.resize(legend_x, inspector_y - overview_h, legend_w, overview_h);
+		for inspector in [&mut self.district_inspector, &mut self.building_inspector, &mut self.city_inspector] {
+			inspector.resize(legend_x, inspector_y, legend_w, inspector_h);
+		}//end moving each inspector to the bottom of its tab
+		self.main_window.redraw();
+	}//end update_layout(&mut self)
+
+	/// # initialize_district_toolbar(&mut self)
+	/// 
+	/// sets up the row of controls above the district map
+	fn initialize_district_toolbar(&mut self) {
+		let toolbar_y = self.districts_tab.y() + 4;
+		self.district_tool_choice = Choice::new(40, toolbar_y, 90, 22, "Tool");
+		self.district_tool_choice.add_choice("View|Paint|Lock|Select");
+		self.district_tool_choice.set_value(0);
+		self.district_tool_choice.set_tooltip("Lock keeps a cell's district and neighborhood when regenerating");
+		let mut district_map = self.district_map.clone();
+		self.district_tool_choice.set_callback(move |choice| {
+			district_map.set_paint_mode(choice.value() > 0);
+		});
+		self.paint_district_choice = Choice::new(200, toolbar_y, 180, 22, "District");
+		self.regen_on_paint_check = CheckButton::new(400, toolbar_y, 200, 22, "Regenerate neighborhoods");
+		self.districts_tab.add(&self.district_tool_choice);
+		self.districts_tab.add(&self.paint_district_choice);
+		self.districts_tab.add(&self.regen_on_paint_check);
+		self.update_paint_district_choice();
+
+		// controls for playing back how the districts grew
+		self.growth_play_button = Button::new(605, toolbar_y, 55, 22, "Play");
+		self.growth_play_button.emit(self.menu_msg_sender, MenuChoice::PlayGrowth);
+		self.growth_play_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		self.growth_play_button.set_tooltip("Watch the districts grow, one step at a time");
+		self.growth_step_button = Button::new(665, toolbar_y, 50, 22, "Step");
+		self.growth_step_button.emit(self.menu_msg_sender, MenuChoice::StepGrowth);
+		self.growth_step_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		self.growth_speed_slider = HorNiceSlider::new(765, toolbar_y, 125, 22, "Speed");
+		self.growth_speed_slider.set_align(Align::Left);
+		self.growth_speed_slider.set_bounds(1.0, 60.0);
+		self.growth_speed_slider.set_step(1.0, 1);
+		self.growth_speed_slider.set_value(10.0);
+		self.growth_speed_slider.set_tooltip("Steps shown per second");
+		self.districts_tab.add(&self.growth_play_button);
+		self.districts_tab.add(&self.growth_step_button);
+		self.districts_tab.add(&self.growth_speed_slider);
+	}//end initialize_district_toolbar(&mut self)
 	
 	/// # switch_tab(&mut self, tab_idx:i32)
 	pub fn switch_tab(&mut self, tab_idx:u8) {
@@ -197,6 +691,10 @@ impl GUI<'_> {
 				if cur_vis.is_same(&self.neighborhood_tab) {return;}
 				self.tabs.set_value(&self.neighborhood_tab).expect("tabs");
 			},
+			3 => {
+				if cur_vis.is_same(&self.city_tab) {return;}
+				self.tabs.set_value(&self.city_tab).expect("tabs");
+			},
 			_ => {
 				// do nothing
 			}
@@ -204,6 +702,7 @@ impl GUI<'_> {
 		self.settings_tab.redraw();
 		self.districts_tab.redraw();
 		self.neighborhood_tab.redraw();
+		self.city_tab.redraw();
 	}//end switch_tab(&mut self, tab_idx)
 
 	/// # initialize_top_menu
@@ -212,111 +711,321 @@ impl GUI<'_> {
 	pub fn initialize_top_menu(&mut self) {
 		// set up all the emitters
 		self.top_menu.add_emit(
-			"&File/Choice1...\t",
-			Shortcut::Ctrl | 'n',
+			"&File/Open City...\t",
+			Shortcut::Ctrl | 'o',
 			menu::MenuFlag::Normal,
-			self.menu_msg_sender.clone(),
-			"MenuChoice::Choice1".to_string(),
+			self.menu_msg_sender,
+			MenuChoice::OpenCity,
 		);
+		self.update_recent_files_menu(&[]);
 		self.top_menu.add_emit(
-			"&File/Choice2...\t",
-			Shortcut::Ctrl | 'o',
+			"&File/Save City...\t",
+			Shortcut::Ctrl | 's',
 			menu::MenuFlag::Normal,
-			self.menu_msg_sender.clone(),
-			"MenuChoice::Choice2".to_string(),
+			self.menu_msg_sender,
+			MenuChoice::SaveCity,
+		);
+		self.top_menu.add_emit(
+			"&File/Export City Image...\t",
+			Shortcut::Ctrl | 'e',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::ExportCityImage,
+		);
+		self.top_menu.add_emit(
+			"&File/Export City CSV...\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::ExportCityCsv,
+		);
+		self.top_menu.add_emit(
+			"&File/Export Growth GIF...\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::ExportGrowthGif,
+		);
+		self.top_menu.add_emit(
+			"&Edit/Undo\t",
+			Shortcut::Ctrl | 'z',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::Undo,
+		);
+		self.top_menu.add_emit(
+			"&Edit/Redo\t",
+			Shortcut::Ctrl | 'y',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::Redo,
 		);
 		self.top_menu.add_emit(
 			"Regen",
 			Shortcut::Ctrl | 'r',
 			menu::MenuFlag::Normal,
-			self.menu_msg_sender.clone(),
-			"MenuChoice::GenerateDistricts".to_string(),
+			self.menu_msg_sender,
+			MenuChoice::GenerateDistricts,
+		);
+		self.top_menu.add_emit(
+			"Regen Unlocked",
+			Shortcut::Ctrl | Shortcut::Shift | 'r',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::RegenerateUnlocked,
+		);
+		self.top_menu.add_emit(
+			"&Neighborhoods/View Selected Together\t",
+			Shortcut::Ctrl | 'g',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::ViewSelectedTogether,
 		);
+		self.top_menu.add_emit(
+			"&Neighborhoods/Open Selected Separately\t",
+			Shortcut::Ctrl | Shortcut::Shift | 'g',
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::OpenSelectedSeparately,
+		);
+		self.top_menu.add_emit(
+			"&Palette/Set Building Color...\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::SetBuildingColor,
+		);
+		self.top_menu.add_emit(
+			"&Palette/Building Preset...\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::BuildingPreset,
+		);
+		self.top_menu.add_emit(
+			"&Palette/District Preset...\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::DistrictPreset,
+		);
+		self.top_menu.add_emit(
+			"&Palette/Randomize Building Colors\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::RandomizeBuildingColors,
+		);
+		self.top_menu.add_emit(
+			"&Palette/Default Building Colors\t",
+			Shortcut::None,
+			menu::MenuFlag::Normal,
+			self.menu_msg_sender,
+			MenuChoice::DefaultBuildingColors,
+		);
+		for (index, (name, _)) in get_schemes().into_iter().enumerate() {
+			self.top_menu.add_emit(
+				&get_scheme_menu_path(name),
+				Shortcut::None,
+				menu::MenuFlag::Radio,
+				self.menu_msg_sender,
+				MenuChoice::SetScheme(index),
+			);
+		}//end adding an item for each widget scheme
+		for (index, name) in get_color_theme_names().into_iter().enumerate() {
+			self.top_menu.add_emit(
+				&get_color_theme_menu_path(name),
+				Shortcut::None,
+				menu::MenuFlag::Radio,
+				self.menu_msg_sender,
+				MenuChoice::SetColorTheme(index),
+			);
+		}//end adding an item for each color theme
 	}//end initialize_top_menu
+
+	/// # check_theme_items(&mut self, scheme, color_theme)
+	/// 
+	/// marks the View menu items for the named scheme and color theme as the chosen ones, such as after loading them from settings
+	pub fn check_theme_items(&mut self, scheme:&str, color_theme:&str) {
+		let scheme_paths = get_schemes().into_iter().map(|(name, _)| (get_scheme_menu_path(name), name == scheme));
+		let color_theme_paths = get_color_theme_names().into_iter().map(|name| (get_color_theme_menu_path(name), name == color_theme));
+		for (path, is_chosen) in scheme_paths.chain(color_theme_paths) {
+			if let Some(mut item) = self.top_menu.find_item(&path) {
+				if is_chosen {item.set();}
+				else {item.clear();}
+			}//end if there's an item for path
+		}//end checking or unchecking each item
+	}//end check_theme_items(&mut self, scheme, color_theme)
 	
 	/// # update_grid(self, ext_grid)
 	/// 
-	/// Updates the grid view, and also initializes
+	/// Updates the district map to show the districts in ext_grid
 	pub fn update_grid(&mut self, ext_grid:&Grid<GroupInstance>) {
-		//self.grid_flex = FlexGrid::default();
-		// clear previous nonsense
-		if self.grid_flex.children() > 0 {
-			self.grid_flex.clear_inner_flexes();
-		}//end if we have previous stuff to take care of
-		// create grid of buttons
-		let mut button_grid: Grid<Button> = Grid::new(ext_grid.rows(), ext_grid.cols());
-		// set size of buttons
-		let button_width = get_default_grid_width() / ext_grid.cols() as i32;
-		let button_height = get_default_grid_height() / ext_grid.rows() as i32;
-		let show_label = button_width > get_max_grid_button_width() && button_height > get_max_grid_button_height();
-		// print button size for debugging purposes
-		println!("district button sizes w:{}\th:{}\tshow label:{}", button_width, button_height, show_label);
-		// start looping over everything
-		for row_index in 0..button_grid.rows() {
-			// let mut temp_vec: Vec<Button> = Vec::new();
-			for col_index in 0..button_grid.cols() {
-				// reference variable for this group instance
-				let this_group = ext_grid.get(row_index as usize, col_index as usize).unwrap();
-				// get mutable reference for spot in grid
-				let this_button_spot = button_grid.get_mut(row_index, col_index).unwrap();
-
-				// figure out name limits
-				let mut shrunk;
-				if this_group.group.is_some() {
-					shrunk = this_group.group.as_ref().unwrap().name.clone();
-				}//end if the instance is categorized
-				else { shrunk = "empty".to_string(); }
-				if show_label {
-					if shrunk.len() > 9 {
-						shrunk = shrunk[0..9].to_string();
-					}//end if we need to shrink the name
-				}//end if we should show the label at al
-				else { shrunk = "".to_string(); }
-
-				// make the button, positioned correctly
-				let mut new_button = Button::default()
-					.with_size(button_width, button_height)
-					.with_label(&shrunk);
-
-				// set button color based on grouping
-				if this_group.group.is_some() {
-					let c = this_group.group.as_ref().unwrap().rgb_color;
-					// new_button.set_label_color(Color::from_rgb(c.0, c.1, c.2));
-					new_button.set_color(Color::from_rgb(c.0, c.1, c.2));
-					// do some calculations to determine if we should change label color
-					let luminance = 0.299*c.0 as f32 + 0.587*c.1 as f32 + 0.114*c.2 as f32;
-					if luminance > get_max_luminance_for_white_label() {
-						new_button.set_label_color(Color::Black);
-					}//end if label color should be black
-					else { new_button.set_label_color(Color::White); }
-				}//end if this grouped instance is actually grouped
-				
-				// add click event/emission
-				new_button.emit(self.menu_msg_sender.clone(), format!("{},{}",this_group.coord.unwrap().row,this_group.coord.unwrap().col));
-
-				// update mutable refernce with our new button
-				*this_button_spot = new_button;
-			}//end converting each string into a button
+		let mut cells: Grid<MapCell> = Grid::new(ext_grid.rows(), ext_grid.cols());
+		let lookup = DistrictLookup::new(&self.districts);
+		for row_index in 0..ext_grid.rows() {
+			for col_index in 0..ext_grid.cols() {
+				// set cell color and label based on grouping
+				*cells.get_mut(row_index, col_index).unwrap() = district_map_cell(ext_grid.get(row_index, col_index).unwrap(), &lookup);
+			}//end converting each instance into a cell
 		}//end going through each row
 
-		// save our hard-earned button grid in our struct
-		self.grid_buttons = button_grid;
-		// make the flex grid
-		self.grid_flex.initialize_flex(ext_grid.rows(), ext_grid.cols());
-		self.grid_flex.fill_flex(&self.grid_buttons);
-		// reposition flex grid because it likes to get lost (also screws up resizing for some ungodly reason)
-		// self.grid_flex.outer_flex.resize(0 - button_width / 2, self.districts_tab.y(), self.grid_flex.outer_flex.width() + button_width / 2, self.grid_flex.outer_flex.height());
-		// actually make the grid show up
-		if self.districts_tab.children() < 1 {
-			self.districts_tab.add(&*self.grid_flex);
-		}//end to tab if not there already
-		else {
-			self.districts_tab.add(&*self.grid_flex);
-			self.districts_tab.redraw();
-		}
-		self.grid_flex.recalc();
-		self.grid_flex.redraw();
-	}//end initialize_grid
+		self.district_map.set_cells(cells);
+		// show the colors of the districts we just displayed
+		self.update_district_legend();
+	}//end update_grid
+
+	/// # update_district_cells(&mut self, ext_grid, coords)
+	/// 
+	/// redraws the cells at coords on the district map after they were edited, without resetting the view
+	pub fn update_district_cells(&mut self, ext_grid:&Grid<GroupInstance>, coords:&[Coord]) {
+		let lookup = DistrictLookup::new(&self.districts);
+		for coord in coords {
+			if let Some(this_group) = ext_grid.get(coord.row, coord.col) {
+				self.district_map.update_cell(*coord, district_map_cell(this_group, &lookup));
+			}//end if coord is in the grid
+		}//end redrawing each cell
+	}//end update_district_cells(&mut self, ext_grid, coords)
+
+	/// # refresh_district_map(&mut self, ext_grid)
+	/// 
+	/// redraws every cell of the district map without resetting the view, for after the district list changes.
+	/// Cells only refer to their district by id, so renaming, recoloring or removing a district changes how they look.
+	pub fn refresh_district_map(&mut self, ext_grid:&Grid<GroupInstance>) {
+		let lookup = DistrictLookup::new(&self.districts);
+		for row in 0..ext_grid.rows() {
+			for col in 0..ext_grid.cols() {
+				self.district_map.update_cell(Coord::new(row, col), district_map_cell(ext_grid.get(row, col).unwrap(), &lookup));
+			}//end looping over columns
+		}//end looping over rows
+		self.update_district_legend();
+	}//end refresh_district_map(&mut self, ext_grid)
+
+	/// # update_city_tab(&mut self, city_grid, palette)
+	/// 
+	/// updates the whole city map to show every building in city_grid side by side, with lines between districts.
+	/// Each neighborhood gets as much space as the largest one, and any space it doesn't fill is shown as empty.
+	pub fn update_city_tab(&mut self, city_grid:&Grid<GroupInstance>, palette:&BuildingPalette) {
+		let (nhood_rows, nhood_cols) = largest_neighborhood(city_grid, 0..city_grid.rows(), 0..city_grid.cols());
+		let empty = Building::default();
+		self.city_map.fill_cells(city_grid.rows() * nhood_rows, city_grid.cols() * nhood_cols, |row, col| {
+			let nhood = city_grid.get(row / nhood_rows, col / nhood_cols).unwrap();
+			let building = nhood.sub_grid.get(row % nhood_rows, col % nhood_cols).unwrap_or(&empty);
+			// district ids start at 1, leaving 0 for no district
+			MapCell::new(building.rgb_color, building.build_type.to_string())
+				.with_region(nhood.district_id.unwrap_or(0))
+		});
+		self.city_legend.set_entries("Building Key", &palette.legend_entries());
+		// clicking a building asks to see the neighborhood it's in
+		let sender = self.menu_msg_sender;
+		self.city_map.set_click_callback(move |coord:Coord| {
+			sender.send(MenuChoice::ShowNeighborhood(Coord::new(coord.row / nhood_rows, coord.col / nhood_cols)));
+		});
+		self.city_nhood_dims = Some((nhood_rows, nhood_cols));
+	}//end update_city_tab(&mut self, city_grid, palette)
+
+	/// # update_city_neighborhoods(&mut self, city_grid, coords, palette)
+	/// 
+	/// redraws just the neighborhoods at coords on the city map, without resetting the view.
+	/// Falls back to redrawing the whole map with update_city_tab() if one of them no longer fits in the space each neighborhood is shown in.
+	pub fn update_city_neighborhoods(&mut self, city_grid:&Grid<GroupInstance>, coords:&[Coord], palette:&BuildingPalette) {
+		for coord in coords {
+			if let Some(nhood) = city_grid.get(coord.row, coord.col) {
+				if !self.update_city_neighborhood(*coord, nhood) {
+					self.update_city_tab(city_grid, palette);
+					return;
+				}//end if the whole map needs redrawing
+			}//end if coord is in the grid
+		}//end redrawing each neighborhood
+	}//end update_city_neighborhoods(&mut self, city_grid, coords, palette)
+
+	/// # set_district_selection(&mut self, selection)
+	/// 
+	/// outlines the selected rectangle of district cells, from the top left to the bottom right coord, or removes the outline if selection is None
+	pub fn set_district_selection(&mut self, selection:Option<(Coord,Coord)>) {
+		self.district_map.set_selection(selection);
+	}//end set_district_selection(&mut self, selection)
+
+	/// # open_selected_together(&mut self, grid, selection)
+	/// 
+	/// opens a window showing the neighborhoods of every cell in the selected rectangle of grid side by side, with a line around each neighborhood
+	pub fn open_selected_together(&mut self, grid:&Grid<GroupInstance>, selection:(Coord,Coord)) {
+		let (first, last) = selection;
+		let stitched = stitch_neighborhoods(grid, first.row..last.row + 1, first.col..last.col + 1);
+		let nhoods_per_row = last.col - first.col + 1;
+		let title = format!("Neighborhoods in rows {}-{}, columns {}-{}", first.row + 1, last.row + 1, first.col + 1, last.col + 1);
+		self.open_compare_window(&title, &stitched, 0, |row, col| (row / stitched.nhood_rows * nhoods_per_row + col / stitched.nhood_cols + 1) as u32);
+	}//end open_selected_together(&mut self, grid, selection)
+
+	/// # open_selected_separately(&mut self, grid, selection)
+	/// 
+	/// opens one window for the neighborhood of each cell in the selected rectangle of grid, cascading them across the screen
+	pub fn open_selected_separately(&mut self, grid:&Grid<GroupInstance>, selection:(Coord,Coord)) {
+		let (first, last) = selection;
+		let mut index = 0;
+		for row in first.row..=last.row {
+			for col in first.col..=last.col {
+				let stitched = stitch_neighborhoods(grid, row..row + 1, col..col + 1);
+				let title = neighborhood_title(grid, &self.districts, Coord::new(row, col));
+				self.open_compare_window(&title, &stitched, index, |_, _| 0);
+				index += 1;
+			}//end looping over selected columns
+		}//end looping over selected rows
+	}//end open_selected_separately(&mut self, grid, selection)
+
+	/// # open_compare_window(&mut self, title, stitched, index, region_at)
+	/// 
+	/// opens a window with its own zoomable map of the buildings in stitched, which is closed and deleted separately from the main window.
+	/// region_at gives the region of the building at each row and col, with lines drawn between different regions.
+	/// index is how many windows were opened before this one at the same time, and moves this one over so they don't stack exactly.
+	fn open_compare_window<F: Fn(usize, usize) -> u32>(&mut self, title:&str, stitched:&StitchedCity, index:usize, region_at:F) {
+		let (win_w, win_h) = get_default_compare_window_size();
+		let cascade = get_compare_window_cascade() * (index as i32 + 1);
+		let mut window = Window::new(self.main_window.x() + cascade, self.main_window.y() + cascade, win_w, win_h, None);
+		// @ starts a symbol in fltk labels, so it has to be doubled to show up as is
+		window.set_label(&title.replace('@', "@@"));
+		let mut map = MapView::new(0, 0, win_w, win_h);
+		let buildings = &stitched.buildings;
+		map.fill_cells(buildings.rows(), buildings.cols(), |row, col| {
+			let building = buildings.get(row, col).unwrap();
+			MapCell::new(building.rgb_color, building.build_type.to_string())
+				.with_region(region_at(row, col))
+		});
+		map.set_show_borders(true);
+		window.end();
+		window.add(&*map);
+		window.resizable(&*map);
+		let mut resized_map = map.clone();
+		window.resize_callback(move |_, _, _, w, h| {
+			resized_map.set_area(0, 0, w, h);
+		});
+		window.set_callback(|window| {
+			window.hide();
+			Window::delete(window.clone());
+		});
+		window.show();
+	}//end open_compare_window(&mut self, title, stitched, index, region_at)
+
+	/// # update_city_neighborhood(&mut self, coord, nhood)
+	/// 
+	/// redraws just the buildings of the neighborhood at coord on the city map, without resetting the view. Any space nhood doesn't fill is shown as empty.
+	/// Returns false without changing anything if nhood is bigger than the space each neighborhood is shown in, in which case update_city_tab() should be used instead.
+	pub fn update_city_neighborhood(&mut self, coord:Coord, nhood:&GroupInstance) -> bool {
+		let (nhood_rows, nhood_cols) = match self.city_nhood_dims {
+			Some(dims) if nhood.sub_grid.rows() <= dims.0 && nhood.sub_grid.cols() <= dims.1 => dims,
+			_ => return false,
+		};//end matching whether nhood fits in the city map
+		let region = nhood.district_id.unwrap_or(0);
+		let empty = Building::default();
+		for sub_row in 0..nhood_rows {
+			for sub_col in 0..nhood_cols {
+				let building = nhood.sub_grid.get(sub_row, sub_col).unwrap_or(&empty);
+				let cell = MapCell::new(building.rgb_color, building.build_type.to_string()).with_region(region);
+				self.city_map.update_cell(Coord::new(coord.row * nhood_rows + sub_row, coord.col * nhood_cols + sub_col), cell);
+			}//end looping over neighborhood columns
+		}//end looping over neighborhood rows
+		true
+	}//end update_city_neighborhood(&mut self, coord, nhood)
 
 	/// # initialize_setting(self)
 	/// 
@@ -351,119 +1060,640 @@ impl GUI<'_> {
 			.with_size(130, 30)
 			.with_pos(50, 100)
 			.with_label("Set Color...");
-		set_color_button.emit(self.menu_msg_sender.clone(), "MenuChoice::SetColor".to_string());
+		set_color_button.emit(self.menu_msg_sender, MenuChoice::SetColor);
 		set_color_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
 		let mut add_district_button = Button::default()
 			.with_size(130, 30)
 			.below_of(&set_color_button, 10)
-			.with_label("Add District...");
-		add_district_button.emit(self.menu_msg_sender.clone(), "MenuChoice::AddDistrict".to_string());
+			.with_label("Add District");
+		add_district_button.emit(self.menu_msg_sender, MenuChoice::AddDistrict);
 		add_district_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
 		let mut remove_district_button = Button::default()
 			.with_size(130, 30)
 			.below_of(&add_district_button, 10)
-			.with_label("Remove District...");
-		remove_district_button.emit(self.menu_msg_sender.clone(), "MenuChoice::RemoveDistrict".to_string());
+			.with_label("Remove Districts");
+		remove_district_button.emit(self.menu_msg_sender, MenuChoice::RemoveDistrict);
 		remove_district_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
 
+		let mut district_preset_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&remove_district_button, 10)
+			.with_label("District Preset...");
+		district_preset_button.emit(self.menu_msg_sender, MenuChoice::DistrictPreset);
+		district_preset_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut lock_district_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&district_preset_button, 10)
+			.with_label("Lock Districts");
+		lock_district_button.emit(self.menu_msg_sender, MenuChoice::LockDistrict);
+		lock_district_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		lock_district_button.set_tooltip("Locked districts keep their cells and neighborhoods when regenerating");
+
 		// button for generating districts
-		let mut gen_districts_button = Button::default()
+		self.gen_districts_button = Button::default()
 			.with_size(150, 40)
-			.below_of(&remove_district_button, 50)
+			.below_of(&lock_district_button, 10)
 			.with_label("Generate Districts");
-		gen_districts_button.emit(self.menu_msg_sender.clone(), "MenuChoice::GenerateDistricts".to_string());
-		gen_districts_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
-
-		// scrollable text display for showing districts
-		let mut dist_list_disp = TextDisplay::default()
-			.with_size(270, 300)
-			.right_of(&set_color_button, 50)
-			.with_label("Districts to Generate");
-		// populate district list buffer to show districts
-		self.update_district_list_buf();
-		// update text display with buffer
-		dist_list_disp.set_buffer(self.districts_list_buffer.clone());
+		self.gen_districts_button.emit(self.menu_msg_sender, MenuChoice::GenerateDistricts);
+		self.gen_districts_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+
+		// progress bar and cancel button for generation
+		self.gen_progress = Progress::default()
+			.with_size(330, 25)
+			.below_of(&self.gen_districts_button, 10);
+		self.gen_progress.set_minimum(0.0);
+		self.gen_progress.set_maximum(1.0);
+		self.gen_progress.set_value(0.0);
+		self.gen_progress.set_selection_color(Color::from_rgb(74,132,232));
+		self.cancel_gen_button = Button::default()
+			.with_size(150, 40)
+			.right_of(&self.gen_districts_button, 30)
+			.with_label("Cancel");
+		self.cancel_gen_button.emit(self.menu_msg_sender, MenuChoice::CancelGeneration);
+		self.cancel_gen_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		self.cancel_gen_button.deactivate();
+		self.regen_unlocked_button = Button::default()
+			.with_size(150, 30)
+			.below_of(&self.gen_progress, 10)
+			.with_label("Regenerate Unlocked");
+		self.regen_unlocked_button.emit(self.menu_msg_sender, MenuChoice::RegenerateUnlocked);
+		self.regen_unlocked_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		self.regen_unlocked_button.set_tooltip("Regenerates everything except locked districts, cells and neighborhoods, which keep their buildings");
+		self.city_seed_input = IntInput::default()
+			.with_size(100, 30)
+			.right_of(&self.regen_unlocked_button, 80)
+			.with_label("City Seed");
+		self.city_seed_input.set_tooltip("Leave empty to generate a different city every time");
+		self.lazy_nhoods_check = CheckButton::default()
+			.with_size(250, 20)
+			.below_of(&self.city_seed_input, 10)
+			.with_label("Generate neighborhoods when viewed");
+		self.lazy_nhoods_check.set_tooltip("Saves time and memory for big cities by only generating each neighborhood the first time it's shown or exported");
+
+		// input and buttons for the building palette
+		self.palette_seed_input = IntInput::default()
+			.with_size(130, 30)
+			.with_pos(720, 100)
+			.with_label("Building Color Seed");
+		let mut apply_seed_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&self.palette_seed_input, 10)
+			.with_label("Apply Seed");
+		apply_seed_button.emit(self.menu_msg_sender, MenuChoice::ApplyPaletteSeed);
+		apply_seed_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut random_palette_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&apply_seed_button, 10)
+			.with_label("Randomize Colors");
+		random_palette_button.emit(self.menu_msg_sender, MenuChoice::RandomizeBuildingColors);
+		random_palette_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut default_palette_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&random_palette_button, 10)
+			.with_label("Default Colors");
+		default_palette_button.emit(self.menu_msg_sender, MenuChoice::DefaultBuildingColors);
+		default_palette_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut set_building_color_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&default_palette_button, 10)
+			.with_label("Set Building Color...");
+		set_building_color_button.emit(self.menu_msg_sender, MenuChoice::SetBuildingColor);
+		set_building_color_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+		let mut building_preset_button = Button::default()
+			.with_size(130, 30)
+			.below_of(&set_building_color_button, 10)
+			.with_label("Building Preset...");
+		building_preset_button.emit(self.menu_msg_sender, MenuChoice::BuildingPreset);
+		building_preset_button.set_frame(widget_themes::OS_HOVERED_UP_BOX);
+
+		// list for showing and selecting districts
+		self.district_list = DistrictList::new(set_color_button.x() + set_color_button.w() + 50, set_color_button.y(), 335, 300, "Districts to Generate", self.menu_msg_sender);
+		self.update_district_list();
 
 		// add everything to settings tab
-		self.settings_tab.add(&self.districts_rows_input);
-		self.settings_tab.add(&self.districts_cols_input);
-		self.settings_tab.add(&self.neighborhood_rows_input);
-		self.settings_tab.add(&self.neighborhood_cols_input);
-		self.settings_tab.add(&set_color_button);
-		self.settings_tab.add(&add_district_button);
-		self.settings_tab.add(&remove_district_button);
-		self.settings_tab.add(&gen_districts_button);
-		self.settings_tab.add(&dist_list_disp);
+		self.settings_panel.add(&self.districts_rows_input);
+		self.settings_panel.add(&self.districts_cols_input);
+		self.settings_panel.add(&self.neighborhood_rows_input);
+		self.settings_panel.add(&self.neighborhood_cols_input);
+		self.settings_panel.add(&set_color_button);
+		self.settings_panel.add(&add_district_button);
+		self.settings_panel.add(&remove_district_button);
+		self.settings_panel.add(&district_preset_button);
+		self.settings_panel.add(&lock_district_button);
+		self.settings_panel.add(&self.gen_districts_button);
+		self.settings_panel.add(&self.cancel_gen_button);
+		self.settings_panel.add(&self.regen_unlocked_button);
+		self.settings_panel.add(&self.city_seed_input);
+		self.settings_panel.add(&self.lazy_nhoods_check);
+		self.settings_panel.add(&self.gen_progress);
+		self.settings_panel.add(&*self.district_list);
+		self.settings_panel.add(&self.palette_seed_input);
+		self.settings_panel.add(&apply_seed_button);
+		self.settings_panel.add(&random_palette_button);
+		self.settings_panel.add(&default_palette_button);
+		self.settings_panel.add(&set_building_color_button);
+		self.settings_panel.add(&building_preset_button);
 	}//end initialize_settings(self)
 
 	/// # get_districts_dims(&self)
 	/// 
-	/// gets the number of rows and columns for district dimensions
-	pub fn get_districts_dims(&mut self) -> (usize, usize) {
-		// get the raw values
-		let mut rows_result: i32 = self.districts_rows_input.value().parse().expect("Should have been an int???");
-		let mut cols_result: i32 = self.districts_cols_input.value().parse().expect("Should have been an int???");
-		
-		// do a little input handling
-		rows_result = rows_result.max(self.districts.len() as i32);
-		cols_result = cols_result.max(self.districts.len() as i32);
+	/// gets the number of rows and columns for district dimensions.
+	/// Returns an error describing the problem if either input isn't a whole number from 1 to get_max_district_len().
+	pub fn get_districts_dims(&mut self) -> Result<(usize, usize), CityGenError> {
+		let rows = parse_dimension("district rows", &self.districts_rows_input.value(), 1, get_max_district_len())?;
+		let cols = parse_dimension("district columns", &self.districts_cols_input.value(), 1, get_max_district_len())?;
 
-		// make sure we update our text in case we handled input
-		self.districts_rows_input.set_value(&rows_result.to_string());
-		self.districts_cols_input.set_value(&cols_result.to_string());
+		// make sure we update our text in case there was extra whitespace
+		self.districts_rows_input.set_value(&rows.to_string());
+		self.districts_cols_input.set_value(&cols.to_string());
 
 		// return dims
-		(rows_result as usize, cols_result as usize)
+		Ok((rows, cols))
 	}//end get_districts_dims
 
 	/// # get_neighborhood_dims(&self)
 	/// 
-	/// gets the number of rows and columns for neighborhood dimensions
-	pub fn get_neighborhood_dims(&mut self) -> (usize, usize) {
-		// get the raw values
-		let mut rows_result: i32 = self.neighborhood_rows_input.value().parse().expect("Should have been an int???");
-		let mut cols_result: i32 = self.neighborhood_cols_input.value().parse().expect("Should have been an int???");
-		
-		// do a little input handling
-		rows_result = rows_result.max(3);
-		cols_result = cols_result.max(3);
+	/// gets the number of rows and columns for neighborhood dimensions.
+	/// Returns an error describing the problem if either input isn't a whole number from get_min_neighborhood_len() to get_max_neighborhood_len().
+	pub fn get_neighborhood_dims(&mut self) -> Result<(usize, usize), CityGenError> {
+		let rows = parse_dimension("neighborhood rows", &self.neighborhood_rows_input.value(), get_min_neighborhood_len(), get_max_neighborhood_len())?;
+		let cols = parse_dimension("neighborhood columns", &self.neighborhood_cols_input.value(), get_min_neighborhood_len(), get_max_neighborhood_len())?;
 
-		// make sure we update our text in case we handled input
-		self.neighborhood_rows_input.set_value(&rows_result.to_string());
-		self.neighborhood_cols_input.set_value(&cols_result.to_string());
+		// make sure we update our text in case there was extra whitespace
+		self.neighborhood_rows_input.set_value(&rows.to_string());
+		self.neighborhood_cols_input.set_value(&cols.to_string());
 
 		// return dims
-		(rows_result as usize, cols_result as usize)
+		Ok((rows, cols))
 	}//end get_neighborhood_dims
 
-	/// # update_district_list_buf
+	/// # set_generating(&mut self, generating)
+	/// 
+	/// switches which of the generate and cancel buttons can be clicked, depending on whether generation is running
+	pub fn set_generating(&mut self, generating:bool) {
+		if generating {
+			self.gen_districts_button.deactivate();
+			self.regen_unlocked_button.deactivate();
+			self.cancel_gen_button.activate();
+		}//end if generation just started
+		else {
+			self.gen_districts_button.activate();
+			self.regen_unlocked_button.activate();
+			self.cancel_gen_button.deactivate();
+		}//end else generation is over
+	}//end set_generating(&mut self, generating)
+
+	/// # set_generation_progress(&mut self, fraction, description)
 	/// 
-	/// updates the text buffer to show the list of districts
-	pub fn update_district_list_buf(&mut self) {
-		self.districts_list_buffer.set_text("");
+	/// updates the progress bar, where fraction goes from 0.0 to 1.0
+	pub fn set_generation_progress(&mut self, fraction:f64, description:&str) {
+		self.gen_progress.set_value(fraction);
+		self.gen_progress.set_label(description);
+		self.gen_progress.redraw();
+	}//end set_generation_progress(&mut self, fraction, description)
+
+	/// # get_palette_seed(&self)
+	/// 
+	/// gets the seed typed into the building color seed input, if there is a valid one
+	pub fn get_palette_seed(&self) -> Option<u64> {
+		self.palette_seed_input.value().parse().ok()
+	}//end get_palette_seed(&self)
+
+	/// # get_city_seed(&self)
+	/// 
+	/// gets the seed typed into the city seed input, or None if it's empty and a random seed should be used
+	pub fn get_city_seed(&self) -> Result<Option<u64>, CityGenError> {
+		let value = self.city_seed_input.value();
+		if value.trim().is_empty() {return Ok(None);}
+		match value.trim().parse::<u64>() {
+			Ok(seed) => Ok(Some(seed)),
+			Err(_) => Err(CityGenError::InvalidSeed(value)),
+		}//end matching whether the seed is valid
+	}//end get_city_seed(&self)
+
+	/// # set_palette_seed(&mut self, seed)
+	/// 
+	/// shows the seed of the current building palette, or clears the input if the palette has no seed
+	pub fn set_palette_seed(&mut self, seed:Option<u64>) {
+		match seed {
+			Some(seed) => self.palette_seed_input.set_value(&seed.to_string()),
+			None => self.palette_seed_input.set_value(""),
+		}//end matching whether we have a seed to show
+	}//end set_palette_seed(&mut self, seed)
+
+	/// # update_district_legend(&mut self)
+	/// 
+	/// updates the color key on the districts tab to show the current list of districts
+	pub fn update_district_legend(&mut self) {
+		let mut entries = Vec::new();
+		for district in &self.districts {
+			entries.push((district.name.clone(), district.rgb_color));
+		}//end adding each district to the key
+		self.district_legend.set_entries("District Key", &entries);
+	}//end update_district_legend(&mut self)
+
+	/// # update_building_legend(&mut self, palette)
+	/// 
+	/// updates the color key on the neighborhood tab to show the colors in palette
+	pub fn update_building_legend(&mut self, palette:&BuildingPalette) {
+		self.building_legend.set_entries("Building Key", &palette.legend_entries());
+	}//end update_building_legend(&mut self, palette)
+
+	/// # inspect_district_cell(&mut self, grid, coord)
+	/// 
+	/// fills in the district inspector and the tooltip on the district map with the details of the cell at coord in grid.
+	/// If coord is None or outside the grid, the inspector goes back to its hint.
+	pub fn inspect_district_cell(&mut self, grid:&Grid<GroupInstance>, coord:Option<Coord>) {
+		let (coord, instance) = match coord.and_then(|coord| grid.get(coord.row, coord.col).map(|instance| (coord, instance))) {
+			Some(found) => found,
+			None => {self.district_inspector.clear(); return;},
+		};//end matching whether there's a cell to inspect
+		let mut lines = vec![format!("Row {}, Column {}", coord.row + 1, coord.col + 1)];
+		lines.extend(describe_district(instance, &self.districts));
+		lines.push(String::new());
+		if instance.has_neighborhood() {
+			lines.push("Buildings:".to_string());
+			for (build_type, count) in instance.building_counts() {
+				lines.push(format!("  {}: {}", build_type, count));
+			}//end listing the count of each building type
+		}//end if the neighborhood has been generated
+		else {lines.push("Neighborhood not generated yet".to_string());}
+		self.district_inspector.set_lines(&lines);
+
+		let mut tooltip = match instance.district(&self.districts) {
+			Some(district) => district.name.clone(),
+			None => "Empty".to_string(),
+		};//end matching whether the cell has a district
+		if instance.is_locked(&self.districts) {tooltip.push_str(" (locked)");}
+		self.district_map.set_hover_text(coord, Some(tooltip));
+	}//end inspect_district_cell(&mut self, grid, coord)
+
+	/// # inspect_building(&mut self, nhood, coord)
+	/// 
+	/// fills in the building inspector and the tooltip on the neighborhood map with the details of the building at coord in nhood.
+	/// If there isn't a building there, the inspector goes back to its hint.
+	pub fn inspect_building(&mut self, nhood:Option<&GroupInstance>, coord:Option<Coord>) {
+		let (coord, building) = match coord.zip(nhood).and_then(|(coord, nhood)| nhood.sub_grid.get(coord.row, coord.col).map(|building| (coord, building))) {
+			Some(found) => found,
+			None => {self.building_inspector.clear(); return;},
+		};//end matching whether there's a building to inspect
+		let mut lines = vec![format!("Row {}, Column {}", coord.row + 1, coord.col + 1)];
+		lines.extend(describe_building(building));
+		lines.push(String::new());
+		lines.push("Click while viewing to edit the name and notes.".to_string());
+		self.building_inspector.set_lines(&lines);
+		self.neighborhood_map.set_hover_text(coord, Some(building_tooltip(building)));
+	}//end inspect_building(&mut self, nhood, coord)
+
+	/// # inspect_city_building(&mut self, grid, coord)
+	/// 
+	/// fills in the city inspector and the tooltip on the whole city map with the details of the building at coord, along with the district and neighborhood it's in.
+	/// If there isn't a building there, the inspector goes back to its hint.
+	pub fn inspect_city_building(&mut self, grid:&Grid<GroupInstance>, coord:Option<Coord>) {
+		let (coord, (nhood_rows, nhood_cols)) = match coord.zip(self.city_nhood_dims) {
+			Some(found) => found,
+			None => {self.city_inspector.clear(); return;},
+		};//end matching whether the city map is showing anything
+		let nhood_coord = Coord::new(coord.row / nhood_rows, coord.col / nhood_cols);
+		let building_coord = Coord::new(coord.row % nhood_rows, coord.col % nhood_cols);
+		let (instance, building) = match grid.get(nhood_coord.row, nhood_coord.col).and_then(|instance| instance.sub_grid.get(building_coord.row, building_coord.col).map(|building| (instance, building))) {
+			Some(found) => found,
+			None => {self.city_inspector.clear(); return;},
+		};//end matching whether there's a building to inspect
+		let mut lines = vec![format!("Neighborhood row {}, column {}", nhood_coord.row + 1, nhood_coord.col + 1)];
+		lines.extend(describe_district(instance, &self.districts));
+		lines.push(String::new());
+		lines.push(format!("Building row {}, column {}", building_coord.row + 1, building_coord.col + 1));
+		lines.extend(describe_building(building));
+		self.city_inspector.set_lines(&lines);
+
+		let tooltip = match instance.district(&self.districts) {
+			Some(district) => format!("{} in {}", building_tooltip(building), district.name),
+			None => building_tooltip(building),
+		};//end matching whether the neighborhood has a district
+		self.city_map.set_hover_text(coord, Some(tooltip));
+	}//end inspect_city_building(&mut self, grid, coord)
+
+	/// # get_building_info(&self, building)
+	/// 
+	/// asks the user for a new name and notes for building, starting from the ones it already has.
+	/// 
+	/// ## Return
+	/// returns the (name, notes) the user typed in, or None if they cancelled either dialog.
+	pub fn get_building_info(&self, building:&Building) -> Option<(String, String)> {
+		let name = dialog::input(0, 0, &format!("Enter a name for this {}, or leave it empty for no name.", building.build_type), building.name())?;
+		let notes = dialog::input(0, 0, &format!("Enter any notes about {}.", if name.trim().is_empty() {format!("this {}", building.build_type)} else {name.trim().to_string()}), building.notes())?;
+		return Some((name, notes));
+	}//end get_building_info(&self, building)
+
+	/// # update_district_list
+	/// 
+	/// updates the district list on the settings tab, and the district choice for painting, to show the current districts
+	pub fn update_district_list(&mut self) {
+		self.district_list.set_districts(&self.districts);
+		self.update_paint_district_choice();
+	}//end update_district_list(&mut self)
+
+	/// # take_district_id(&mut self)
+	/// 
+	/// returns an id that no other district has used yet, for a new district
+	pub fn take_district_id(&mut self) -> u32 {
+		let id = self.next_district_id;
+		self.next_district_id += 1;
+		return id;
+	}//end take_district_id(&mut self)
+
+	/// # reserve_district_ids(&mut self)
+	/// 
+	/// makes sure new districts won't reuse any id already in the district list, such as after loading a city
+	pub fn reserve_district_ids(&mut self) {
+		let max_id = self.districts.iter().map(|district| district.id).max().unwrap_or(0);
+		self.next_district_id = self.next_district_id.max(max_id + 1);
+	}//end reserve_district_ids(&mut self)
+
+	/// # selected_districts(&self)
+	/// 
+	/// returns the indices of the districts selected in the district list, from first to last
+	pub fn selected_districts(&self) -> Vec<usize> {
+		self.district_list.selected()
+	}//end selected_districts(&self)
+
+	/// # set_selected_districts(&mut self, indices)
+	/// 
+	/// selects exactly the districts at indices in the district list
+	pub fn set_selected_districts(&mut self, indices:&[usize]) {
+		self.district_list.set_selected(indices);
+	}//end set_selected_districts(&mut self, indices)
+
+	/// # get_district_name_input(&self)
+	/// 
+	/// returns the name typed in the district list for renaming the selected district
+	pub fn get_district_name_input(&self) -> String {
+		self.district_list.name_input_value()
+	}//end get_district_name_input(&self)
+
+	/// # focus_district_name_input(&mut self)
+	/// 
+	/// moves keyboard focus to the name input of the district list, so the selected district can be renamed right away
+	pub fn focus_district_name_input(&mut self) {
+		self.district_list.focus_name_input();
+	}//end focus_district_name_input(&mut self)
+
+	/// # initialize_neighborhood_toolbar(&mut self)
+	/// 
+	/// sets up the row of controls above the neighborhood map
+	fn initialize_neighborhood_toolbar(&mut self) {
+		let toolbar_y = self.neighborhood_tab.y() + 4;
+		self.building_tool_choice = Choice::new(40, toolbar_y, 90, 22, "Tool");
+		self.building_tool_choice.add_choice("View|Edit|Lock");
+		self.building_tool_choice.set_value(0);
+		let mut neighborhood_map = self.neighborhood_map.clone();
+		self.building_tool_choice.set_callback(move |choice| {
+			neighborhood_map.set_paint_mode(choice.value() > 0);
+		});
+		self.paint_building_choice = Choice::new(200, toolbar_y, 140, 22, "Building");
+		for build_type in BuildingType::editable_types() {
+			self.paint_building_choice.add_choice(&escape_menu_label(&build_type.to_string()));
+		}//end adding each building type as a choice
+		self.paint_building_choice.set_value(0);
+		self.nhood_lock_check = CheckButton::new(350, toolbar_y, 135, 22, "Lock neighborhood");
+		self.nhood_lock_check.emit(self.menu_msg_sender, MenuChoice::ToggleNeighborhoodLock);
+		self.neighborhood_tab.add(&self.building_tool_choice);
+		self.neighborhood_tab.add(&self.paint_building_choice);
+		self.neighborhood_tab.add(&self.nhood_lock_check);
+		// arrows to move to the next neighborhood over, which also work with ctrl and the arrow keys while this tab is shown
+		self.nhood_nav_buttons.clear();
+		for (index, (direction, symbol, key, key_name, name)) in [
+			(Direction::Left, "@<-", Key::Left, "Left", "to the left"),
+			(Direction::Up, "@8->", Key::Up, "Up", "above"),
+			(Direction::Down, "@2->", Key::Down, "Down", "below"),
+			(Direction::Right, "@->", Key::Right, "Right", "to the right"),
+		].into_iter().enumerate() {
+			let mut button = Button::new(490 + 22 * index as i32, toolbar_y, 22, 22, symbol);
+			button.set_label_size(10);
+			button.set_shortcut(Shortcut::Ctrl | key);
+			button.set_tooltip(&format!("Show the neighborhood {} (Ctrl+{})", name, key_name));
+			button.emit(self.menu_msg_sender, MenuChoice::ShowAdjacentNeighborhood(direction));
+			button.deactivate();
+			self.neighborhood_tab.add(&button);
+			self.nhood_nav_buttons.push((direction, button));
+		}//end adding a button for each direction
+		self.nhood_breadcrumb = Frame::new(585, toolbar_y, get_default_grid_width() - 590, 22, None);
+		self.nhood_breadcrumb.set_align(Align::Inside | Align::Left | Align::Clip);
+		self.nhood_breadcrumb.set_label_size(12);
+		self.neighborhood_tab.add(&self.nhood_breadcrumb);
+	}//end initialize_neighborhood_toolbar(&mut self)
+
+	/// # update_neighborhood_navigation(&mut self, grid, coord)
+	/// 
+	/// points the breadcrumb, overview, and arrow buttons on the neighborhood tab at the neighborhood at coord in grid
+	pub fn update_neighborhood_navigation(&mut self, grid:&Grid<GroupInstance>, coord:Coord) {
+		let breadcrumb = neighborhood_title(grid, &self.districts, coord);
+		// @ starts a symbol in fltk labels, so it has to be doubled to show up as is
+		self.nhood_breadcrumb.set_label(&breadcrumb.replace('@', "@@"));
+		self.nhood_breadcrumb.set_tooltip(&breadcrumb);
+		self.nhood_breadcrumb.redraw();
+
+		let mut colors: Grid<(u8,u8,u8)> = Grid::new(grid.rows(), grid.cols());
+		let lookup = DistrictLookup::new(&self.districts);
+		for row in 0..grid.rows() {
+			for col in 0..grid.cols() {
+				let district_id = grid.get(row, col).unwrap().district_id.unwrap_or(0);
+				*colors.get_mut(row, col).unwrap() = district_id_map_cell(district_id, &lookup).rgb_color;
+			}//end looping through columns of grid
+		}//end coloring each cell by its district
+		self.nhood_overview.set_colors(colors, Some(coord));
+
+		for (direction, button) in self.nhood_nav_buttons.iter_mut() {
+			if coord.step(*direction, grid.rows(), grid.cols()).is_some() {button.activate();}
+			else {button.deactivate();}
+		}//end enabling the arrows that lead somewhere
+	}//end update_neighborhood_navigation(&mut self, grid, coord)
+
+	/// # clear_neighborhood_navigation(&mut self)
+	/// 
+	/// empties the breadcrumb and overview on the neighborhood tab and disables its arrows, for when no neighborhood is shown
+	pub fn clear_neighborhood_navigation(&mut self) {
+		self.nhood_breadcrumb.set_label("");
+		self.nhood_breadcrumb.redraw();
+		self.nhood_overview.set_colors(Grid::new(0,0), None);
+		for (_, button) in self.nhood_nav_buttons.iter_mut() {
+			button.deactivate();
+		}//end disabling each arrow
+	}//end clear_neighborhood_navigation(&mut self)
+
+	/// # nhood_lock_checked(&self)
+	/// 
+	/// returns whether the box for locking the shown neighborhood is checked
+	pub fn nhood_lock_checked(&self) -> bool {
+		self.nhood_lock_check.is_checked()
+	}//end nhood_lock_checked(&self)
+
+	/// # get_paint_building(&self)
+	/// 
+	/// returns the building type chosen for placing in the neighborhood, if there is one
+	pub fn get_paint_building(&self) -> Option<BuildingType> {
+		let value = self.paint_building_choice.value();
+		if value < 0 {return None;}
+		BuildingType::editable_types().get(value as usize).cloned()
+	}//end get_paint_building(&self)
+
+	/// # update_paint_district_choice(&mut self)
+	/// 
+	/// refills the list of districts that can be painted with, keeping the current selection if it still exists
+	fn update_paint_district_choice(&mut self) {
+		let prev_value = self.paint_district_choice.value();
+		self.paint_district_choice.clear();
 		for district in &self.districts {
-			let mut shrunk_name = district.name.clone();
-			if shrunk_name.len() > 50 {
-				shrunk_name = shrunk_name[0..50].to_string();
-			}//end if we need to shrink the name
-			self.districts_list_buffer.append(&format!("{},      rgb color: {},{},{}\n", shrunk_name, &district.rgb_color.0, &district.rgb_color.1, &district.rgb_color.2));
-		}//end adding each district to buffer
-	}//end update_district_list_buf(&mut self)
-
-	/// # clear_district_locations(self)
-	/// 
-	/// clears locations in districts. best to do this whenever regenerating things
-	pub fn clear_district_locations(&mut self) {
-		for dist_idx in 0..self.districts.len() {
-			self.districts.get_mut(dist_idx).unwrap().locations.clear();
-		}//end clearing each district's locations
-	}//end clear_district_locations(self)
+			self.paint_district_choice.add_choice(&escape_menu_label(&district.name));
+		}//end adding each district as a choice
+		if prev_value >= 0 && (prev_value as usize) < self.districts.len() {
+			self.paint_district_choice.set_value(prev_value);
+		}//end if previous choice still exists
+		else if !self.districts.is_empty() {
+			self.paint_district_choice.set_value(0);
+		}//end else if we can choose the first one
+		self.paint_district_choice.redraw();
+	}//end update_paint_district_choice(&mut self)
+
+	/// # get_paint_district(&self)
+	/// 
+	/// returns the index of the district chosen for painting, if there is one
+	pub fn get_paint_district(&self) -> Option<usize> {
+		let value = self.paint_district_choice.value();
+		if value >= 0 && (value as usize) < self.districts.len() {Some(value as usize)}
+		else {None}
+	}//end get_paint_district(&self)
+
+	/// # regen_on_paint(&self)
+	/// 
+	/// returns whether neighborhoods should be regenerated when their district cell is painted
+	pub fn regen_on_paint(&self) -> bool {
+		self.regen_on_paint_check.is_checked()
+	}//end regen_on_paint(&self)
+
+	/// # growth_steps_per_second(&self)
+	/// 
+	/// returns how many steps of district growth to show each second, from the speed slider
+	pub fn growth_steps_per_second(&self) -> f64 {
+		self.growth_speed_slider.value().max(1.0)
+	}//end growth_steps_per_second(&self)
+
+	/// # growth_playing(&self)
+	/// 
+	/// returns whether district growth is playing back right now
+	pub fn growth_playing(&self) -> bool {
+		self.growth_timer.is_some()
+	}//end growth_playing(&self)
+
+	/// # set_growth_playing(&mut self, playing)
+	/// 
+	/// starts or stops sending MenuChoice::GrowthTick at the speed chosen on the slider, and shows whether growth is playing on the play button
+	pub fn set_growth_playing(&mut self, playing:bool) {
+		if playing && self.growth_timer.is_none() {
+			let sender = self.menu_msg_sender;
+			let slider = self.growth_speed_slider.clone();
+			self.growth_timer = Some(app::add_timeout3(1.0 / self.growth_steps_per_second(), move |handle| {
+				sender.send(MenuChoice::GrowthTick);
+				// read the slider every time, so changing the speed takes effect right away
+				app::repeat_timeout3(1.0 / slider.value().max(1.0), handle);
+			}));
+		}//end if we need to start playing
+		else if !playing {
+			if let Some(handle) = self.growth_timer.take() {
+				app::remove_timeout3(handle);
+			}//end if we were playing
+		}//end else if we need to stop playing
+		self.growth_play_button.set_label(if playing {"Pause"} else {"Play"});
+	}//end set_growth_playing(&mut self, playing)
+
+	/// # update_growth_cells(&mut self, cells)
+	/// 
+	/// redraws each (coord, district id) of cells on the district map, without lock marks, to show a step of district growth
+	pub fn update_growth_cells(&mut self, cells:&[(Coord, u32)]) {
+		let lookup = DistrictLookup::new(&self.districts);
+		for (coord, district_id) in cells {
+			self.district_map.update_cell(*coord, district_id_map_cell(*district_id, &lookup));
+		}//end redrawing each cell
+	}//end update_growth_cells(&mut self, cells)
+
+	/// # apply_settings(&mut self, settings)
+	/// 
+	/// fills in the inputs and district list, and sizes the main window, from settings remembered from last time
+	pub fn apply_settings(&mut self, settings:&AppSettings) {
+		self.districts_rows_input.set_value(&settings.district_dims.0.to_string());
+		self.districts_cols_input.set_value(&settings.district_dims.1.to_string());
+		self.neighborhood_rows_input.set_value(&settings.nhood_dims.0.to_string());
+		self.neighborhood_cols_input.set_value(&settings.nhood_dims.1.to_string());
+		match settings.city_seed {
+			Some(seed) => self.city_seed_input.set_value(&seed.to_string()),
+			None => self.city_seed_input.set_value(""),
+		}//end matching whether there's a seed to show
+		self.lazy_nhoods_check.set_checked(settings.lazy_neighborhoods);
+		if !settings.districts.is_empty() {
+			self.districts.clear();
+			for district in &settings.districts {
+				let id = self.take_district_id();
+				self.districts.push(district.clone().with_id(id));
+			}//end giving each remembered district a fresh id
+			self.update_district_list();
+			self.update_district_legend();
+		}//end if there are districts to remember
+		if let Some((width, height)) = settings.window_size {
+			self.main_window.set_size(width.max(get_default_win_width()), height.max(get_default_win_height()));
+			self.update_layout();
+		}//end if there's a window size to remember
+	}//end apply_settings(&mut self, settings)
+
+	/// # store_settings(&mut self, settings)
+	/// 
+	/// copies the inputs, district list, and window size into settings, so they can be remembered next time.
+	/// Inputs that don't hold a valid value keep whatever settings already had.
+	pub fn store_settings(&mut self, settings:&mut AppSettings) {
+		if let Ok(dims) = self.get_districts_dims() {settings.district_dims = dims;}
+		if let Ok(dims) = self.get_neighborhood_dims() {settings.nhood_dims = dims;}
+		if let Ok(seed) = self.get_city_seed() {settings.city_seed = seed;}
+		settings.lazy_neighborhoods = self.lazy_neighborhoods();
+		settings.districts = self.districts.clone();
+		settings.window_size = Some((self.main_window.w(), self.main_window.h()));
+	}//end store_settings(&mut self, settings)
+
+	/// # update_recent_files_menu(&mut self, recent_files)
+	/// 
+	/// refills File/Open Recent with a numbered item for each path in recent_files
+	pub fn update_recent_files_menu(&mut self, recent_files:&[PathBuf]) {
+		let submenu = "&File/Open Recent";
+		let index = self.top_menu.find_index(submenu);
+		if index >= 0 {
+			let _ = self.top_menu.clear_submenu(index);
+		}//end if the submenu is already there
+		if recent_files.is_empty() {
+			self.top_menu.add(&format!("{}/(none)", submenu), Shortcut::None, menu::MenuFlag::Inactive, |_| {});
+		}//end if there's nothing to list
+		for (index, path) in recent_files.iter().enumerate() {
+			let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string());
+			self.top_menu.add_emit(
+				&format!("{}/&{} {}\t", submenu, index + 1, escape_menu_label(&name)),
+				Shortcut::None,
+				menu::MenuFlag::Normal,
+				self.menu_msg_sender,
+				MenuChoice::OpenRecent(index),
+			);
+		}//end adding an item for each recent file
+	}//end update_recent_files_menu(&mut self, recent_files)
+
+	/// # lazy_neighborhoods(&self)
+	/// 
+	/// returns whether neighborhoods should be left ungenerated until they're viewed or exported
+	pub fn lazy_neighborhoods(&self) -> bool {
+		self.lazy_nhoods_check.is_checked()
+	}//end lazy_neighborhoods(&self)
+
 
 	/// # show(self)
 	/// 
 	/// Simply causes the gui to become visible
 	pub fn show(&mut self) {
-		self.grid_flex.recalc();
 		self.main_window.show();
 	}//end show(&mut self)
 
@@ -474,24 +1704,65 @@ impl GUI<'_> {
 		dialog::color_chooser("Choose Color", dialog::ColorMode::Rgb)
 	}//end get_color(&self)
 
-	/// # get_new_district_name(&self)
+	/// # get_save_path(&self, title, filter, default_name)
 	/// 
-	/// Opens a dialgue box and displays it to the user, prompting them to give a new district name.
-	pub fn get_new_district_name(&self) -> Option<String> {
-		let dialog = "Enter the name for a new district. It cannot be empty.";
+	/// opens a native save dialog so the user can pick where to save a file.
+	/// filter is in the native chooser format, such as "*.png".
+	/// 
+	/// ## Return
+	/// returns the chosen path, or None if the user cancelled.
+	pub fn get_save_path(&self, title:&str, filter:&str, default_name:&str) -> Option<PathBuf> {
+		self.get_export_path(title, filter, default_name, None)
+	}//end get_save_path(&self, title, filter, default_name)
 
+	/// # get_export_path(&self, title, filter, default_name, dir)
+	/// 
+	/// like get_save_path(), but starts the dialog in dir if there is one, such as the folder of the last export
+	pub fn get_export_path(&self, title:&str, filter:&str, default_name:&str, dir:Option<&Path>) -> Option<PathBuf> {
+		let mut chooser = dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseSaveFile);
+		chooser.set_title(title);
+		if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+			let _ = chooser.set_directory(&dir);
+		}//end if we know where to start
+		chooser.set_filter(filter);
+		chooser.set_preset_file(default_name);
+		chooser.set_option(dialog::NativeFileChooserOptions::SaveAsConfirm);
+		chooser.show();
+		let path = chooser.filename();
+		if path.as_os_str().is_empty() {None}
+		else {Some(path)}
+	}//end get_export_path(&self, title, filter, default_name, dir)
+
+	/// # get_open_path(&self, title, filter)
+	/// 
+	/// opens a native open dialog so the user can pick a file to load.
+	/// filter is in the native chooser format, such as "*.city".
+	/// 
+	/// ## Return
+	/// returns the chosen path, or None if the user cancelled.
+	pub fn get_open_path(&self, title:&str, filter:&str) -> Option<PathBuf> {
+		let mut chooser = dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseFile);
+		chooser.set_title(title);
+		chooser.set_filter(filter);
+		chooser.show();
+		let path = chooser.filename();
+		if path.as_os_str().is_empty() {None}
+		else {Some(path)}
+	}//end get_open_path(&self, title, filter)
+
+	/// # get_export_scale(&self, what, default)
+	/// 
+	/// asks the user how many pixels wide each of what, such as "building", should be in an exported image, suggesting default.
+	/// Returns None if the user cancelled.
+	pub fn get_export_scale(&self, what:&str, default:usize) -> Option<usize> {
 		loop {
-			let dialog_result = dialog::input(0, 0, dialog, "");
-			if dialog_result.is_some() {
-				let result = dialog_result.unwrap();
-				if !result.eq("") && result.eq_ignore_ascii_case("empty") {
-					dialog::message(0, 0, "District name cannot be empty :-(. Try again.");
-				}//end if user had empty name
-				else { return Some(result); }
-			}//end if we got something
-			else {return None;}
+			let result = dialog::input(0, 0, &format!("How many pixels wide should each {} be in the image?", what), &default.to_string())?;
+			match result.trim().parse::<usize>() {
+				Ok(scale) if scale > 0 => return Some(scale),
+				_ => dialog::message(0, 0, "Please enter a whole number greater than 0."),
+			}//end matching whether we got a valid scale
 		}//end looping until we get something valid
-	}//end get_new_district_name(&self)
+	}//end get_export_scale(&self, what, default)
 
 	/// # show_message(&self, msg)
 	/// 
@@ -500,178 +1771,194 @@ impl GUI<'_> {
 		dialog::message(0, 0, msg);
 	}//end show_message(&self, msg)
 
-	/// # show_neighborhood_window(&self, nhood)
+	/// # update_neighborhood_tab(&mut self, nhood)
 	/// 
-	/// shows a new window with a colorful display of the specified neighborhood.
+	/// updates the neighborhood map with a colorful display of the specified neighborhood.
 	pub fn update_neighborhood_tab(&mut self,nhood:&GroupInstance) {
-		// try and re-initialize neighborhood_flex
-		// self.neighborhood_flex = FlexGrid::default();
-		// clear previous nonsense
-		if self.neighborhood_flex.children() > 0 {
-			self.neighborhood_flex.clear_inner_flexes();
-		}//end if we have previous stuff to take care of
-		// create and fill a grid of buttons
-		let mut button_grid = Grid::new(nhood.sub_grid.rows(), nhood.sub_grid.cols());
-		let button_width = get_default_grid_width() / button_grid.cols() as i32;
-		let button_height = get_default_grid_height() / button_grid.rows() as i32;
-		// don't show text if button is too small
-		let show_label = button_width > get_max_grid_button_width() && button_height > get_max_grid_button_height();
-		// print button size for debugging purposes
-		println!("neighborhood button sizes w:{}\th:{}\tshow label:{}", button_width, button_height, show_label);
-		// loop through and build the grid
-		for row_idx in 0..button_grid.rows() {
-			for col_idx in 0..button_grid.cols() {
+		let mut cells: Grid<MapCell> = Grid::new(nhood.sub_grid.rows(), nhood.sub_grid.cols());
+		for row_idx in 0..nhood.sub_grid.rows() {
+			for col_idx in 0..nhood.sub_grid.cols() {
 				let this_building = nhood.sub_grid.get(row_idx, col_idx).unwrap();
-				let mut this_button = Button::default()
-					.with_size(button_width, button_height);
-				// only add label if button big enough
-				if show_label {
-					this_button.set_label(format!("{}",this_building.build_type).as_str())
-				}//end if we have room to show the label
-				// start some color calculations
-				let c = (this_building.rgb_color.0, this_building.rgb_color.1, this_building.rgb_color.2);
-				// set color based on building
-				this_button.set_color(Color::from_rgb(c.0, c.1, c.2));
-				// do some calculations to determine if we should change label color
-				let luminance = 0.299*c.0 as f32 + 0.587*c.1 as f32 + 0.114*c.2 as f32;
-				if luminance > get_max_luminance_for_white_label() {
-					this_button.set_label_color(Color::Black);
-				}//end if label color should be black
-				else { this_button.set_label_color(Color::White); }
-				// TODO: set label color to negative of button color
-				// update spot in button grid using through reference
-				let this_spot = button_grid.get_mut(row_idx, col_idx).unwrap();
-				*this_spot = this_button;
+				*cells.get_mut(row_idx, col_idx).unwrap() = MapCell::new(this_building.rgb_color, this_building.build_type.to_string()).with_locked(this_building.locked);
 			}//end looping through columns of grid
 		}//end looping through rows of grid
-		// initialize our flex grid's size
-		self.neighborhood_flex.initialize_flex(nhood.sub_grid.rows(), nhood.sub_grid.cols());
-		// fill the flex_grid
-		self.neighborhood_flex.fill_flex(&button_grid);
-		// throw our buttons in the flex
-		self.neighborhood_flex.buttons = button_grid;
-		// actually try and make things show up
-		if self.neighborhood_tab.children() < 1 {
-			self.neighborhood_tab.add(&*self.neighborhood_flex);
-		}//end to tab if not there already
-		self.neighborhood_flex.recalc();
-		self.neighborhood_flex.redraw();
-	}//end show_neighborhood_window(&self, nhood)
-
-	/// # choose_district(&self)
-	/// 
-	/// opens dialog box prompting user to choose a district from the internal list
+		self.neighborhood_map.set_cells(cells);
+		self.nhood_lock_check.set_checked(nhood.nhood_locked);
+	}//end update_neighborhood_tab(&mut self, nhood)
+
+	/// # update_building_cell(&mut self, nhood, coord)
+	/// 
+	/// redraws a single building of the neighborhood map after it was edited, without resetting the view
+	pub fn update_building_cell(&mut self, nhood:&GroupInstance, coord:Coord) {
+		if let Some(this_building) = nhood.sub_grid.get(coord.row, coord.col) {
+			self.neighborhood_map.update_cell(coord, MapCell::new(this_building.rgb_color, this_building.build_type.to_string()).with_locked(this_building.locked));
+		}//end if coord is in the neighborhood
+	}//end update_building_cell(&mut self, nhood, coord)
+
+	/// # choose_preset(&self, purpose)
+	/// 
+	/// opens dialog box prompting user to choose one of the palette presets. purpose is shown in the prompt, such as "building colors".
 	/// 
 	/// ## Return
-	/// returns the index of self.districts that was selected.
+	/// returns the index of the selected preset in get_palette_presets().
 	/// If the user cancelled the dialogue, then None will be returned.
-	pub fn choose_district(&self) -> Option<usize> {
-		let mut choose_district_dialog = "Enter the name of a district in the following list, case sensitive.".to_string();
-		for district in &self.districts {
-			choose_district_dialog = format!("{}\n{}", choose_district_dialog,district.name);
-		}//end adding all the district names
-		let choose_district_default:&str;
-		if self.districts.len() > 0 {
-			choose_district_default = &self.districts.first().unwrap().name;
-		}//end if there is at least one option
-		else {return None;}
+	pub fn choose_preset(&self, purpose:&str) -> Option<usize> {
+		let presets = get_palette_presets();
+		let mut choose_preset_dialog = format!("Enter the name of a color preset to use for {}.", purpose);
+		for preset in &presets {
+			choose_preset_dialog = format!("{}\n{}", choose_preset_dialog, preset.display_name());
+		}//end adding all the preset names
+		let choose_preset_default = presets.first().unwrap().name;
 
 		// loop to get dialog from the user
 		loop {
-			let result = dialog::input(0, 0, &choose_district_dialog, choose_district_default);
+			let result = dialog::input(0, 0, &choose_preset_dialog, choose_preset_default);
 
-			if result.is_some() {
-				let temp_result = result.clone().unwrap();
-				for i in 0..self.districts.len() {
-					if self.districts.get(i).unwrap().name.eq_ignore_ascii_case(&temp_result) {
+			if let Some(temp_result) = result {
+				for i in 0..presets.len() {
+					let preset = presets.get(i).unwrap();
+					if preset.name.eq_ignore_ascii_case(temp_result.trim()) || preset.display_name().eq_ignore_ascii_case(temp_result.trim()) {
 						return Some(i);
 					}//end if we found a match
-				}//end checking each district for a match
+				}//end checking each preset for a match
+			}//end if we got something to validate
+			else {return None;}
+		}//end looping until we get a result
+	}//end choose_preset(&self, purpose)
+
+	/// # choose_building_type(&self)
+	/// 
+	/// opens dialog box prompting user to choose one of the building types that has a palette color
+	/// 
+	/// ## Return
+	/// returns the selected building type.
+	/// If the user cancelled the dialogue, then None will be returned.
+	pub fn choose_building_type(&self) -> Option<BuildingType> {
+		let build_types = BuildingType::palette_types();
+		let mut choose_type_dialog = "Enter the name of a building type in the following list.".to_string();
+		for build_type in &build_types {
+			choose_type_dialog = format!("{}\n{}", choose_type_dialog, build_type);
+		}//end adding all the building type names
+		let choose_type_default = build_types.first().unwrap().to_string();
+
+		// loop to get dialog from the user
+		loop {
+			let result = dialog::input(0, 0, &choose_type_dialog, &choose_type_default);
+
+			if let Some(temp_result) = result {
+				for build_type in &build_types {
+					if build_type.to_string().eq_ignore_ascii_case(temp_result.trim()) {
+						return Some(build_type.clone());
+					}//end if we found a match
+				}//end checking each building type for a match
 			}//end if we got something to validate
 			else {return None;}
 		}//end looping until we get a result
-	}//end choose_district(self)
+	}//end choose_building_type(&self)
 }//end impl for gui
 
-/// # FlexGrid
+/// # Legend
 /// 
-/// This struct is meant to be a sort of wrapper around a bunch of buttons and nested flexes in order to mimic a grid of buttons.
-pub struct FlexGrid {
-	/// # buttons
-	/// The 2d array of buttons filling the grid
-	pub buttons: Grid<Button>,
-	/// # outer_flex
-	/// The flex containing the flex containing the buttons
-	pub outer_flex: Flex,
-	/// # inner_flexes
-	/// the flexes contained within the inner flex
-	pub inner_flexes: Vec<Flex>,
-}//end struct FlexGrid
-
-impl FlexGrid {
+/// This struct is a scrollable color key, showing a colored swatch for each named entry.
+pub struct Legend {
+	/// # scroll
+	/// The scrollable area the legend lives in
+	pub scroll: Scroll,
+	/// # pack
+	/// The vertical pack holding the title and one swatch per entry
+	pub pack: Pack,
+}//end struct Legend
+
+impl Legend {
 	/// # default()
 	/// 
-	/// constructs the empty FlexGrid
-	pub fn default() -> FlexGrid {
-		let new_outer_flex = Flex::new(0, get_default_menu_height() + get_default_tab_padding(), get_default_grid_width(), get_default_grid_height(), None);
-		new_outer_flex.end();
-		FlexGrid {
-			buttons: Grid::new(0,0),
-			outer_flex: new_outer_flex,
-			inner_flexes: Vec::new(),
+	/// constructs an empty legend to the right of the grid area, leaving room for an inspector under it
+	pub fn default() -> Legend {
+		Legend::new(get_default_grid_height() - get_default_inspector_height())
+	}//end default()
+
+	/// # new(height)
+	/// 
+	/// constructs an empty legend to the right of the grid area, with the specified height
+	pub fn new(height:i32) -> Legend {
+		let mut scroll = Scroll::new(get_default_grid_width(), get_default_menu_height() + get_default_tab_padding(), get_default_legend_width(), height, None);
+		scroll.set_type(group::ScrollType::Vertical);
+		let mut pack = Pack::new(scroll.x() + 5, scroll.y() + 5, scroll.width() - 25, scroll.height() - 10, None);
+		pack.set_spacing(2);
+		pack.end();
+		scroll.end();
+		scroll.add(&pack);
+		Legend {
+			scroll,
+			pack,
 		}//end struct construction
-	}//end new()
-
-	/// # clear_inner_flexes
-	/// 
-	/// clears the children of this struct. should hopefully work
-	pub fn clear_inner_flexes(&mut self) {
-		self.outer_flex.clear();
-		self.inner_flexes.clear();
-		self.buttons.clear();
-	}//end clear_inner_flexes(&mut self)
-
-	/// #initialize_flex(self, grid)]
-	/// 
-	/// Sets up the flex-boxes like a grid
-	pub fn initialize_flex(&mut self, rows:usize, cols:usize) {
-		// set outer flex to be have rows of elements
-		self.outer_flex.set_type(group::FlexType::Column);
-		self.outer_flex.set_align(Align::LeftTop);
-		for _row_index in 0..rows {
-			let inner_flex_x = 0;//self.outer_flex.x();
-			let inner_flex_y = self.outer_flex.y() + (self.outer_flex.width() / cols as i32);
-			let inner_flex_w = get_default_grid_width() / cols as i32;
-			let inner_flex_h = get_default_grid_height() / rows as i32;
-			let mut inner_flex = Flex::new(inner_flex_x,inner_flex_y,inner_flex_w,inner_flex_h,None);
-			inner_flex.set_type(group::FlexType::Row);
-			// make flex show up
-			self.outer_flex.add(&inner_flex);
-			// save flex to struct
-			self.inner_flexes.push(inner_flex);
-		}//end adding inner flexes
-		// println!("{} inner flexes", self.inner_flexes.len());
-		// println!("inner flex x:{}", self.inner_flexes.first().unwrap().x());
-	}//end initialize_flex(self, grid)
-
-	/// # fill_flex(self, buttons)
-	/// fills up the flex with buttons such that the buttons will show up in the flex looking like a grid
-	/// 
-	/// It should be noted that this function should expect to receive things in the order of col, rows
-	pub fn fill_flex(&mut self, buttons:&Grid<Button>) {
-		for row_idx in 0..buttons.rows() {
-			let this_inner_flex = self.inner_flexes.get_mut(row_idx).unwrap();
-			// loop over the current row of buttons
-			for button in buttons.iter_row(row_idx) {
-				if !button.was_deleted() {
-					this_inner_flex.add(button);
-				}//end if button wasn't deleted
-				else {println!("button was deleted, row {}", row_idx);}
-			}//end adding each button in row to inner flex
-			this_inner_flex.end();
-		}//end looping over each inner flex and adding buttons
-		self.outer_flex.end();
-	}//end fill_flex
-}//end impl for FlexGrid
-
-widget_extends!(FlexGrid, Flex, outer_flex);
\ No newline at end of file
+	}//end new(height)
+
+	/// # set_entries(&mut self, title, entries)
+	/// 
+	/// replaces the contents of the legend with a title followed by one colored swatch for each name and color in entries
+	pub fn set_entries(&mut self, title:&str, entries:&Vec<(String,(u8,u8,u8))>) {
+		self.pack.clear();
+		let swatch_width = self.pack.width();
+		let mut title_frame = Frame::default()
+			.with_size(swatch_width, 25)
+			.with_label(title);
+		title_frame.set_label_size(16);
+		self.pack.add(&title_frame);
+		for (name, color) in entries {
+			let mut swatch = Frame::default()
+				.with_size(swatch_width, 20)
+				.with_label(name);
+			swatch.set_frame(FrameType::FlatBox);
+			swatch.set_color(Color::from_rgb(color.0, color.1, color.2));
+			swatch.set_label_color(get_label_color(color.to_owned()));
+			self.pack.add(&swatch);
+		}//end adding a swatch for each entry
+		self.scroll.redraw();
+	}//end set_entries(&mut self, title, entries)
+}//end impl for Legend
+
+widget_extends!(Legend, Scroll, scroll);
+
+/// # Inspector
+/// 
+/// This struct is a small panel of text describing whatever is under the mouse on the map next to it.
+pub struct Inspector {
+	/// # frame
+	/// The box the text is drawn in
+	pub frame: Frame,
+}//end struct Inspector
+
+impl Inspector {
+	/// # default()
+	/// 
+	/// constructs an empty inspector under the legend, to the right of the grid area
+	pub fn default() -> Inspector {
+		let y = get_default_menu_height() + get_default_tab_padding() + get_default_grid_height() - get_default_inspector_height();
+		let mut frame = Frame::new(get_default_grid_width(), y, get_default_legend_width(), get_default_inspector_height(), None);
+		frame.set_frame(FrameType::DownBox);
+		frame.set_align(Align::Inside | Align::Left | Align::Top | Align::Wrap | Align::Clip);
+		frame.set_label_size(12);
+		let mut inspector = Inspector { frame };
+		inspector.clear();
+		return inspector;
+	}//end default()
+
+	/// # set_lines(&mut self, lines)
+	/// 
+	/// replaces the text of the inspector with lines, one after another
+	pub fn set_lines(&mut self, lines:&[String]) {
+		// @ starts a symbol in fltk labels, so it has to be doubled to show up as is
+		self.frame.set_label(&lines.join("\n").replace('@', "@@"));
+		self.frame.redraw();
+	}//end set_lines(&mut self, lines)
+
+	/// # clear(&mut self)
+	/// 
+	/// goes back to the hint shown when nothing is under the mouse
+	pub fn clear(&mut self) {
+		self.set_lines(&["Hover over the map to see details here.".to_string()]);
+	}//end clear(&mut self)
+}//end impl for Inspector
+
+widget_extends!(Inspector, Frame, frame);
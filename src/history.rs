@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use grid::Grid;
+
+use crate::grouping::Coord;
+use crate::grouping::GroupInstance;
+use crate::grouping::Grouping;
+use crate::grouping::cells_in_district;
+
+/// # get_max_history_len()
+///
+/// The most edits that will be remembered for undo before the oldest ones are forgotten
+fn get_max_history_len() -> usize {100}
+
+/// # CellChange
+///
+/// What a single district cell, including its neighborhood, looked like before and after an edit.
+/// Most edits don't touch the neighborhood inside the cell, so in that case it isn't stored at all and the neighborhood already in the grid is kept.
+#[derive(Clone, Debug)]
+struct CellChange {
+    coord: Coord,
+    before: GroupInstance,
+    after: GroupInstance,
+    /// whether the edit left the neighborhood alone, in which case the sub_grids of before and after are left empty
+    same_neighborhood: bool,
+}//end struct CellChange
+
+impl CellChange {
+    /// # new(coord, before, after)
+    ///
+    /// creates a change from before to after, dropping both neighborhoods if they're the same
+    fn new(coord:Coord, mut before:GroupInstance, mut after:GroupInstance) -> CellChange {
+        let same_neighborhood = before.sub_grid == after.sub_grid;
+        if same_neighborhood {
+            before.sub_grid = Grid::new(0, 0);
+            after.sub_grid = Grid::new(0, 0);
+        }//end if we don't need to remember the neighborhood
+        CellChange { coord, before, after, same_neighborhood }
+    }//end new(coord, before, after)
+
+    /// # merge(&mut self, before, after)
+    ///
+    /// updates this change to end at after, where before is what the cell looked like just before that.
+    /// If the neighborhood wasn't stored but has changed now, before still has the original neighborhood, so it's taken from there.
+    fn merge(&mut self, before:GroupInstance, mut after:GroupInstance) {
+        if self.same_neighborhood {
+            if before.sub_grid == after.sub_grid {
+                after.sub_grid = Grid::new(0, 0);
+            }//end if the neighborhood still hasn't changed
+            else {
+                self.before.sub_grid = before.sub_grid;
+                self.same_neighborhood = false;
+            }//end else we need both neighborhoods now
+        }//end if we didn't have the neighborhood before
+        self.after = after;
+    }//end merge(&mut self, before, after)
+
+    /// # apply(&self, instance, forward)
+    ///
+    /// puts instance back how it was after this change if forward is true, or before it if forward is false
+    fn apply(&self, instance:&mut GroupInstance, forward:bool) {
+        let mut target = if forward {self.after.clone()} else {self.before.clone()};
+        if self.same_neighborhood {
+            std::mem::swap(&mut target.sub_grid, &mut instance.sub_grid);
+        }//end if the neighborhood in the grid should be kept
+        *instance = target;
+    }//end apply(&self, instance, forward)
+}//end impl for CellChange
+
+/// # DistrictsChange
+///
+/// How an edit changed the district list.
+/// Only the districts that were added, removed or changed are stored, without their locations, since those can be worked out from the city grid.
+#[derive(Clone, Debug)]
+struct DistrictsChange {
+    /// the ids of the districts in list order before and after the edit, if the edit added, removed or reordered districts
+    order: Option<(Vec<u32>, Vec<u32>)>,
+    /// the districts from before the edit that were removed or changed by it
+    before: Vec<Grouping>,
+    /// the districts from after the edit that were added or changed by it
+    after: Vec<Grouping>,
+}//end struct DistrictsChange
+
+impl DistrictsChange {
+    /// # between(before, after)
+    ///
+    /// works out what changed between two district lists, or returns None if nothing did.
+    /// Locations are ignored.
+    fn between(before:&[Grouping], after:&[Grouping]) -> Option<DistrictsChange> {
+        let before_ids: Vec<u32> = before.iter().map(|district| district.id).collect();
+        let after_ids: Vec<u32> = after.iter().map(|district| district.id).collect();
+        let before_by_id: HashMap<u32, &Grouping> = before.iter().map(|district| (district.id, district)).collect();
+        let after_by_id: HashMap<u32, &Grouping> = after.iter().map(|district| (district.id, district)).collect();
+        let changed_before: Vec<Grouping> = before.iter()
+            .filter(|district| !after_by_id.get(&district.id).is_some_and(|other| same_header(district, other)))
+            .map(district_header)
+            .collect();
+        let changed_after: Vec<Grouping> = after.iter()
+            .filter(|district| !before_by_id.get(&district.id).is_some_and(|other| same_header(district, other)))
+            .map(district_header)
+            .collect();
+        let order = if before_ids == after_ids {None} else {Some((before_ids, after_ids))};
+        if order.is_none() && changed_before.is_empty() && changed_after.is_empty() {return None;}
+        Some(DistrictsChange { order, before: changed_before, after: changed_after })
+    }//end between(before, after)
+
+    /// # apply(&self, grid, districts, forward)
+    ///
+    /// puts districts back how they were after this change if forward is true, or before it if forward is false.
+    /// Districts that stay in the list keep their locations, and districts that come back get theirs from grid.
+    fn apply(&self, grid:&Grid<GroupInstance>, districts:&mut Vec<Grouping>, forward:bool) {
+        let headers = if forward {&self.after} else {&self.before};
+        match &self.order {
+            None => {
+                for header in headers {
+                    if let Some(district) = districts.iter_mut().find(|district| district.id == header.id) {
+                        let locations = std::mem::take(&mut district.locations);
+                        *district = Grouping { locations, ..header.clone() };
+                    }//end if the district is in the list
+                }//end putting back each changed district
+            },
+            Some((order_before, order_after)) => {
+                let order = if forward {order_after} else {order_before};
+                let mut current: HashMap<u32, Grouping> = districts.drain(..).map(|district| (district.id, district)).collect();
+                for id in order {
+                    let header = headers.iter().find(|header| header.id == *id);
+                    let district = match (header, current.remove(id)) {
+                        (Some(header), Some(district)) => Grouping { locations: district.locations, ..header.clone() },
+                        (Some(header), None) => Grouping { locations: cells_in_district(grid, *id), ..header.clone() },
+                        (None, Some(district)) => district,
+                        (None, None) => continue,
+                    };//end matching where the district comes from
+                    districts.push(district);
+                }//end putting each district back in order
+            },
+        }//end matching whether the list itself changed
+    }//end apply(&self, grid, districts, forward)
+}//end impl for DistrictsChange
+
+/// # district_header(district)
+///
+/// copies everything about district except its locations
+fn district_header(district:&Grouping) -> Grouping {
+    Grouping {
+        id: district.id,
+        name: district.name.clone(),
+        locations: Vec::new(),
+        rgb_color: district.rgb_color,
+        locked: district.locked,
+    }//end struct construction
+}//end district_header(district)
+
+/// # same_header(a, b)
+///
+/// returns true if a and b are the same apart from their locations
+fn same_header(a:&Grouping, b:&Grouping) -> bool {
+    a.id == b.id && a.name == b.name && a.rgb_color == b.rgb_color && a.locked == b.locked
+}//end same_header(a, b)
+
+/// # Edit
+///
+/// One undoable change to the city, such as adding a district or painting a stroke of cells.
+/// An edit can change the district list, any number of cells in the city grid, or both.
+#[derive(Clone, Debug)]
+pub struct Edit {
+    /// short description of the edit, shown when undoing or redoing it
+    description: String,
+    /// the district list before the edit, without locations, kept only until the list after the edit is recorded
+    districts_before: Option<Vec<Grouping>>,
+    /// how the edit changed the district list, if it did
+    districts: Option<DistrictsChange>,
+    /// every cell the edit changed, in the order they were changed
+    cells: Vec<CellChange>,
+}//end struct Edit
+
+impl Edit {
+    /// # new(description)
+    ///
+    /// creates an edit that doesn't change anything yet
+    pub fn new(description:&str) -> Edit {
+        Edit {
+            description: description.to_string(),
+            districts_before: None,
+            districts: None,
+            cells: Vec::new(),
+        }//end struct construction
+    }//end new(description)
+
+    /// # description(&self)
+    pub fn description(&self) -> &str {
+        &self.description
+    }//end description(&self)
+
+    /// # record_districts_before(&mut self, districts)
+    ///
+    /// remembers the district list from before this edit, unless it was already recorded.
+    /// Locations aren't needed, since any cells that change districts are recorded separately with record_cell().
+    pub fn record_districts_before(&mut self, districts:&[Grouping]) {
+        if self.districts_before.is_none() {
+            self.districts_before = Some(districts.iter().map(district_header).collect());
+        }//end if we don't have it yet
+    }//end record_districts_before(&mut self, districts)
+
+    /// # record_districts_after(&mut self, districts)
+    ///
+    /// compares the district list from after this edit to the one from record_districts_before(), keeping only what changed
+    pub fn record_districts_after(&mut self, districts:&[Grouping]) {
+        if let Some(before) = self.districts_before.take() {
+            self.districts = DistrictsChange::between(&before, districts);
+        }//end if we know what the list looked like before
+    }//end record_districts_after(&mut self, districts)
+
+    /// # record_cell(&mut self, coord, before, after)
+    ///
+    /// remembers that the cell at coord went from before to after.
+    /// If the same cell was already changed earlier in this edit, the original before is kept.
+    pub fn record_cell(&mut self, coord:Coord, before:GroupInstance, after:GroupInstance) {
+        match self.cells.iter_mut().find(|change| change.coord == coord) {
+            Some(change) => change.merge(before, after),
+            None => self.cells.push(CellChange::new(coord, before, after)),
+        }//end matching whether this cell was already changed
+    }//end record_cell(&mut self, coord, before, after)
+
+    /// # is_empty(&self)
+    ///
+    /// returns true if this edit doesn't actually change anything
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.districts.is_none()
+    }//end is_empty(&self)
+
+    /// # changed_cells(&self)
+    ///
+    /// returns the coordinates of every cell this edit changed
+    pub fn changed_cells(&self) -> Vec<Coord> {
+        self.cells.iter().map(|change| change.coord).collect()
+    }//end changed_cells(&self)
+
+    /// # changes_districts(&self)
+    ///
+    /// returns true if this edit changed the district list
+    pub fn changes_districts(&self) -> bool {
+        self.districts.is_some()
+    }//end changes_districts(&self)
+
+    /// # apply(&self, grid, districts, forward)
+    ///
+    /// puts grid and districts back how they were after this edit if forward is true, or before it if forward is false.
+    /// The locations of each district are kept matching the cells of grid.
+    fn apply(&self, grid:&mut Grid<GroupInstance>, districts:&mut Vec<Grouping>, forward:bool) {
+        for change in &self.cells {
+            if let Some(instance) = grid.get_mut(change.coord.row, change.coord.col) {
+                change.apply(instance, forward);
+            }//end if the cell is still in the grid
+        }//end putting back each changed cell
+
+        // move the changed cells to the districts they belong to now
+        if !self.cells.is_empty() {
+            let changed: HashSet<Coord> = self.cells.iter().map(|change| change.coord).collect();
+            for district in districts.iter_mut() {
+                district.locations.retain(|location| !changed.contains(location));
+            }//end taking the changed cells away from every district
+            for change in &self.cells {
+                let district_id = grid.get(change.coord.row, change.coord.col).and_then(|instance| instance.district_id);
+                if let Some(district) = districts.iter_mut().find(|district| Some(district.id) == district_id) {
+                    district.locations.push(change.coord);
+                }//end if the cell's district is in the list
+            }//end giving each changed cell to its district
+        }//end if any cells changed
+
+        if let Some(change) = &self.districts {
+            change.apply(grid, districts, forward);
+        }//end if this edit changed the district list
+    }//end apply(&self, grid, districts, forward)
+}//end impl for Edit
+
+/// # History
+///
+/// Keeps track of edits so they can be undone and redone.
+/// Edits made by dragging the mouse are collected in a pending edit until the mouse is released, so a whole stroke is undone at once.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo_stack: VecDeque<Edit>,
+    redo_stack: Vec<Edit>,
+    pending: Option<Edit>,
+}//end struct History
+
+impl History {
+    /// # push(&mut self, edit)
+    ///
+    /// adds a finished edit to the history, forgetting anything that could have been redone
+    pub fn push(&mut self, edit:Edit) {
+        if edit.is_empty() {return;}
+        self.undo_stack.push_back(edit);
+        if self.undo_stack.len() > get_max_history_len() {
+            self.undo_stack.pop_front();
+        }//end if we need to forget the oldest edit
+        self.redo_stack.clear();
+    }//end push(&mut self, edit)
+
+    /// # pending(&mut self, description)
+    ///
+    /// returns the edit currently being collected, starting a new one with description if there isn't one
+    pub fn pending(&mut self, description:&str) -> &mut Edit {
+        self.pending.get_or_insert_with(|| Edit::new(description))
+    }//end pending(&mut self, description)
+
+    /// # pending_mut(&mut self)
+    ///
+    /// returns the edit currently being collected, if there is one
+    pub fn pending_mut(&mut self) -> Option<&mut Edit> {
+        self.pending.as_mut()
+    }//end pending_mut(&mut self)
+
+    /// # finish_pending(&mut self)
+    ///
+    /// moves the edit being collected into the history
+    pub fn finish_pending(&mut self) {
+        if let Some(edit) = self.pending.take() {
+            self.push(edit);
+        }//end if there was anything being collected
+    }//end finish_pending(&mut self)
+
+    /// # undo(&mut self, grid, districts)
+    ///
+    /// undoes the most recent edit to grid and districts.
+    ///
+    /// ## Return
+    /// Returns the edit that was undone, or None if there was nothing to undo.
+    pub fn undo(&mut self, grid:&mut Grid<GroupInstance>, districts:&mut Vec<Grouping>) -> Option<&Edit> {
+        self.finish_pending();
+        let edit = self.undo_stack.pop_back()?;
+        edit.apply(grid, districts, false);
+        self.redo_stack.push(edit);
+        self.redo_stack.last()
+    }//end undo(&mut self, grid, districts)
+
+    /// # redo(&mut self, grid, districts)
+    ///
+    /// redoes the most recently undone edit to grid and districts.
+    ///
+    /// ## Return
+    /// Returns the edit that was redone, or None if there was nothing to redo.
+    pub fn redo(&mut self, grid:&mut Grid<GroupInstance>, districts:&mut Vec<Grouping>) -> Option<&Edit> {
+        self.finish_pending();
+        let edit = self.redo_stack.pop()?;
+        edit.apply(grid, districts, true);
+        self.undo_stack.push_back(edit);
+        self.undo_stack.back()
+    }//end redo(&mut self, grid, districts)
+
+    /// # clear(&mut self)
+    ///
+    /// forgets every edit, for when the whole city gets replaced
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.pending = None;
+    }//end clear(&mut self)
+}//end impl for History
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::move_districts;
+    use crate::editing::reassign_district_cell;
+    use crate::grouping::two_district_city;
+    use crate::grouping::BuildingType;
+
+    /// # city_with_neighborhoods()
+    ///
+    /// Makes the two_district_city() with a 2x2 neighborhood of empty buildings in every cell
+    fn city_with_neighborhoods() -> (Grid<GroupInstance>, Vec<Grouping>) {
+        let (mut grid, districts) = two_district_city();
+        for instance in grid.iter_mut() {
+            instance.initialize_sub_grid(2, 2);
+        }//end giving each cell a neighborhood
+        (grid, districts)
+    }//end city_with_neighborhoods()
+
+    /// # sorted_locations(districts)
+    ///
+    /// returns the locations of each district in a consistent order, since edits don't keep the order of locations
+    fn sorted_locations(districts:&[Grouping]) -> Vec<Vec<Coord>> {
+        districts.iter().map(|district| {
+            let mut locations = district.locations.clone();
+            locations.sort();
+            locations
+        }).collect()
+    }//end sorted_locations(districts)
+
+    #[test]
+    fn undo_and_redo_paint_stroke() {
+        let (mut grid, mut districts) = city_with_neighborhoods();
+        let (grid_before, districts_before) = (grid.clone(), districts.clone());
+        let mut history = History::default();
+        for coord in [Coord::new(0, 0), Coord::new(1, 0)] {
+            let before = grid.get(coord.row, coord.col).unwrap().clone();
+            assert!(reassign_district_cell(&mut grid, &mut districts, coord, 1));
+            history.pending("Paint districts").record_cell(coord, before, grid.get(coord.row, coord.col).unwrap().clone());
+        }//end painting each cell
+        history.finish_pending();
+        let (grid_after, districts_after) = (grid.clone(), districts.clone());
+
+        let edit = history.undo(&mut grid, &mut districts).unwrap();
+        assert_eq!(edit.changed_cells().len(), 2);
+        assert!(!edit.changes_districts());
+        assert_eq!(grid, grid_before);
+        assert_eq!(sorted_locations(&districts), sorted_locations(&districts_before));
+        history.redo(&mut grid, &mut districts).unwrap();
+        assert_eq!(grid, grid_after);
+        assert_eq!(sorted_locations(&districts), sorted_locations(&districts_after));
+        assert!(history.redo(&mut grid, &mut districts).is_none());
+    }//end undo_and_redo_paint_stroke()
+
+    #[test]
+    fn unchanged_neighborhoods_are_not_stored() {
+        let (grid, _) = city_with_neighborhoods();
+        let before = grid.get(0, 0).unwrap().clone();
+        let mut after = before.clone();
+        after.district_id = Some(2);
+        let mut edit = Edit::new("Paint districts");
+        edit.record_cell(Coord::new(0, 0), before.clone(), after.clone());
+        assert!(edit.cells[0].same_neighborhood);
+        assert!(edit.cells[0].before.sub_grid.is_empty());
+
+        // regenerating the same cell later in the stroke still remembers the original neighborhood
+        let mut regenerated = after.clone();
+        regenerated.sub_grid.get_mut(0, 0).unwrap().build_type = BuildingType::Park;
+        edit.record_cell(Coord::new(0, 0), after, regenerated.clone());
+        let mut grid = grid;
+        edit.apply(&mut grid, &mut Vec::new(), false);
+        assert_eq!(*grid.get(0, 0).unwrap(), before);
+        edit.apply(&mut grid, &mut Vec::new(), true);
+        assert_eq!(*grid.get(0, 0).unwrap(), regenerated);
+    }//end unchanged_neighborhoods_are_not_stored()
+
+    #[test]
+    fn undo_and_redo_regeneration() {
+        let (mut grid, mut districts) = city_with_neighborhoods();
+        let (old_grid, old_districts) = (grid.clone(), districts.clone());
+        // regenerating gives the top right cell to the left district with a new neighborhood
+        let mut new_districts = old_districts.clone();
+        new_districts[1].locations.retain(|location| *location != Coord::new(0, 1));
+        new_districts[0].locations.push(Coord::new(0, 1));
+        let cell = grid.get_mut(0, 1).unwrap();
+        cell.district_id = Some(1);
+        cell.sub_grid.get_mut(1, 1).unwrap().build_type = BuildingType::Road;
+        districts = new_districts.clone();
+
+        let mut edit = Edit::new("Regenerate unlocked");
+        edit.record_districts_before(&old_districts);
+        edit.record_districts_after(&new_districts);
+        edit.record_cell(Coord::new(0, 1), old_grid.get(0, 1).unwrap().clone(), grid.get(0, 1).unwrap().clone());
+        let new_grid = grid.clone();
+        let mut history = History::default();
+        history.push(edit);
+
+        history.undo(&mut grid, &mut districts).unwrap();
+        assert_eq!(grid, old_grid);
+        assert_eq!(sorted_locations(&districts), sorted_locations(&old_districts));
+        history.redo(&mut grid, &mut districts).unwrap();
+        assert_eq!(grid, new_grid);
+        assert_eq!(sorted_locations(&districts), sorted_locations(&new_districts));
+    }//end undo_and_redo_regeneration()
+
+    #[test]
+    fn undo_and_redo_district_list_edits() {
+        let (mut grid, mut districts) = city_with_neighborhoods();
+        let original = districts.clone();
+        let mut history = History::default();
+
+        let mut edit = Edit::new("Add district");
+        edit.record_districts_before(&districts);
+        districts.push(Grouping::new("new".to_string()).with_id(3));
+        edit.record_districts_after(&districts);
+        history.push(edit);
+
+        let mut edit = Edit::new("Set district color");
+        edit.record_districts_before(&districts);
+        districts[0].rgb_color = (10,20,30);
+        edit.record_districts_after(&districts);
+        assert_eq!(edit.districts.as_ref().unwrap().after.len(), 1);
+        assert!(edit.districts.as_ref().unwrap().order.is_none());
+        history.push(edit);
+
+        let mut edit = Edit::new("Reorder districts");
+        edit.record_districts_before(&districts);
+        move_districts(&mut districts, &[2], true);
+        edit.record_districts_after(&districts);
+        history.push(edit);
+
+        let mut edit = Edit::new("Remove districts");
+        edit.record_districts_before(&districts);
+        districts.remove(0);
+        edit.record_districts_after(&districts);
+        history.push(edit);
+        let last = districts.clone();
+
+        for _ in 0..4 {
+            history.undo(&mut grid, &mut districts).unwrap();
+        }//end undoing every edit
+        assert_eq!(districts, original);
+        for _ in 0..4 {
+            history.redo(&mut grid, &mut districts).unwrap();
+        }//end redoing every edit
+        assert_eq!(districts, last);
+
+        // the removed district gets its cells back from the grid when the removal is undone
+        history.undo(&mut grid, &mut districts).unwrap();
+        assert_eq!(districts[0].rgb_color, (10,20,30));
+        assert_eq!(districts[0].locations, vec![Coord::new(0, 0), Coord::new(1, 0)]);
+    }//end undo_and_redo_district_list_edits()
+
+    #[test]
+    fn edits_that_change_nothing_are_ignored() {
+        let (mut grid, mut districts) = city_with_neighborhoods();
+        let mut history = History::default();
+        let mut edit = Edit::new("Rename district");
+        edit.record_districts_before(&districts);
+        edit.record_districts_after(&districts);
+        assert!(edit.is_empty());
+        history.push(edit);
+        assert!(history.undo(&mut grid, &mut districts).is_none());
+    }//end edits_that_change_nothing_are_ignored()
+
+    #[test]
+    fn oldest_edits_are_forgotten() {
+        let (mut grid, mut districts) = city_with_neighborhoods();
+        let mut history = History::default();
+        for index in 0..get_max_history_len() + 5 {
+            let mut edit = Edit::new("Set district color");
+            edit.record_districts_before(&districts);
+            districts[0].rgb_color = (0,0,(index % 200) as u8 + 1);
+            edit.record_districts_after(&districts);
+            history.push(edit);
+        }//end making more edits than the history holds
+        let mut num_undone = 0;
+        while history.undo(&mut grid, &mut districts).is_some() {
+            num_undone += 1;
+        }//end undoing everything we can
+        assert_eq!(num_undone, get_max_history_len());
+        assert_eq!(districts[0].rgb_color, (0,0,5));
+    }//end oldest_edits_are_forgotten()
+}//end mod tests
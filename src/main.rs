@@ -1,475 +1,914 @@
+#![allow(clippy::needless_return)]
 use fltk::app::App;
-use fltk_theme::SchemeType;
-use fltk_theme::WidgetScheme;
+use generation::GenProgress;
+use generation::GenerationJob;
+use generation::GrowthRecording;
+use generation::LazyNeighborhoods;
+use generation::create_empty_grid;
+use generation::regenerate_neighborhood;
+use editing::move_districts;
+use editing::reassign_district_cell;
+use editing::rename_district;
+use editing::set_building_info;
+use editing::set_building_locked;
+use editing::set_building_type;
+use editing::set_cell_locked;
+use editing::set_district_locked;
+use editing::validate_district_name;
 use grid::Grid;
-use grouping::Building;
-use grouping::BuildingType;
 use grouping::GroupInstance;
+use export::export_city_csv;
+use export::check_png_size;
+use export::export_city_png;
+use export::largest_neighborhood;
+use export::export_growth_gif;
+use export::stitch_city;
 use gui::GUI;
+use history::Edit;
+use history::History;
+use gui::MenuChoice;
+use palette::BuildingPalette;
+use palette::gen_distinct_colors;
+use palette::get_palette_presets;
 use rand::Rng;
+use save::load_city;
+use save::save_city;
+use settings::AppSettings;
+use theme::ThemeManager;
+use theme::get_color_theme_names;
+use theme::get_schemes;
 mod grouping;
 use grouping::Coord;
 use grouping::Grouping;
-use rand::rngs::ThreadRng;
+use grouping::district_at;
+mod district_list;
+mod editing;
+mod error;
+mod export;
+mod generation;
 mod gui;
+mod history;
+mod map_view;
+mod palette;
+mod save;
+mod settings;
+mod theme;
+
+/// # get_max_compare_windows()
+/// 
+/// The most neighborhoods that can be opened in their own windows at once, so a big selection doesn't bury the screen in windows
+fn get_max_compare_windows() -> usize {12}
 
 fn main() {
     // create random number generator for whole program
     let mut rng = rand::thread_rng();
     // create our empty grid
     let mut city_grid: Grid<GroupInstance> = create_empty_grid(10, 10);
+    // create the building palette shared by every neighborhood in the city
+    let mut building_palette = BuildingPalette::default_preset();
+    // keep track of which neighborhood is shown, so it can be refreshed
+    let mut cur_nhood_coord: Option<Coord> = None;
+    // the city generation running in the background, if any
+    let mut gen_job: Option<GenerationJob> = None;
+    // edits that can be undone or redone
+    let mut history = History::default();
+    // the city and districts from before regenerating unlocked parts, so the regeneration can be undone
+    let mut partial_regen_base: Option<(Grid<GroupInstance>, Vec<Grouping>)> = None;
+    // how to generate the neighborhoods that were left until they're needed, if any were
+    let mut lazy_nhoods: Option<LazyNeighborhoods> = None;
+    // how the districts grew the last time a city was generated, for playing back or exporting
+    let mut growth_recording: Option<GrowthRecording> = None;
+    // while growth is shown on the district map, the number of steps shown so far
+    let mut growth_playback: Option<usize> = None;
+    // the top left and bottom right district cells selected for comparing their neighborhoods
+    let mut district_selection: Option<(Coord,Coord)> = None;
+    // the cell the current selection stroke started from
+    let mut selection_anchor: Option<Coord> = None;
+    // whether the lock tool is currently locking or unlocking, decided by the first cell of each stroke
+    let mut lock_stroke: Option<bool> = None;
+    // whether cells of the current paint or lock stroke were ignored because a city was being generated
+    let mut stroke_ignored = false;
+    // settings remembered from last time
+    let (mut settings, settings_problem) = match AppSettings::load() {
+        Ok((settings, skipped)) if skipped.is_empty() => (settings, None),
+        Ok((settings, skipped)) => {
+            // a badly broken file could skip every line, which wouldn't fit in a dialog
+            let mut shown = skipped.iter().take(10).cloned().collect::<Vec<String>>().join("\n");
+            if skipped.len() > 10 {
+                shown.push_str(&format!("\nand {} more", skipped.len() - 10));
+            }//end if some were left out
+            (settings, Some(format!("Some lines of the settings file couldn't be read, so they were skipped:\n{}", shown)))
+        },
+        Err(e) => (AppSettings::default(), Some(format!("Couldn't read the settings file, so the defaults are used instead.\n{}", e))),
+    };//end matching whether the settings could be read
     // create application object
     let app = App::default();
-    // create theme objects
-    let widget_scheme = WidgetScheme::new(SchemeType::Fluent);
-    widget_scheme.apply();
+    // create theme objects, before any color theme changes the default colors
+    let themes = ThemeManager::new();
+    settings.scheme = themes.apply_scheme(&settings.scheme).to_string();
+    settings.color_theme = themes.apply_color_theme(&settings.color_theme).to_string();
     
     // set up gui
     let mut gui = GUI::default(&app);
     gui.initialize_top_menu();
     gui.initialize_settings();
+    gui.update_district_legend();
+    gui.update_building_legend(&building_palette);
+    gui.apply_settings(&settings);
+    gui.update_recent_files_menu(&settings.recent_files);
+    gui.check_theme_items(&settings.scheme, &settings.color_theme);
     
     // show the gui
     gui.show();
+    if let Some(problem) = settings_problem {
+        gui.show_message(&problem);
+    }//end if there was trouble with the settings
     while app.wait() {
         if let Some(val) = gui.menu_msg_receiver.recv() {
-            match val.as_str() {
-                "MenuChoice::SetColor" => {
-                    let dist_index_option = gui.choose_district();
-                    if dist_index_option.is_some() {
-                        let dist_index = dist_index_option.unwrap();
+            // strokes send a message for every cell they pass over, so they're ignored quietly and only mentioned once the stroke is done
+            if gen_job.is_some() && is_stroke_cell(&val) {
+                stroke_ignored = true;
+                continue;
+            }//end if this cell of a stroke has to wait
+            if stroke_ignored && matches!(val, MenuChoice::PaintDistrictsFinished | MenuChoice::PaintBuildingsFinished) {
+                stroke_ignored = false;
+                gui.show_message("Please wait for generation to finish, or cancel it first.");
+            }//end if part of this stroke was ignored
+            // don't let the district list change out from under the generation thread
+            if gen_job.is_some() && is_blocked_while_generating(&val) {
+                gui.show_message("Please wait for generation to finish, or cancel it first.");
+                continue;
+            }//end if this message has to wait
+            // anything that could change the district map puts it back to showing the actual city
+            if growth_playback.is_some() && is_blocked_while_generating(&val) {
+                stop_growth_playback(&mut gui, &city_grid, &mut growth_playback);
+            }//end if we're in the middle of playing back growth
+            match val {
+                MenuChoice::SetColor => {
+                    let selected = gui.selected_districts();
+                    if selected.is_empty() {
+                        gui.show_message("Please select one or more districts in the list first.");
+                    }//end if nothing is selected
+                    else {
+                        // get a color from user
+                        let color_prompt = match selected.as_slice() {
+                            [dist_index] => format!("Please select a color for district {}", gui.districts[*dist_index].name),
+                            _ => format!("Please select a color for the {} selected districts", selected.len()),
+                        };//end matching how many districts we're coloring
+                        gui.show_message(&color_prompt);
+                        if let Some(color) = gui.get_color() {
+                            let mut edit = Edit::new("Set district color");
+                            edit.record_districts_before(&gui.districts);
+                            for dist_index in &selected {
+                                gui.districts[*dist_index].rgb_color = color;
+                            }//end recoloring each selected district
+                            edit.record_districts_after(&gui.districts);
+                            history.push(edit);
+                            gui.update_district_list();
+                            gui.refresh_district_map(&city_grid);
+                        }//end if we got a color to use
+                    }//end else we have districts to color
+                },
+                MenuChoice::AddDistrict => {
+                    let mut new_district = Grouping::new(get_unused_district_name(&gui.districts)).with_id(gui.take_district_id());
+                    // pick a color that looks as different as possible from the other districts
+                    let existing_colors: Vec<(u8,u8,u8)> = gui.districts.iter().map(|district| district.rgb_color).collect();
+                    let color_options = gen_distinct_colors(&mut rng, 1, &existing_colors);
+                    new_district.rgb_color = color_options.first().unwrap().to_owned();
+                    println!("Adding district {}", new_district.name);
+                    let mut edit = Edit::new("Add district");
+                    edit.record_districts_before(&gui.districts);
+                    gui.districts.push(new_district);
+                    edit.record_districts_after(&gui.districts);
+                    history.push(edit);
+                    gui.update_district_list();
+                    // let the user name the new district right away
+                    gui.set_selected_districts(&[gui.districts.len() - 1]);
+                    gui.focus_district_name_input();
+                },
+                MenuChoice::RemoveDistrict => {
+                    let selected = gui.selected_districts();
+                    if selected.is_empty() {
+                        gui.show_message("Please select one or more districts in the list first.");
+                    }//end if nothing is selected
+                    else {
+                        let mut edit = Edit::new("Remove districts");
+                        edit.record_districts_before(&gui.districts);
+                        // remove from the bottom up so earlier indices stay valid
+                        for dist_index in selected.iter().rev() {
+                            let removed = gui.districts.remove(*dist_index);
+                            println!("Removed district {}", removed.name);
+                        }//end removing each selected district
+                        edit.record_districts_after(&gui.districts);
+                        history.push(edit);
+                        gui.set_selected_districts(&[]);
+                        gui.update_district_list();
+                        // cells of removed districts show up as empty now
+                        gui.refresh_district_map(&city_grid);
+                    }//end else we have districts to remove
+                },
+                MenuChoice::RenameDistrict => {
+                    if let [dist_index] = gui.selected_districts().as_slice() {
+                        let dist_index = *dist_index;
+                        match validate_district_name(&gui.get_district_name_input(), &gui.districts, Some(dist_index)) {
+                            Err(msg) => gui.show_message(&msg),
+                            Ok(new_name) if !gui.districts[dist_index].name.eq(&new_name) => {
+                                let mut edit = Edit::new("Rename district");
+                                edit.record_districts_before(&gui.districts);
+                                let changed_cells = rename_district(&city_grid, &mut gui.districts, dist_index, &new_name);
+                                edit.record_districts_after(&gui.districts);
+                                history.push(edit);
+                                gui.update_district_cells(&city_grid, &changed_cells);
+                                gui.update_district_list();
+                                gui.update_district_legend();
+                            },
+                            Ok(_) => {},
+                        }//end matching whether the new name is allowed
+                    }//end if exactly one district is selected
+                },
+                MenuChoice::MoveDistrictsUp | MenuChoice::MoveDistrictsDown => {
+                    let mut edit = Edit::new("Reorder districts");
+                    edit.record_districts_before(&gui.districts);
+                    let selected = gui.selected_districts();
+                    let new_selected = move_districts(&mut gui.districts, &selected, val == MenuChoice::MoveDistrictsUp);
+                    edit.record_districts_after(&gui.districts);
+                    history.push(edit);
+                    gui.update_district_list();
+                    gui.set_selected_districts(&new_selected);
+                },
+                MenuChoice::SetBuildingColor => {
+                    let build_type_option = gui.choose_building_type();
+                    if let Some(build_type) = build_type_option {
                         // get a color from user
-                        gui.show_message(&format!("Please select a color for district {}", gui.districts.get(dist_index).unwrap().name));
+                        gui.show_message(&format!("Please select a color for building type {}", build_type));
                         let gui_color_result = gui.get_color();
-                        if gui_color_result.is_some() {
-                            gui.districts.get_mut(dist_index).unwrap().rgb_color = gui_color_result.unwrap();
-                            gui.update_district_list_buf();
+                        if let Some(color) = gui_color_result {
+                            building_palette.set_color(&build_type, color);
+                            update_building_palette(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord);
                         }//end if we got a color to use
-                    }//end if we got something
-                },
-                "MenuChoice::AddDistrict" => {
-                    let new_dist_name = gui.get_new_district_name();
-                    if new_dist_name.is_some() {
-                        let mut new_district = Grouping::new(new_dist_name.unwrap());
-                        let color_options = gen_nhood_colors(&mut rng, 42);
-                        let color_option = color_options.get(rng.gen_range(0..color_options.len())).unwrap();
-                        new_district.rgb_color = (color_option.0, color_option.1, color_option.2);
-                        println!("Adding district {}", new_district.name);
-                        gui.districts.push(new_district);
-                        gui.update_district_list_buf();
-                    }//end if we got a name for a new district
-                },
-                "MenuChoice::RemoveDistrict" => {
-                    // figure out what to remove
-                    let district_index_to_remove = gui.choose_district();
-                    // remove the specified district
-                    if district_index_to_remove.is_some() {
-                        let removed = gui.districts.remove(district_index_to_remove.unwrap());
-                        println!("Removed district {}", removed.name);
-                        gui.update_district_list_buf();
-                    }//end if we can remove one
-                },
-                "MenuChoice::GenerateDistricts" => {
-                    // figure out district row and column width to make new grid
-                    let distr_dims = gui.get_districts_dims();
-                    city_grid = create_empty_grid(distr_dims.0, distr_dims.1);
-                    // figure out neighborhood row and column width for inner grids
-                    let neigh_dims = gui.get_neighborhood_dims();
-
-                    // reset district locations
-                    gui.clear_district_locations();
-
-                    // add group starts in random spots
-                    println!("\nStarting grid priming");
-                    prime_grid_with_groups(&mut city_grid, &mut gui.districts, &mut rng, neigh_dims.0, neigh_dims.1);
-                    println!("\nGrid is primed.");
-                    
-                    // advance groups until enclosed
-                    let mut all_enclosed = false;
-                    println!("\nStarting grid generation");
-                    // counter to keep track of iteration
-                    let mut num_iterations: usize = 0;
-                    while !all_enclosed {
-                        let num_enclosed = advance_group_expansion(&mut city_grid, &mut gui.districts, &mut rng, neigh_dims.0, neigh_dims.1);
-                        //println!("{} groups are fully enclosed", &num_enclosed);
-                        all_enclosed = num_enclosed.eq(&gui.districts.len());
-                        // update iteration number
-                        num_iterations += 1;
-                        // potentially print iterations if they're high
-                        if num_iterations < 10 || num_iterations % 10 == 0 {
-                            if num_iterations < 100 || num_iterations % 100 == 0 {
-                                if num_iterations < 1000 || num_iterations % 1000 == 0 {
-                                    if num_iterations < 5000 || num_iterations % 5000 == 0 {
-                                        println!("Reached the iteration {} of group expansion!", num_iterations);
-                                    }//end if fourth interval
-                                }//end if third interval
-                            }//end if num_iterations is on interval
-                        }//end if first interval
-                        // previous printed numbers
-                        let mut prev_print = 0;
-                        // potentially print num enclosed if they're high
-                        if gui.districts.len() < 10 || num_enclosed % 10 == 0 {
-                            if gui.districts.len() < 100 || num_enclosed % 50 == 0 {
-                                if prev_print < num_enclosed {
-                                    println!("{} groups have been enclosed!", num_enclosed);
-                                    prev_print = num_enclosed;
-                                }//end if not printed before
-                            }//end if 100 interval
-                        }//end if 10 interval
-                    }//end looping while some groupings are still able to expand
-                    println!("\nFinished generating grid");
-
-                    // display the new grid stuff
-                    gui.update_grid(&city_grid);
-
-                    // generate all neighborhoods
-                    println!("\nStarting neighborhood generation");
-                    for row in 0..city_grid.rows() {
-                        for col in 0..city_grid.cols() {
-                            let this_instance = city_grid.get_mut(row, col).expect("valid index");
-                            generate_neighborhood(this_instance, &mut rng);
-                        }//end looping over cols in city grid
-                    }//end looping over rows in city grid
-                    println!("Finished neighborhood generation\n");
-                    // switch to district tab so user can see what happened
-                    gui.switch_tab(1);
-                },
-                _ => {
-                    if val.contains(',') {
-                        let coord_vals: Vec<&str> = val.split(',').collect();
-                        if coord_vals.len() == 2 {
-                            // get our row and col index parsed
-                            let row_idx:usize = coord_vals.get(0).unwrap().parse().unwrap();
-                            let col_idx:usize = coord_vals.get(1).unwrap().parse().unwrap();
-                            // print out the row-col pair for all to see
-                            println!("Received message asking after neighborhood at row {} and column {}", row_idx + 1, col_idx + 1);
-                            // TODO: Send Message to GUI to display neighborhood at coordinate
-                            // get the GroupInstance at the specified coordinate
-                            let this_nhood = city_grid.get(row_idx, col_idx).unwrap();
-                            // display it, hopefully ????
+                    }//end if we got a building type
+                },
+                MenuChoice::RandomizeBuildingColors => {
+                    building_palette = BuildingPalette::from_seed(rng.gen());
+                    update_building_palette(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord);
+                },
+                MenuChoice::ApplyPaletteSeed => {
+                    match gui.get_palette_seed() {
+                        Some(seed) => {
+                            building_palette = BuildingPalette::from_seed(seed);
+                            update_building_palette(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord);
+                        },
+                        None => gui.show_message("Please enter a whole number to use as the building color seed."),
+                    }//end matching whether the seed was valid
+                },
+                MenuChoice::DefaultBuildingColors => {
+                    building_palette = BuildingPalette::default_preset();
+                    update_building_palette(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord);
+                },
+                MenuChoice::BuildingPreset => {
+                    if let Some(preset_index) = gui.choose_preset("building colors") {
+                        let preset = get_palette_presets().get(preset_index).unwrap().to_owned();
+                        building_palette = BuildingPalette::from_preset(&preset);
+                        update_building_palette(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord);
+                    }//end if user picked a preset
+                },
+                MenuChoice::DistrictPreset => {
+                    if let Some(preset_index) = gui.choose_preset("district colors") {
+                        let preset = get_palette_presets().get(preset_index).unwrap().to_owned();
+                        let colors = preset.extended_colors(gui.districts.len(), &[]);
+                        let mut edit = Edit::new("Apply district preset");
+                        edit.record_districts_before(&gui.districts);
+                        for (district, color) in gui.districts.iter_mut().zip(colors) {
+                            district.rgb_color = color;
+                        }//end recoloring each district
+                        edit.record_districts_after(&gui.districts);
+                        history.push(edit);
+                        gui.update_district_list();
+                        gui.refresh_district_map(&city_grid);
+                    }//end if user picked a preset
+                },
+                MenuChoice::ExportCityImage => {
+                    if let Some(path) = gui.get_export_path("Export City Image", "*.png", "city.png", settings.export_dir.as_deref()) {
+                        settings.export_dir = path.parent().map(|dir| dir.to_path_buf());
+                        if let Some(scale) = gui.get_export_scale("building", 4) {
+                            // check the size first, since generating and stitching a city that's too big to export would be wasted
+                            let (mut nhood_rows, mut nhood_cols) = largest_neighborhood(&city_grid, 0..city_grid.rows(), 0..city_grid.cols());
+                            if let Some(lazy) = lazy_nhoods {
+                                nhood_rows = nhood_rows.max(lazy.dims.0);
+                                nhood_cols = nhood_cols.max(lazy.dims.1);
+                            }//end if some neighborhoods aren't generated yet
+                            let result = check_png_size(city_grid.rows() * nhood_rows, city_grid.cols() * nhood_cols, scale).and_then(|_| {
+                                materialize_all_neighborhoods(&mut gui, &mut city_grid, &building_palette, lazy_nhoods);
+                                export_city_png(&path, &stitch_city(&city_grid), scale, true)
+                            });
+                            match result {
+                                Ok(()) => println!("Exported city image to {}", path.display()),
+                                Err(e) => gui.show_message(&format!("Couldn't export the city image:\n{}", e)),
+                            }//end matching whether export worked
+                        }//end if we got a scale
+                    }//end if we got a path
+                },
+                MenuChoice::ExportCityCsv => {
+                    if let Some(path) = gui.get_export_path("Export City CSV", "*.csv", "city.csv", settings.export_dir.as_deref()) {
+                        settings.export_dir = path.parent().map(|dir| dir.to_path_buf());
+                        match export_city_csv(&path, &city_grid, &gui.districts, lazy_nhoods.map(|lazy| (lazy, &building_palette))) {
+                            Ok(()) => println!("Exported city csv to {}", path.display()),
+                            Err(e) => gui.show_message(&format!("Couldn't export the city csv:\n{}", e)),
+                        }//end matching whether export worked
+                    }//end if we got a path
+                },
+                MenuChoice::SaveCity => {
+                    if let Some(path) = gui.get_save_path("Save City", "*.city", "city.city") {
+                        match save_city(&path, &gui.districts, &city_grid, lazy_nhoods) {
+                            Ok(()) => {
+                                println!("Saved city to {}", path.display());
+                                remember_recent_file(&mut gui, &mut settings, &path);
+                            },
+                            Err(e) => gui.show_message(&format!("Couldn't save the city:\n{}", e)),
+                        }//end matching whether saving worked
+                    }//end if we got a path
+                },
+                MenuChoice::OpenCity | MenuChoice::OpenRecent(_) => {
+                    let path = match val {
+                        MenuChoice::OpenRecent(index) => settings.recent_files.get(index).cloned(),
+                        _ => gui.get_open_path("Open City", "*.city"),
+                    };//end matching where to open from
+                    if let Some(path) = path {
+                        match load_city(&path) {
+                            Ok((districts, mut new_grid, new_lazy_nhoods)) => {
+                                println!("Loaded city from {}", path.display());
+                                remember_recent_file(&mut gui, &mut settings, &path);
+                                building_palette.recolor_grid(&mut new_grid);
+                                city_grid = new_grid;
+                                lazy_nhoods = new_lazy_nhoods;
+                                // save files don't keep track of how the districts grew
+                                growth_recording = None;
+                                gui.districts = districts;
+                                gui.reserve_district_ids();
+                                // edits were made to the old city, so they can't be undone anymore
+                                history.clear();
+                                cur_nhood_coord = None;
+                                gui.clear_neighborhood_navigation();
+                                district_selection = None;
+                                gui.set_district_selection(None);
+                                gui.set_selected_districts(&[]);
+                                gui.update_district_list();
+                                gui.update_district_legend();
+                                gui.update_grid(&city_grid);
+                                gui.update_city_tab(&city_grid, &building_palette);
+                                gui.switch_tab(1);
+                            },
+                            Err(e) => gui.show_message(&format!("Couldn't open the city:\n{}", e)),
+                        }//end matching whether loading worked
+                    }//end if we got a path
+                },
+                MenuChoice::GenerateDistricts => {
+                    // figure out district row and column width to make new grid, and neighborhood row and column width for inner grids
+                    // start generating in the background so the window stays responsive
+                    let job = match (gui.get_districts_dims(), gui.get_neighborhood_dims(), gui.get_city_seed()) {
+                        (Ok(distr_dims), Ok(neigh_dims), Ok(seed)) => GenerationJob::spawn(gui.districts.clone(), distr_dims, neigh_dims, building_palette.clone(), seed.unwrap_or_else(|| rng.gen()), gui.lazy_neighborhoods()),
+                        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(e),
+                    };//end matching whether the settings could be read
+                    match job {
+                        Ok(job) => {
+                            println!("\nStarting city generation with seed {}", job.seed);
+                            gen_job = Some(job);
+                            partial_regen_base = None;
+                            gui.set_generating(true);
+                            gui.set_generation_progress(0.0, "Starting generation");
+                        },
+                        Err(e) => gui.show_message(&e.to_string()),
+                    }//end matching whether generation could start
+                },
+                MenuChoice::RegenerateUnlocked => {
+                    let job = match (gui.get_neighborhood_dims(), gui.get_city_seed()) {
+                        (Ok(neigh_dims), Ok(seed)) => GenerationJob::spawn_partial(city_grid.clone(), gui.districts.clone(), neigh_dims, building_palette.clone(), seed.unwrap_or_else(|| rng.gen()), gui.lazy_neighborhoods()),
+                        (Err(e), _) | (_, Err(e)) => Err(e),
+                    };//end matching whether the settings could be read
+                    match job {
+                        Ok(job) => {
+                            println!("\nStarting regeneration of unlocked cells with seed {}", job.seed);
+                            gen_job = Some(job);
+                            partial_regen_base = Some((city_grid.clone(), gui.districts.clone()));
+                            gui.set_generating(true);
+                            gui.set_generation_progress(0.0, "Starting regeneration");
+                        },
+                        Err(e) => gui.show_message(&e.to_string()),
+                    }//end matching whether regeneration could start
+                },
+                MenuChoice::LockDistrict => {
+                    let selected = gui.selected_districts();
+                    if selected.is_empty() {
+                        gui.show_message("Please select one or more districts in the list first.");
+                    }//end if nothing is selected
+                    else {
+                        // lock everything unless the first selected district is already locked
+                        let locked = !gui.districts[selected[0]].locked;
+                        let mut edit = Edit::new(if locked {"Lock districts"} else {"Unlock districts"});
+                        edit.record_districts_before(&gui.districts);
+                        for district_index in &selected {
+                            let changed_cells = set_district_locked(&city_grid, &mut gui.districts, *district_index, locked);
+                            gui.update_district_cells(&city_grid, &changed_cells);
+                        }//end locking each selected district
+                        edit.record_districts_after(&gui.districts);
+                        history.push(edit);
+                        gui.update_district_list();
+                    }//end else we have districts to lock
+                },
+                MenuChoice::LockDistrictCell(coord) => {
+                    if let Some(before) = city_grid.get(coord.row, coord.col).cloned() {
+                        let locked = *lock_stroke.get_or_insert(!before.cell_locked);
+                        if set_cell_locked(&mut city_grid, coord, locked) {
+                            history.pending(if locked {"Lock cells"} else {"Unlock cells"}).record_cell(coord, before, city_grid.get(coord.row, coord.col).unwrap().clone());
+                            gui.update_district_cells(&city_grid, &[coord]);
+                        }//end if the lock actually changed
+                    }//end if coord is in the grid
+                },
+                MenuChoice::LockBuilding(coord) => {
+                    if let Some(nhood_coord) = cur_nhood_coord {
+                        if let Some(nhood) = city_grid.get_mut(nhood_coord.row, nhood_coord.col) {
+                            if let Some(building) = nhood.sub_grid.get(coord.row, coord.col) {
+                                let locked = *lock_stroke.get_or_insert(!building.locked);
+                                let before = nhood.clone();
+                                if set_building_locked(nhood, coord, locked) {
+                                    history.pending(if locked {"Lock buildings"} else {"Unlock buildings"}).record_cell(nhood_coord, before, nhood.clone());
+                                    gui.update_building_cell(nhood, coord);
+                                }//end if the lock actually changed
+                            }//end if coord is in the neighborhood
+                        }//end if the shown neighborhood is in the grid
+                    }//end if a neighborhood is being shown
+                },
+                MenuChoice::ToggleNeighborhoodLock => {
+                    let locked = gui.nhood_lock_checked();
+                    if let Some(nhood_coord) = cur_nhood_coord {
+                        if let Some(nhood) = city_grid.get_mut(nhood_coord.row, nhood_coord.col) {
+                            let before = nhood.clone();
+                            nhood.nhood_locked = locked;
+                            let mut edit = Edit::new(if locked {"Lock neighborhood"} else {"Unlock neighborhood"});
+                            edit.record_cell(nhood_coord, before, nhood.clone());
+                            history.push(edit);
+                        }//end if the shown neighborhood is in the grid
+                    }//end if a neighborhood is being shown
+                },
+                MenuChoice::CancelGeneration => {
+                    if let Some(job) = &gen_job {
+                        job.cancel();
+                        gui.set_generation_progress(0.0, "Cancelling...");
+                    }//end if there's something to cancel
+                },
+                MenuChoice::ShowNeighborhood(coord) => {
+                    // print out the row-col pair and district for all to see
+                    let district_name = district_at(&city_grid, &gui.districts, coord).map(|district| district.name.as_str()).unwrap_or("empty");
+                    println!("Received message asking after neighborhood at row {} and column {}, in district {}", coord.row + 1, coord.col + 1, district_name);
+                    // neighborhoods left for later are generated the first time they're shown
+                    if let Some(lazy) = lazy_nhoods {
+                        if lazy.materialize(&mut city_grid, coord, &building_palette) {
+                            let nhood = city_grid.get(coord.row, coord.col).unwrap();
+                            if !gui.update_city_neighborhood(coord, nhood) {
+                                gui.update_city_tab(&city_grid, &building_palette);
+                            }//end if the whole city map needs redrawing
+                        }//end if the neighborhood was just generated
+                    }//end if neighborhoods are generated when needed
+                    // get the GroupInstance at the specified coordinate
+                    match city_grid.get(coord.row, coord.col) {
+                        Some(this_nhood) => {
                             gui.update_neighborhood_tab(this_nhood);
+                            gui.update_neighborhood_navigation(&city_grid, coord);
+                            cur_nhood_coord = Some(coord);
                             // switch to neighborhood tab to show it to user
                             gui.switch_tab(2);
-                        }//end found a coordinate pair
-                    }//end if we have a comma-separated value
-                    else {println!("Unhandled message!!\n")}
-                }//end if we have an irregular message
+                        },
+                        None => println!("No neighborhood at row {} and column {}", coord.row + 1, coord.col + 1),
+                    }//end matching whether coordinate is in the grid
+                },
+                MenuChoice::ShowAdjacentNeighborhood(direction) => {
+                    // shortcuts still come through at the edges of the city, where there's nowhere to go
+                    if let Some(next) = cur_nhood_coord.and_then(|coord| coord.step(direction, city_grid.rows(), city_grid.cols())) {
+                        gui.menu_msg_sender.send(MenuChoice::ShowNeighborhood(next));
+                    }//end if there's a neighborhood in that direction
+                },
+                MenuChoice::SelectDistrictCell(coord) => {
+                    let anchor = *selection_anchor.get_or_insert(coord);
+                    let first = Coord::new(anchor.row.min(coord.row), anchor.col.min(coord.col));
+                    let last = Coord::new(anchor.row.max(coord.row), anchor.col.max(coord.col));
+                    district_selection = Some((first, last));
+                    gui.set_district_selection(district_selection);
+                },
+                MenuChoice::SelectDistrictsFinished => {
+                    selection_anchor = None;
+                },
+                MenuChoice::ViewSelectedTogether | MenuChoice::OpenSelectedSeparately => {
+                    match district_selection {
+                        None => gui.show_message("Please choose the Select tool on the Districts tab, then drag over the neighborhoods to compare."),
+                        Some((first, last)) if val == MenuChoice::OpenSelectedSeparately && (last.row - first.row + 1) * (last.col - first.col + 1) > get_max_compare_windows() => {
+                            gui.show_message(&format!("Only {} neighborhoods can be opened separately at once. Please select fewer, or view them together instead.", get_max_compare_windows()));
+                        },
+                        Some(selection) => {
+                            // neighborhoods left for later need to exist before they can be shown
+                            if let Some(lazy) = lazy_nhoods {
+                                let mut materialized = Vec::new();
+                                for row in selection.0.row..=selection.1.row {
+                                    for col in selection.0.col..=selection.1.col {
+                                        if lazy.materialize(&mut city_grid, Coord::new(row, col), &building_palette) {
+                                            materialized.push(Coord::new(row, col));
+                                        }//end if the neighborhood was just generated
+                                    }//end looping over selected columns
+                                }//end looping over selected rows
+                                gui.update_city_neighborhoods(&city_grid, &materialized, &building_palette);
+                            }//end if neighborhoods are generated when needed
+                            if val == MenuChoice::ViewSelectedTogether {gui.open_selected_together(&city_grid, selection);}
+                            else {gui.open_selected_separately(&city_grid, selection);}
+                        },
+                    }//end matching whether anything is selected
+                },
+                MenuChoice::PaintDistrictCell(coord) => {
+                    if let (Some(district_index), Some(before)) = (gui.get_paint_district(), city_grid.get(coord.row, coord.col).cloned()) {
+                        let edit = history.pending("Paint districts");
+                        if reassign_district_cell(&mut city_grid, &mut gui.districts, coord, district_index) {
+                            // neighborhoods that haven't been generated yet will be once they're viewed
+                            if gui.regen_on_paint() && !before.nhood_locked && (lazy_nhoods.is_none() || before.has_neighborhood()) {
+                                // a message box in the middle of a stroke would be annoying, so fall back to the current neighborhood size
+                                let (nhood_rows, nhood_cols) = gui.get_neighborhood_dims().unwrap_or((before.sub_grid.rows(), before.sub_grid.cols()));
+                                if let Some(nhood) = city_grid.get_mut(coord.row, coord.col) {
+                                    regenerate_neighborhood(nhood, &mut rng, &building_palette, nhood_rows, nhood_cols);
+                                }//end if we can get the neighborhood
+                            }//end if we should regenerate the painted neighborhood
+                            edit.record_cell(coord, before, city_grid.get(coord.row, coord.col).unwrap().clone());
+                            gui.update_district_cells(&city_grid, &[coord]);
+                        }//end if the cell actually changed
+                    }//end if there's a district to paint with and a cell to paint
+                },
+                MenuChoice::PaintDistrictsFinished => {
+                    lock_stroke = None;
+                    let painted_cells = history.pending_mut().map(|edit| edit.changed_cells()).unwrap_or_default();
+                    history.finish_pending();
+                    // refresh everything that depends on district locations once the stroke is done
+                    gui.update_district_legend();
+                    gui.update_city_neighborhoods(&city_grid, &painted_cells, &building_palette);
+                    if let Some(coord) = cur_nhood_coord {
+                        if let Some(this_nhood) = city_grid.get(coord.row, coord.col) {
+                            gui.update_neighborhood_tab(this_nhood);
+                        }//end if current neighborhood still exists
+                    }//end if a neighborhood is being shown
+                },
+                MenuChoice::PaintBuilding(coord) => {
+                    if let (Some(nhood_coord), Some(build_type)) = (cur_nhood_coord, gui.get_paint_building()) {
+                        if let Some(nhood) = city_grid.get_mut(nhood_coord.row, nhood_coord.col) {
+                            let before = nhood.clone();
+                            if set_building_type(nhood, coord, &build_type, &building_palette) {
+                                history.pending("Edit buildings").record_cell(nhood_coord, before, nhood.clone());
+                                gui.update_building_cell(nhood, coord);
+                            }//end if the building actually changed
+                        }//end if the shown neighborhood is in the grid
+                    }//end if we have a neighborhood and a building type
+                },
+                MenuChoice::PaintBuildingsFinished => {
+                    lock_stroke = None;
+                    history.finish_pending();
+                    if let Some(coord) = cur_nhood_coord {
+                        gui.update_city_neighborhoods(&city_grid, &[coord], &building_palette);
+                    }//end if a neighborhood was being painted
+                },
+                MenuChoice::EditBuildingInfo(coord) => {
+                    if let Some(nhood_coord) = cur_nhood_coord {
+                        let info = city_grid.get(nhood_coord.row, nhood_coord.col)
+                            .and_then(|nhood| nhood.sub_grid.get(coord.row, coord.col))
+                            .and_then(|building| gui.get_building_info(building));
+                        if let (Some((name, notes)), Some(nhood)) = (info, city_grid.get_mut(nhood_coord.row, nhood_coord.col)) {
+                            let before = nhood.clone();
+                            if set_building_info(nhood, coord, &name, &notes) {
+                                let mut edit = Edit::new("Edit building info");
+                                edit.record_cell(nhood_coord, before, nhood.clone());
+                                history.push(edit);
+                                gui.inspect_building(Some(nhood), Some(coord));
+                            }//end if the name or notes actually changed
+                        }//end if the user gave a name and notes
+                    }//end if a neighborhood is being shown
+                },
+                MenuChoice::InspectDistrictCell(coord) => {
+                    // while growth is playing back, the district map isn't showing the actual city
+                    let coord = if growth_playback.is_some() {None} else {coord};
+                    gui.inspect_district_cell(&city_grid, coord);
+                },
+                MenuChoice::InspectBuilding(coord) => {
+                    let nhood = cur_nhood_coord.and_then(|nhood_coord| city_grid.get(nhood_coord.row, nhood_coord.col));
+                    gui.inspect_building(nhood, coord);
+                },
+                MenuChoice::InspectCityBuilding(coord) => {
+                    gui.inspect_city_building(&city_grid, coord);
+                },
+                MenuChoice::Undo => {
+                    match history.undo(&mut city_grid, &mut gui.districts) {
+                        Some(edit) => {
+                            println!("Undid {}", edit.description());
+                            refresh_edited_city(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord, &edit.changed_cells(), edit.changes_districts());
+                        },
+                        None => println!("Nothing to undo"),
+                    }//end matching whether there was something to undo
+                },
+                MenuChoice::Redo => {
+                    match history.redo(&mut city_grid, &mut gui.districts) {
+                        Some(edit) => {
+                            println!("Redid {}", edit.description());
+                            refresh_edited_city(&mut gui, &mut city_grid, &building_palette, cur_nhood_coord, &edit.changed_cells(), edit.changes_districts());
+                        },
+                        None => println!("Nothing to redo"),
+                    }//end matching whether there was something to redo
+                },
+                MenuChoice::PlayGrowth => {
+                    if gui.growth_playing() {
+                        gui.set_growth_playing(false);
+                    }//end if we should pause
+                    else if let Some(recording) = &growth_recording {
+                        if growth_playback.is_none() {
+                            start_growth_playback(&mut gui, recording);
+                            growth_playback = Some(0);
+                        }//end if we're starting from the beginning
+                        gui.set_growth_playing(true);
+                        gui.switch_tab(1);
+                    }//end else if there's something to play
+                    else {
+                        gui.show_message("There's no district growth to play back yet. Please generate a city first.");
+                    }//end else nothing has been generated
+                },
+                MenuChoice::StepGrowth | MenuChoice::GrowthTick => {
+                    // ticks can still be waiting in the queue after playback was paused
+                    if val == MenuChoice::GrowthTick && !gui.growth_playing() {continue;}
+                    match (&growth_recording, growth_playback.as_mut()) {
+                        (None, _) => gui.show_message("There's no district growth to step through yet. Please generate a city first."),
+                        (Some(recording), None) => {
+                            start_growth_playback(&mut gui, recording);
+                            growth_playback = Some(0);
+                            gui.switch_tab(1);
+                        },
+                        (Some(recording), Some(num_steps_shown)) => {
+                            if *num_steps_shown >= recording.steps.len() {
+                                stop_growth_playback(&mut gui, &city_grid, &mut growth_playback);
+                            }//end if every step has been shown
+                            else {
+                                gui.update_growth_cells(&recording.steps[*num_steps_shown]);
+                                *num_steps_shown += 1;
+                            }//end else show the next step
+                        },
+                    }//end matching whether there's growth to show
+                },
+                MenuChoice::ExportGrowthGif => {
+                    match &growth_recording {
+                        Some(recording) => {
+                            if let Some(path) = gui.get_export_path("Export Growth GIF", "*.gif", "growth.gif", settings.export_dir.as_deref()) {
+                                settings.export_dir = path.parent().map(|dir| dir.to_path_buf());
+                                if let Some(scale) = gui.get_export_scale("district cell", 8) {
+                                    match export_growth_gif(&path, recording, &gui.districts, scale, gui.growth_steps_per_second()) {
+                                        Ok(()) => println!("Exported district growth to {}", path.display()),
+                                        Err(e) => gui.show_message(&format!("Couldn't export the growth animation:\n{}", e)),
+                                    }//end matching whether export worked
+                                }//end if we got a scale
+                            }//end if we got a path
+                        },
+                        None => gui.show_message("There's no district growth to export yet. Please generate a city first."),
+                    }//end matching whether there's growth to export
+                },
+                MenuChoice::Resize => gui.update_layout(),
+                MenuChoice::SetScheme(index) => {
+                    if let Some((name, _)) = get_schemes().get(index) {
+                        settings.scheme = themes.apply_scheme(name).to_string();
+                        save_theme_settings(&mut gui, &mut settings);
+                    }//end if index is a scheme we know
+                },
+                MenuChoice::SetColorTheme(index) => {
+                    if let Some(name) = get_color_theme_names().get(index) {
+                        settings.color_theme = themes.apply_color_theme(name).to_string();
+                        save_theme_settings(&mut gui, &mut settings);
+                    }//end if index is a color theme we know
+                },
             }//end matching message values
         }//end if we received a message from receiver
+
+        // check on background generation
+        let mut job_done = false;
+        if let Some(job) = &gen_job {
+            while let Ok(progress) = job.receiver.try_recv() {
+                match progress {
+                    GenProgress::Finished(new_grid, new_districts, recording) => {
+                        println!("Finished city generation\n");
+                        match partial_regen_base.take() {
+                            Some((old_grid, old_districts)) => {
+                                // regenerating unlocked parts can be undone, unlike starting over
+                                let mut edit = Edit::new("Regenerate unlocked");
+                                edit.record_districts_before(&old_districts);
+                                edit.record_districts_after(&new_districts);
+                                for row in 0..new_grid.rows() {
+                                    for col in 0..new_grid.cols() {
+                                        let (old_nhood, new_nhood) = (old_grid.get(row, col).unwrap(), new_grid.get(row, col).unwrap());
+                                        if old_nhood != new_nhood {
+                                            edit.record_cell(Coord::new(row, col), old_nhood.clone(), new_nhood.clone());
+                                        }//end if this cell changed
+                                    }//end looping over columns
+                                }//end looping over rows
+                                history.push(edit);
+                            },
+                            // edits were made to the old city, so they can't be undone anymore
+                            None => history.clear(),
+                        }//end matching whether this was a partial regeneration
+                        city_grid = new_grid;
+                        gui.districts = new_districts;
+                        lazy_nhoods = job.lazy_neighborhoods;
+                        growth_recording = Some(recording);
+                        // palette might have changed while we were generating
+                        building_palette.recolor_grid(&mut city_grid);
+                        // display the new grid stuff
+                        gui.update_grid(&city_grid);
+                        // show all the neighborhoods together
+                        gui.update_city_tab(&city_grid, &building_palette);
+                        // old neighborhood is gone, so don't try to refresh it
+                        cur_nhood_coord = None;
+                        gui.clear_neighborhood_navigation();
+                        district_selection = None;
+                        gui.set_district_selection(None);
+                        gui.set_generation_progress(1.0, &format!("Finished generating with seed {}", job.seed));
+                        job_done = true;
+                        // switch to district tab so user can see what happened
+                        gui.switch_tab(1);
+                    },
+                    GenProgress::Cancelled => {
+                        println!("City generation cancelled\n");
+                        partial_regen_base = None;
+                        gui.set_generation_progress(0.0, "Generation cancelled");
+                        job_done = true;
+                    },
+                    progress => gui.set_generation_progress(progress.fraction(), &progress.description()),
+                }//end matching progress message
+            }//end handling each progress message
+        }//end if we're generating something
+        if job_done {
+            gen_job = None;
+            gui.set_generating(false);
+        }//end if generation is over
     }//end application loop
+
+    // remember how things were left for next time
+    gui.store_settings(&mut settings);
+    if let Err(e) = settings.save() {
+        println!("Couldn't save the settings file. {}", e);
+    }//end if settings couldn't be saved
 }//end main function
 
-/// # advance_group_expansion(grid, groups)
-/// 
-/// For each group, an adjacent, unclaimed tile will be claimed. If no tiles can be claimed, the group will not expand.
-/// 
-/// ## Parameters
-/// inner_rows and inner_cols refer to the number of rows and columns to initialize the grid in each GroupInstance with
-/// groups is the list of possible groups or categories to choose from
-/// the grid represents which spots have been claimed by which groups
+/// # save_theme_settings(gui, settings)
 /// 
-/// ## Return
-/// This function returns the number of groups which could not be expanded because they were completely enclosed
-fn advance_group_expansion(grid:&mut Grid<GroupInstance>, groups:&mut Vec<Grouping>, rng:&mut ThreadRng, inner_rows:usize, inner_cols:usize) -> usize {
-    // counter to keep track of fully enclosed groups
-    let mut num_enclosed: usize = 0;
-    // start looping through the groups to advance
-    for group in groups {
-        // find the coords which are adjacent to group and unclaimed by any other group
-        let adjacent_coords = group.get_adjacent_coords(grid.rows() - 1, grid.cols() - 1, false);
-        let mut open_coords = Vec::new();
-        for coord in adjacent_coords {
-            let this_group = &grid.get(coord.row, coord.col).unwrap().group;
-            if this_group.is_none() {
-                open_coords.push(coord);
-            }//end if the coord is still unclaimed
-        }//end checking each coord in adjacent_coords to add to open_coords
+/// Checks the chosen scheme and color theme in the View menu, and saves the settings right away so the choice is kept even if the program doesn't close normally
+fn save_theme_settings(gui:&mut GUI, settings:&mut AppSettings) {
+    gui.check_theme_items(&settings.scheme, &settings.color_theme);
+    gui.store_settings(settings);
+    if let Err(e) = settings.save() {
+        println!("Couldn't save the settings file. {}", e);
+    }//end if settings couldn't be saved
+}//end save_theme_settings(gui, settings)
 
-        // don't try to advance if there's no valid open coords
-        if open_coords.len() == 0 {
-            num_enclosed += 1;
-            continue;
-        }//end if we have an enclosed district
-        // pick one of the open_coords
-        let coord_to_use = weighted_coord_rng_districts(rng, &mut open_coords, group).to_owned();
-        // update group name in grid
-        let grid_spot = grid.get_mut(coord_to_use.row, coord_to_use.col).unwrap();
-        // update grouping locations
-        group.locations.push(coord_to_use.clone());
-        // update grid ref
-        *grid_spot = GroupInstance::new(group.clone(), coord_to_use.clone(), inner_rows, inner_cols);
-    }//end looping over each group to advance
-    return num_enclosed;
-}//end advance_group_expansion(grid, groups)
-
-/// # weighted_coord_rng_districts
-/// 
-/// Does some weird number manipulation to prefer lower distances in random coord picking.
-/// Uses adapted algorithm from http://stackoverflow.com/questions/1761626/weighted-random-numbers/1761646#1761646
-/// 
-/// This function can handle coords having only one element, but don't call it with coords being empty.
+/// # remember_recent_file(gui, settings, path)
 /// 
-/// ## Previous Issue: Potential Panic
-/// The function works by summing up the dist_from_center of each coord. If for some reason, this sum is 0, then the function will panic, saying something about rng not working because something something range bounds. Basically it doesn't like being called with a range of "0..0". This *shouldn't* happen anymore, but if panics like that start cropping up, then take a look at this function or the dist_from_center function in grouping.rs.
-fn weighted_coord_rng_districts<'a>(rng: & mut ThreadRng, coords:&'a Vec<Coord>, grouping:&'a Grouping) -> &'a Coord  {
-    // edge case for only one coord
-    if coords.len() == 1 {
-        return coords.first().unwrap();
-    }//end if we just have one choice
-    // figure out the sum of distances
-    let mut sum_of_weight = 0;
-    for coord in coords {
-        sum_of_weight += grouping.dist_from_center(coord).ceil() as i32;
-    }//end summing up distances from center
-    // do the generation ???
-    let mut rnd_num = rng.gen_range(0..sum_of_weight);
-    for coord in coords {
-        let this_weight = grouping.dist_from_center(coord).ceil() as i32;
-        if rnd_num < this_weight {
-            return coord;
-        }//end if we have a winner
-        rnd_num -= this_weight;
-    }//end looping over choice coords
+/// Puts path at the top of File/Open Recent, and saves the settings right away so the list isn't lost if the program doesn't close normally
+fn remember_recent_file(gui:&mut GUI, settings:&mut AppSettings, path:&std::path::Path) {
+    settings.add_recent_file(path);
+    gui.update_recent_files_menu(&settings.recent_files);
+    gui.store_settings(settings);
+    if let Err(e) = settings.save() {
+        println!("Couldn't save the settings file. {}", e);
+    }//end if settings couldn't be saved
+}//end remember_recent_file(gui, settings, path)
 
-    // should never make it here, but use unweighted generation if weighted doesn't work for some reason
-    return coords.get(rng.gen_range(0..coords.len())).unwrap();
-}//end weighted_coord_rng_districts
-
-/// # prime_grid_with_groups()
+/// # is_blocked_while_generating(msg)
 /// 
-/// Adds single instance of each group in random spots in the grid.
-/// inner_rows and inner_cols refer to the number of rows and columns that the grouped instance should have.
-fn prime_grid_with_groups(grid:&mut Grid<GroupInstance>, groups:&mut Vec<Grouping>, rng:&mut ThreadRng, inner_rows:usize, inner_cols:usize){
-    // start looping through groups to actually do stuff
-    for group in groups {
-        loop {
-            // generate random location
-            let row = rng.gen_range(0..grid.rows());
-            let col = rng.gen_range(0..grid.cols());
-            // check that we're not overlapping
-            let this_group = &grid.get(row, col).unwrap().group;
-            if this_group.is_none() {
-                // actually put the group in
-                let spot = grid.get_mut(row, col).unwrap();
-                // get the Coord for this new group instance
-                let this_coord = Coord::new(row, col);
-                // update the grouping
-                group.locations.push(this_coord.clone());
-                // put the right references into this GroupInstance
-                *spot = GroupInstance::new(group.clone(), this_coord.clone(), inner_rows, inner_cols);
-                break;
-            }//end if we can continue
-            else {continue;}
-        }//end while we need to do check to not overwrite other group
-    }//end generating something for each group
-}//end prime_grid_with_groups
+/// Returns true for the messages that can't be handled while a city is being generated in the background, because they change the list of districts or start another generation.
+fn is_blocked_while_generating(msg:&MenuChoice) -> bool {
+    matches!(msg,
+        MenuChoice::SetColor |
+        MenuChoice::AddDistrict |
+        MenuChoice::RemoveDistrict |
+        MenuChoice::DistrictPreset |
+        MenuChoice::GenerateDistricts |
+        MenuChoice::OpenCity |
+        MenuChoice::OpenRecent(_) |
+        MenuChoice::PaintDistrictCell(_) |
+        MenuChoice::PaintBuilding(_) |
+        MenuChoice::LockDistrictCell(_) |
+        MenuChoice::LockBuilding(_) |
+        MenuChoice::EditBuildingInfo(_) |
+        MenuChoice::ToggleNeighborhoodLock |
+        MenuChoice::LockDistrict |
+        MenuChoice::RenameDistrict |
+        MenuChoice::MoveDistrictsUp |
+        MenuChoice::MoveDistrictsDown |
+        MenuChoice::RegenerateUnlocked |
+        MenuChoice::Undo |
+        MenuChoice::Redo
+    )
+}//end is_blocked_while_generating(msg)
 
-/// # generate_neighborhood
+/// # is_stroke_cell(msg)
 /// 
-/// Generates a neighborhood grid inside nhood's sub_grid field.
-/// This method will use the rows and columns of the provided nhood object.
-/// The rng parameter is used for random number generation.
-fn generate_neighborhood(nhood:&mut GroupInstance, rng:&mut ThreadRng) {
-    // save some handy reference variables for later
-    let rows = nhood.sub_grid.rows();
-    let cols = nhood.sub_grid.cols();
-    
-    // add roads to our neighborhood
-    let _num_colors = add_roads_to_neighborhood(nhood, rng);
+/// Returns true for the messages sent for each cell a paint or lock stroke passes over.
+fn is_stroke_cell(msg:&MenuChoice) -> bool {
+    matches!(msg,
+        MenuChoice::PaintDistrictCell(_) |
+        MenuChoice::PaintBuilding(_) |
+        MenuChoice::LockDistrictCell(_) |
+        MenuChoice::LockBuilding(_)
+    )
+}//end is_stroke_cell(msg)
 
-    // figure out number of color options to use
-    let color_options = gen_nhood_colors(rng, 42);
-
-    // loop through the whole grid
-    for row in 0..rows {
-        for col in 0..cols {
-            // pull out the building we want to edit
-            let this_build = nhood.sub_grid.get_mut(row, col).expect("Index should have been safe.");
-            
-            // make sure we aren't overwriting a road
-            if this_build.build_type.eq(&BuildingType::Road) {continue;}
-
-            // generate new type and color for building
-            let (build_type, _color) = gen_build_type_color(rng, &color_options);
-            this_build.build_type = build_type;
-            // use alternative category-based color picking
-            color_code_building(this_build, &color_options);
-            //this_build.rgb_color = color;
-        }//end looping through columns
-    }//end looping through rows
-}//end generate_neighborhood(nhood, rng)
-
-/// # color_code_building(building, color_options)
-/// 
-/// Assuming you give the function the same vector, it will consistently give buildings of the same building type the same color, as chosen from color_options.  
-/// 
-/// ## Panic Condition
-/// This function will panic if the length of color_options is less than the number of possible variants for BuildingType.
+/// # update_building_palette(gui, grid, palette, cur_nhood_coord)
 /// 
-/// ## Return
-/// There is no return for this method. Instead, the rgb_color field of building is set manually within this method.
-fn color_code_building(building: &mut Building, color_options: &Vec<(u8,u8,u8)>) {
-    match building.build_type {
-        BuildingType::Empty => building.rgb_color = (0,0,0),
-        BuildingType::Road => building.rgb_color = (55,55,55),
-        BuildingType::Residence => building.rgb_color = color_options.get(0).unwrap().to_owned(),
-        BuildingType::Shop => building.rgb_color = color_options.get(1).unwrap().to_owned(),
-        BuildingType::School => building.rgb_color = color_options.get(2).unwrap().to_owned(),
-        BuildingType::Museum => building.rgb_color = color_options.get(3).unwrap().to_owned(),
-        BuildingType::Hospital => building.rgb_color = color_options.get(4).unwrap().to_owned(),
-        BuildingType::Pharmacy => building.rgb_color = color_options.get(5).unwrap().to_owned(),
-        BuildingType::Park => building.rgb_color = color_options.get(6).unwrap().to_owned(),
-        BuildingType::Arcade => building.rgb_color = color_options.get(7).unwrap().to_owned(),
-        BuildingType::Pound => building.rgb_color = color_options.get(8).unwrap().to_owned(),
-        BuildingType::GovOffice => building.rgb_color = color_options.get(9).unwrap().to_owned(),
-        BuildingType::Cafe => building.rgb_color = color_options.get(10).unwrap().to_owned(),
-        BuildingType::CorpOffice => building.rgb_color = color_options.get(11).unwrap().to_owned(),
-        BuildingType::ChainStore => building.rgb_color = color_options.get(12).unwrap().to_owned(),
-        BuildingType::Police => building.rgb_color = color_options.get(13).unwrap().to_owned(),
-        BuildingType::Market => building.rgb_color = color_options.get(14).unwrap().to_owned(),
-        BuildingType::Temple => building.rgb_color = color_options.get(15).unwrap().to_owned(),
-        BuildingType::Church => building.rgb_color = color_options.get(16).unwrap().to_owned(),
-        BuildingType::Shrine => building.rgb_color = color_options.get(17).unwrap().to_owned(),
-        BuildingType::Spa => building.rgb_color = color_options.get(18).unwrap().to_owned(),
-        BuildingType::Mansion => building.rgb_color = color_options.get(19).unwrap().to_owned(),
-        BuildingType::Landfill => building.rgb_color = color_options.get(20).unwrap().to_owned(),
-        BuildingType::Factory => building.rgb_color = color_options.get(21).unwrap().to_owned(),
-        BuildingType::Prison => building.rgb_color = color_options.get(22).unwrap().to_owned(),
-        BuildingType::FireDept => building.rgb_color = color_options.get(23).unwrap().to_owned(),
-    }//end matching build_type to a color
-}//end color_code_neighborhood(building, color_options)
+/// Should be called after the building palette changes.
+/// Recolors every generated neighborhood, updates the color key, and refreshes the neighborhood being shown, if there is one.
+fn update_building_palette(gui:&mut GUI, grid:&mut Grid<GroupInstance>, palette:&BuildingPalette, cur_nhood_coord:Option<Coord>) {
+    palette.recolor_grid(grid);
+    gui.set_palette_seed(palette.seed);
+    gui.update_building_legend(palette);
+    gui.update_city_tab(grid, palette);
+    if let Some(coord) = cur_nhood_coord {
+        if let Some(nhood) = grid.get(coord.row, coord.col) {
+            gui.update_neighborhood_tab(nhood);
+        }//end if coord is still in the grid
+    }//end if we're showing a neighborhood
+}//end update_building_palette(gui, grid, palette, cur_nhood_coord)
 
-/// # add_roads_to_neighborhood(nhood, rng)
+/// # materialize_all_neighborhoods(gui, grid, palette, lazy_nhoods)
 /// 
-/// This function could be seen as a helper function for generate_neighborhood().
-/// It will generate roads and place them in the nhood parameter.
-/// 
-/// Returns the recommended number of colors to use
-fn add_roads_to_neighborhood(nhood:&mut GroupInstance, rng:&mut ThreadRng) -> usize{
-    // save some handy reference variables for later
-    let rows = nhood.sub_grid.rows();
-    let cols = nhood.sub_grid.cols();
-    
-    // figure out number of roads to slam in there
-    let num_roads_low_bound = ((rows + cols) as f32 * 1.2).sqrt().ceil() as usize;
-    let num_roads_upp_bound = ((rows + cols) as f32 * 2.2).sqrt().ceil() as usize;
-    let num_roads_total = rng.gen_range(num_roads_low_bound..num_roads_upp_bound);
-    let num_roads_horizontal = rng.gen_range(1.min(num_roads_total / 2)..num_roads_total.min((num_roads_total as f32 * 0.7).ceil() as usize));
-    let num_roads_vertical = num_roads_total - num_roads_horizontal;
-
-    // determine number of colors from roads
-    let num_colors = 3.max((num_roads_upp_bound - num_roads_total) * 2);
-    
-    // slap some horizontal roads in there
-    let mut roads_hor_idxs = Vec::new();
-    while roads_hor_idxs.len() < num_roads_horizontal.min(rows) {
-        let road_hor_idx = rng.gen_range(0..rows);
-        if !roads_hor_idxs.contains(&road_hor_idx) {
-            roads_hor_idxs.push(road_hor_idx);
-        }//end if we haven't already generated this index
-    }//end looping while we can fit some more horizontal roads in there
-
-    // slap some vertical roads in there
-    let mut roads_ver_idxs = Vec::new();
-    while roads_ver_idxs.len() < num_roads_vertical.min(cols) {
-        let road_ver_idx = rng.gen_range(0..cols);
-        if !roads_ver_idxs.contains(&road_ver_idx) {
-            roads_ver_idxs.push(road_ver_idx);
-        }//end if we haven't already generated this index
-    }//end looping while we can fit some more vertical roads int there
+/// Generates every neighborhood that was left until it was needed, such as before exporting the whole city.
+/// The city map is redrawn if anything was generated.
+fn materialize_all_neighborhoods(gui:&mut GUI, grid:&mut Grid<GroupInstance>, palette:&BuildingPalette, lazy_nhoods:Option<LazyNeighborhoods>) {
+    if let Some(lazy) = lazy_nhoods {
+        let num_generated = lazy.materialize_all(grid, palette);
+        if num_generated > 0 {
+            println!("Generated {} neighborhoods that hadn't been viewed yet", num_generated);
+            gui.update_city_tab(grid, palette);
+        }//end if anything was generated
+    }//end if neighborhoods are generated when needed
+}//end materialize_all_neighborhoods(gui, grid, palette, lazy_nhoods)
 
-    let road_color:(u8,u8,u8) = (55,55,55);
-
-    // actually edit nhood with horizontal roads
-    for row_idx in roads_hor_idxs {
-        for col_idx in 0..cols {
-            let this_building = nhood.sub_grid.get_mut(row_idx, col_idx).expect("Those indices seemed pretty valid to me... Should be in bounds and everything.");
-            // set type to road and color the roads
-            this_building.build_type = BuildingType::Road;
-            this_building.rgb_color = road_color;
-        }//end looping over each column on our way horizontal
-    }//end looping over each horizontal road index to add
-
-    // actually edit nhood with vertical roads
-    for col_idx in roads_ver_idxs {
-        for row_idx in 0..rows {
-            let this_building = nhood.sub_grid.get_mut(row_idx, col_idx).expect("Those indices seemed pretty valid to me... Should be in bounds and everything.");
-            // set type to road and color the roads
-            this_building.build_type = BuildingType::Road;
-            this_building.rgb_color = road_color;
-        }//end looping over reach row on our way vertical
-    }//end looping over each vertical road index
-
-    return num_colors;
-}//end add_roads_to_neighborhood(nhood, rng)
-
-/// # get_nhood_colors(rng, num_colors)
-/// 
-/// This function could be seen as a helper function for generate_neighborhood().
-/// It will generate a random list of rgb colors, with the number of colors depending on the input number of roads. Generally, the idea is that if you have a relative surplus of roads, then you want relatively fewer colors, and vice versa.
+/// # start_growth_playback(gui, recording)
 /// 
-/// Returns a vector of (u8,u8,u8), representing rgb values.
-fn gen_nhood_colors(rng: & mut ThreadRng, num_colors:usize) -> Vec<(u8, u8, u8)> {
-    // get our vector of colors
-    let mut color_options:Vec<(u8,u8,u8)> = Vec::new();
+/// Shows the district map as it was before any of the districts in recording grew.
+fn start_growth_playback(gui:&mut GUI, recording:&GrowthRecording) {
+    let cells: Vec<(Coord, u32)> = recording.start.iter().enumerate()
+        .map(|(index, district_id)| (Coord::new(index / recording.cols, index % recording.cols), *district_id))
+        .collect();
+    gui.update_growth_cells(&cells);
+}//end start_growth_playback(gui, recording)
 
-    let px_num = 42.min(num_colors * 2);
-    let px_interval = 255 / px_num;
-    while color_options.len() < num_colors {
-        // generate an rgb value somewhat randomly
-        let r = (rng.gen_range(1..(px_num - 1)) * px_interval) as u8;
-        let g = (rng.gen_range(1..(px_num - 1)) * px_interval) as u8;
-        let b = (rng.gen_range(1..(px_num - 1)) * px_interval) as u8;
-        let rgb = (r,g,b);
-
-        if !color_options.contains(&rgb) {
-            color_options.push(rgb);
-        }//end if we found a new color value
-    }//end looping while we should still fill our list
-
-    return color_options;
-}//end gen_nhood_colors()
+/// # stop_growth_playback(gui, grid, growth_playback)
+/// 
+/// Stops playing back district growth and puts the district map back to showing grid.
+fn stop_growth_playback(gui:&mut GUI, grid:&Grid<GroupInstance>, growth_playback:&mut Option<usize>) {
+    gui.set_growth_playing(false);
+    *growth_playback = None;
+    gui.refresh_district_map(grid);
+}//end stop_growth_playback(gui, grid, growth_playback)
 
-/// # gen_build_type_color(rng, colors)
+/// # get_unused_district_name(districts)
 /// 
-/// This function could be seen as a helper function for generate_neighborhoods().
-/// It will randomly generate a building type and a color. That's it, just useful to keep generate_neighborhoods a little bit cleaner.
+/// Comes up with a placeholder name for a new district that isn't used by any of districts yet
+fn get_unused_district_name(districts:&[Grouping]) -> String {
+    let mut number = districts.len() + 1;
+    loop {
+        let name = format!("district {}", number);
+        if !districts.iter().any(|district| district.name.to_lowercase() == name) {return name;}
+        number += 1;
+    }//end looping until we find an unused name
+}//end get_unused_district_name(districts)
+
+/// # refresh_edited_city(gui, grid, palette, cur_nhood_coord, changed_cells, changes_districts)
 /// 
-/// returns a tuple containing (BuildingType, rgb color as (u8,u8,u8))
-fn gen_build_type_color(rng:&mut ThreadRng, colors:&Vec<(u8,u8,u8)>) -> (BuildingType, (u8, u8, u8)) {
-    let build_type_index = rng.gen_range(0..112);
-    let build_type = match build_type_index {
-        0 => BuildingType::Road,
-        1 => BuildingType::FireDept,
-        2 => BuildingType::Prison,
-        3..=5 => BuildingType::Factory,
-        6 => BuildingType::Landfill,
-        7..=10 => BuildingType::Mansion,
-        11..=12 => BuildingType::Spa,
-        13..=15 => BuildingType::Shrine,
-        16..=18 => BuildingType::Church,
-        19..=21 => BuildingType::Temple,
-        22..=24 => BuildingType::Market,
-        25 => BuildingType::Police,
-        26..=29 => BuildingType::ChainStore,
-        30..=33 =>BuildingType::CorpOffice,
-        34..=35 => BuildingType::Cafe,
-        36..=37 => BuildingType::GovOffice,
-        38 => BuildingType::Pound,
-        39..=40 => BuildingType::Arcade,
-        41..=50 => BuildingType::Park,
-        51..=53 => BuildingType::Pharmacy,
-        54 => BuildingType::Hospital,
-        55 => BuildingType::Museum,
-        56..=57 => BuildingType::School,
-        58..=70 => BuildingType::Shop,
-        71..=110 => BuildingType::Residence,
-        111 => BuildingType::Road,
-        _ => BuildingType::Empty,
-    };
-    let color = colors.get(rng.gen_range(0..colors.len())).expect("Proper indexing").to_owned();
-    return (build_type, color);
-}//end gen_build_type_color(rng, colors)
+/// Updates everything shown in the gui after an edit was undone or redone.
+/// Only the cells in changed_cells are redrawn on the district map, and their buildings are recolored in case the palette changed since the edit.
+fn refresh_edited_city(gui:&mut GUI, grid:&mut Grid<GroupInstance>, palette:&BuildingPalette, cur_nhood_coord:Option<Coord>, changed_cells:&[Coord], changes_districts:bool) {
+    if changes_districts {
+        gui.update_district_list();
+        // every cell looks its district up by id, so any of them could look different now
+        gui.refresh_district_map(grid);
+    }//end if the district list changed
+    if let Some(coord) = cur_nhood_coord {
+        // the overview on the neighborhood tab shows district colors too
+        gui.update_neighborhood_navigation(grid, coord);
+    }//end if we're showing a neighborhood
+    if changed_cells.is_empty() {return;}
+    for coord in changed_cells {
+        if let Some(nhood) = grid.get_mut(coord.row, coord.col) {
+            for building in nhood.sub_grid.iter_mut() {
+                palette.color_code_building(building);
+            }//end recoloring each building
+        }//end if coord is still in the grid
+    }//end recoloring each changed cell
+    gui.update_district_cells(grid, changed_cells);
+    gui.update_city_neighborhoods(grid, changed_cells, palette);
+    if let Some(coord) = cur_nhood_coord {
+        if changed_cells.contains(&coord) {
+            if let Some(nhood) = grid.get(coord.row, coord.col) {
+                gui.update_neighborhood_tab(nhood);
+            }//end if coord is still in the grid
+        }//end if the shown neighborhood changed
+    }//end if we're showing a neighborhood
+}//end refresh_edited_city(gui, grid, palette, cur_nhood_coord, changed_cells, changes_districts)
 
 /// # print_grid()
 /// 
@@ -479,15 +918,13 @@ fn print_grid(grid:&Grid<String>, title:&str) {
     println!("{}", title);
     for row in grid.iter_rows() {
         for item in row {
-            let mut shrunk = item.as_str();
-            if shrunk.len() > 6 {
-                shrunk = &item[0..6];
-            }//end if we need to shrink the name
+            // count characters rather than bytes, so names with accents or emoji don't get cut in half
+            let shrunk: String = item.chars().take(6).collect();
             print!("{}\t", shrunk);
         }//end looping over elements in row
-        print!("\n");
+        println!();
     }//end looping over rows
-    print!("\n");
+    println!();
 }//end printGrid(grid)
 
 /// # print_groupings(groupings, title)
@@ -497,7 +934,7 @@ fn print_grid(grid:&Grid<String>, title:&str) {
 fn print_groupings(groupings:&Vec<Grouping>, title: &str) {
     println!("{}", title);
     for group in groupings {
-        println!("{}", group.to_string());
+        println!("{}", group);
     }//end printing out each grouping
 }//end print_grouping
 
@@ -511,16 +948,8 @@ fn print_neighborhood(nhood:&mut GroupInstance, title: &str) {
         for col in 0..nhood.sub_grid.cols() {
             print!("{}\t", nhood.sub_grid.get(row, col).unwrap().build_type);
         }//end looping over col indices
-        print!("\n");
+        println!();
     }//end looping over row indices
     println!("\n");
 }//end print_neighborhood
 
-/// # create_empty_grid()
-/// 
-/// This function creates an empty grid of the specified dimensions, filled with the string "empty".
-fn create_empty_grid(rows:usize, cols:usize) -> Grid<GroupInstance> {
-    let mut empty = Grid::new(rows, cols);
-    empty.fill(GroupInstance::default());
-    return empty;
-}//end createEmptyGrid
\ No newline at end of file
@@ -0,0 +1,932 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use fltk::app;
+use fltk::app::MouseButton;
+use fltk::app::MouseWheel;
+use fltk::draw;
+use fltk::enums::Align;
+use fltk::enums::Color;
+use fltk::enums::Event;
+use fltk::enums::Font;
+use fltk::prelude::WidgetBase;
+use fltk::prelude::WidgetExt;
+use fltk::widget::Widget;
+use fltk::widget_extends;
+use grid::Grid;
+
+use crate::grouping::Coord;
+
+fn get_min_label_cell_width() -> f64 {30.0}
+fn get_min_label_cell_height() -> f64 {15.0}
+fn get_min_gap_cell_size() -> f64 {6.0}
+fn get_min_zoom() -> f64 {0.5}
+fn get_max_cell_size() -> f64 {200.0}
+fn get_zoom_step() -> f64 {1.25}
+fn get_drag_threshold() -> i32 {3}
+fn get_label_font_size() -> i32 {12}
+fn get_max_luminance_for_white_label() -> f32 {100.0}
+fn get_min_thick_border_cell_size() -> f64 {4.0}
+fn get_min_lock_marker_cell_size() -> f64 {8.0}
+/// how far the hover tooltip is drawn from the mouse, so the cursor doesn't cover it
+fn get_tooltip_offset() -> (i32,i32) {(14,18)}
+fn get_tooltip_padding() -> i32 {4}
+fn get_tooltip_color() -> Color {Color::from_rgb(255,255,225)}
+
+/// # get_black()
+///
+/// pure black. Color::Black is an entry in fltk's color map, which color themes are free to change, so this is used for anything drawn over cell colors instead.
+pub fn get_black() -> Color {Color::from_rgb(0,0,0)}
+
+/// # get_white()
+///
+/// pure white, which stays white whatever color theme is applied, unlike Color::White
+pub fn get_white() -> Color {Color::from_rgb(255,255,255)}
+
+/// # get_label_color(rgb)
+///
+/// does some calculations to determine whether a label drawn over the specified color should be black or white
+pub fn get_label_color(rgb:(u8,u8,u8)) -> Color {
+	let luminance = 0.299*rgb.0 as f32 + 0.587*rgb.1 as f32 + 0.114*rgb.2 as f32;
+	if luminance > get_max_luminance_for_white_label() {
+		get_black()
+	}//end if label color should be black
+	else { get_white() }
+}//end get_label_color(rgb)
+
+/// # MapCell
+///
+/// The information needed to draw a single cell of a MapView
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct MapCell {
+	/// # rgb_color
+	///
+	/// the fill color of the cell
+	pub rgb_color: (u8,u8,u8),
+	/// # label
+	///
+	/// text to show in the cell, if the cell is zoomed in enough to fit it
+	pub label: String,
+	/// # region
+	///
+	/// which region the cell belongs to. When borders are shown, a line is drawn between neighboring cells of different regions.
+	pub region: u32,
+	/// # locked
+	///
+	/// whether to mark the cell as locked
+	pub locked: bool,
+}//end struct MapCell
+
+impl MapCell {
+	/// # new(rgb_color, label)
+	///
+	/// creates a cell with the specified color and label
+	pub fn new(rgb_color:(u8,u8,u8), label:String) -> MapCell {
+		MapCell {
+			rgb_color,
+			label,
+			region: 0,
+			locked: false,
+		}//end struct construction
+	}//end new(rgb_color, label)
+
+	/// # with_region(self, region)
+	///
+	/// sets the region without needing a separate assignment
+	pub fn with_region(mut self, region:u32) -> MapCell {
+		self.region = region;
+		self
+	}//end with_region(self, region)
+
+	/// # with_locked(self, locked)
+	///
+	/// sets whether the cell is marked as locked without needing a separate assignment
+	pub fn with_locked(mut self, locked:bool) -> MapCell {
+		self.locked = locked;
+		self
+	}//end with_locked(self, locked)
+}//end impl for MapCell
+
+/// # MapCells
+///
+/// A compact store of the cells in a map. Labels are only stored once and referred to by index, so maps with millions of cells don't need millions of strings.
+struct MapCells {
+	/// the fill color of each cell
+	colors: Grid<(u8,u8,u8)>,
+	/// index into label_table for each cell
+	labels: Grid<u32>,
+	/// the region of each cell
+	regions: Grid<u32>,
+	/// whether each cell is marked as locked
+	locked: Grid<bool>,
+	/// each distinct label used by the cells
+	label_table: Vec<String>,
+	/// lookup from label to its index in label_table
+	label_lookup: HashMap<String, u32>,
+}//end struct MapCells
+
+impl MapCells {
+	/// # new(rows, cols)
+	///
+	/// creates a store of empty cells with the specified size
+	fn new(rows:usize, cols:usize) -> MapCells {
+		MapCells {
+			colors: Grid::new(rows, cols),
+			labels: Grid::new(rows, cols),
+			regions: Grid::new(rows, cols),
+			locked: Grid::new(rows, cols),
+			label_table: vec![String::new()],
+			label_lookup: HashMap::from([(String::new(), 0)]),
+		}//end struct construction
+	}//end new(rows, cols)
+
+	fn rows(&self) -> usize {self.colors.rows()}
+	fn cols(&self) -> usize {self.colors.cols()}
+
+	/// # set(&mut self, row, col, cell)
+	///
+	/// stores cell at the specified position, adding its label to the table if it's new
+	fn set(&mut self, row:usize, col:usize, cell:MapCell) {
+		let label_index = match self.label_lookup.get(&cell.label) {
+			Some(index) => *index,
+			None => {
+				self.label_table.push(cell.label.clone());
+				let index = (self.label_table.len() - 1) as u32;
+				self.label_lookup.insert(cell.label, index);
+				index
+			},
+		};//end finding index of label
+		if let Some(spot) = self.colors.get_mut(row, col) {*spot = cell.rgb_color;}
+		if let Some(spot) = self.labels.get_mut(row, col) {*spot = label_index;}
+		if let Some(spot) = self.regions.get_mut(row, col) {*spot = cell.region;}
+		if let Some(spot) = self.locked.get_mut(row, col) {*spot = cell.locked;}
+	}//end set(&mut self, row, col, cell)
+
+	/// # label(&self, row, col)
+	///
+	/// returns the label of the cell at the specified position
+	fn label(&self, row:usize, col:usize) -> &str {
+		let label_index = self.labels.get(row, col).copied().unwrap_or(0);
+		&self.label_table[label_index as usize]
+	}//end label(&self, row, col)
+
+	/// # region(&self, row, col)
+	///
+	/// returns the region of the cell at the specified position
+	fn region(&self, row:usize, col:usize) -> u32 {
+		self.regions.get(row, col).copied().unwrap_or(0)
+	}//end region(&self, row, col)
+}//end impl for MapCells
+
+/// # MapState
+///
+/// The state shared between a MapView and its draw and event callbacks
+struct MapState {
+	/// the cells being displayed
+	cells: MapCells,
+	/// whether to draw lines between cells of different regions
+	show_borders: bool,
+	/// how far we've zoomed in, where 1.0 means the whole map fits in the widget
+	zoom: f64,
+	/// the width and height of the widget the pan and zoom were last worked out for
+	view_size: (i32,i32),
+	/// horizontal pan, in pixels from the left of the widget
+	offset_x: f64,
+	/// vertical pan, in pixels from the top of the widget
+	offset_y: f64,
+	/// the cell currently under the mouse
+	hover: Option<Coord>,
+	/// where the mouse was last seen over the widget, in window coordinates
+	mouse_pos: (i32,i32),
+	/// text to show in a tooltip next to the mouse while it's over the hovered cell
+	hover_text: Option<String>,
+	/// called with the cell under the mouse whenever it changes
+	on_hover: Option<Box<dyn FnMut(Option<Coord>)>>,
+	/// the top left and bottom right cells of a rectangle to outline as selected, if any
+	selection: Option<(Coord,Coord)>,
+	/// where the current mouse drag started
+	drag_last: Option<(i32,i32)>,
+	/// whether the current press has moved far enough to count as a drag instead of a click
+	dragged: bool,
+	/// called with the coordinate of a cell when it's clicked
+	on_click: Option<Box<dyn FnMut(Coord)>>,
+	/// whether dragging with the left mouse button paints cells instead of panning
+	paint_mode: bool,
+	/// whether the left mouse button is currently held down in paint mode
+	painting: bool,
+	/// the last cell painted during the current stroke, so each cell is only painted once as the mouse moves over it
+	last_painted: Option<Coord>,
+	/// called with the coordinate of each cell the mouse passes over while painting
+	on_paint: Option<Box<dyn FnMut(Coord)>>,
+	/// called once the mouse button is released after painting
+	on_paint_done: Option<Box<dyn FnMut()>>,
+}//end struct MapState
+
+impl MapState {
+	/// # cell_size(&self, w, h)
+	///
+	/// returns the size of a single square cell, in pixels, for a widget of the specified size
+	fn cell_size(&self, w:i32, h:i32) -> f64 {
+		if self.cells.rows() == 0 || self.cells.cols() == 0 {return 0.0;}
+		let fit_width = w as f64 / self.cells.cols() as f64;
+		let fit_height = h as f64 / self.cells.rows() as f64;
+		fit_width.min(fit_height) * self.zoom
+	}//end cell_size(&self, w, h)
+
+	/// # cell_at(&self, widget, x, y)
+	///
+	/// returns the coordinate of the cell at the specified window position, if there is one
+	fn cell_at(&self, widget:&Widget, x:i32, y:i32) -> Option<Coord> {
+		let cell_size = self.cell_size(widget.w(), widget.h());
+		if cell_size <= 0.0 {return None;}
+		let map_x = (x - widget.x()) as f64 - self.offset_x;
+		let map_y = (y - widget.y()) as f64 - self.offset_y;
+		if map_x < 0.0 || map_y < 0.0 {return None;}
+		let col = (map_x / cell_size) as usize;
+		let row = (map_y / cell_size) as usize;
+		if row < self.cells.rows() && col < self.cells.cols() {
+			Some(Coord::new(row, col))
+		}//end if the position is inside the map
+		else {None}
+	}//end cell_at(&self, widget, x, y)
+
+	/// # center(&mut self, w, h)
+	///
+	/// resets the pan so that the map is centered in a widget of the specified size
+	fn center(&mut self, w:i32, h:i32) {
+		let cell_size = self.cell_size(w, h);
+		self.offset_x = (w as f64 - cell_size * self.cells.cols() as f64) / 2.0;
+		self.offset_y = (h as f64 - cell_size * self.cells.rows() as f64) / 2.0;
+	}//end center(&mut self, w, h)
+
+	/// # refit(&mut self, w, h)
+	///
+	/// adjusts the pan after the widget changes to the specified size, so the point that was in the middle of the view stays there.
+	/// The zoom is relative to the size that fits the whole map, so cells grow and shrink along with the widget.
+	fn refit(&mut self, w:i32, h:i32) {
+		let (old_w, old_h) = self.view_size;
+		if (old_w, old_h) == (w, h) {return;}
+		self.view_size = (w, h);
+		let old_size = self.cell_size(old_w, old_h);
+		let new_size = self.cell_size(w, h);
+		if old_size <= 0.0 || new_size <= 0.0 {
+			self.center(w, h);
+			return;
+		}//end if there's nothing to keep in place
+		// the middle of the old view, measured in cells from the top left of the map
+		let mid_col = (old_w as f64 / 2.0 - self.offset_x) / old_size;
+		let mid_row = (old_h as f64 / 2.0 - self.offset_y) / old_size;
+		self.offset_x = w as f64 / 2.0 - mid_col * new_size;
+		self.offset_y = h as f64 / 2.0 - mid_row * new_size;
+	}//end refit(&mut self, w, h)
+}//end impl for MapState
+
+/// # MapView
+///
+/// A custom drawn map of colored cells, replacing the old grid of buttons.
+/// The map can be zoomed with the mouse wheel and panned by dragging.
+/// The cell under the mouse is highlighted, and labels are only drawn once cells are big enough to fit them.
+/// Only the cells that are actually visible get drawn, so large maps stay responsive.
+#[derive(Clone)]
+pub struct MapView {
+	/// # widget
+	/// The widget the map is drawn in
+	pub widget: Widget,
+	/// # state
+	/// The cells and view settings, shared with the draw and handle callbacks
+	state: Rc<RefCell<MapState>>,
+}//end struct MapView
+
+#[allow(dead_code)]
+impl MapView {
+	/// # new(x, y, w, h)
+	///
+	/// constructs an empty map view at the specified position and size
+	pub fn new(x:i32, y:i32, w:i32, h:i32) -> MapView {
+		let mut widget = Widget::new(x, y, w, h, None);
+		let state = Rc::new(RefCell::new(MapState {
+			cells: MapCells::new(0,0),
+			show_borders: false,
+			zoom: 1.0,
+			view_size: (w,h),
+			offset_x: 0.0,
+			offset_y: 0.0,
+			hover: None,
+			mouse_pos: (0,0),
+			hover_text: None,
+			on_hover: None,
+			selection: None,
+			drag_last: None,
+			dragged: false,
+			on_click: None,
+			paint_mode: false,
+			painting: false,
+			last_painted: None,
+			on_paint: None,
+			on_paint_done: None,
+		}));
+
+		// set up drawing
+		let draw_state = state.clone();
+		widget.draw(move |wid| {
+			let state = draw_state.borrow();
+			draw_map(wid, &state);
+		});
+
+		// set up event handling
+		let handle_state = state.clone();
+		widget.handle(move |wid, ev| {
+			handle_map_event(wid, &handle_state, ev)
+		});
+
+		MapView {
+			widget,
+			state,
+		}//end struct construction
+	}//end new(x, y, w, h)
+
+	/// # set_cells(&mut self, cells)
+	///
+	/// replaces the cells being shown, and resets the view so the whole map fits
+	pub fn set_cells(&mut self, cells:Grid<MapCell>) {
+		self.fill_cells(cells.rows(), cells.cols(), |row, col| cells.get(row, col).unwrap().clone());
+	}//end set_cells(&mut self, cells)
+
+	/// # fill_cells(&mut self, rows, cols, get_cell)
+	///
+	/// replaces the cells being shown with a map of the specified size, calling get_cell for the contents of each cell, then resets the view so the whole map fits.
+	/// This avoids building a whole grid of MapCells first, which matters for very large maps.
+	pub fn fill_cells<F: FnMut(usize, usize) -> MapCell>(&mut self, rows:usize, cols:usize, mut get_cell:F) {
+		let mut cells = MapCells::new(rows, cols);
+		for row in 0..rows {
+			for col in 0..cols {
+				cells.set(row, col, get_cell(row, col));
+			}//end looping over each column
+		}//end looping over each row
+		{
+			let mut state = self.state.borrow_mut();
+			state.cells = cells;
+			state.hover = None;
+			state.hover_text = None;
+		}
+		self.reset_view();
+	}//end fill_cells(&mut self, rows, cols, get_cell)
+
+	/// # update_cell(&mut self, coord, cell)
+	///
+	/// replaces a single cell without changing the zoom or pan
+	pub fn update_cell(&mut self, coord:Coord, cell:MapCell) {
+		self.state.borrow_mut().cells.set(coord.row, coord.col, cell);
+		self.widget.redraw();
+	}//end update_cell(&mut self, coord, cell)
+
+	/// # set_show_borders(&mut self, show_borders)
+	///
+	/// sets whether lines should be drawn between neighboring cells of different regions
+	pub fn set_show_borders(&mut self, show_borders:bool) {
+		self.state.borrow_mut().show_borders = show_borders;
+		self.widget.redraw();
+	}//end set_show_borders(&mut self, show_borders)
+
+	/// # reset_view(&mut self)
+	///
+	/// zooms out and centers the map so that the whole thing fits in the widget
+	pub fn reset_view(&mut self) {
+		let (w, h) = (self.widget.w(), self.widget.h());
+		{
+			let mut state = self.state.borrow_mut();
+			state.zoom = 1.0;
+			state.view_size = (w, h);
+			state.center(w, h);
+		}
+		self.widget.redraw();
+	}//end reset_view(&mut self)
+
+	/// # set_area(&mut self, x, y, w, h)
+	///
+	/// moves and resizes the map, keeping whatever was in the middle of the view there.
+	/// Cell sizes and which labels fit are worked out from the widget size each time the map is drawn, so they follow along.
+	pub fn set_area(&mut self, x:i32, y:i32, w:i32, h:i32) {
+		self.widget.resize(x, y, w, h);
+		self.state.borrow_mut().refit(w, h);
+		self.widget.redraw();
+	}//end set_area(&mut self, x, y, w, h)
+
+	/// # set_click_callback(&mut self, callback)
+	///
+	/// sets the function to call with the coordinate of a cell whenever it's clicked without dragging
+	pub fn set_click_callback<F: FnMut(Coord) + 'static>(&mut self, callback:F) {
+		self.state.borrow_mut().on_click = Some(Box::new(callback));
+	}//end set_click_callback(&mut self, callback)
+
+	/// # set_paint_mode(&mut self, paint_mode)
+	///
+	/// sets whether the left mouse button paints cells instead of clicking and panning.
+	/// While painting, the map can still be panned by dragging with the right or middle mouse button.
+	pub fn set_paint_mode(&mut self, paint_mode:bool) {
+		let mut state = self.state.borrow_mut();
+		state.paint_mode = paint_mode;
+		state.painting = false;
+		state.last_painted = None;
+	}//end set_paint_mode(&mut self, paint_mode)
+
+	/// # set_paint_callbacks(&mut self, on_paint, on_paint_done)
+	///
+	/// sets the functions to call in paint mode. on_paint is called with each cell the mouse passes over while the left button is held, and on_paint_done is called when the button is released.
+	pub fn set_paint_callbacks<F: FnMut(Coord) + 'static, G: FnMut() + 'static>(&mut self, on_paint:F, on_paint_done:G) {
+		let mut state = self.state.borrow_mut();
+		state.on_paint = Some(Box::new(on_paint));
+		state.on_paint_done = Some(Box::new(on_paint_done));
+	}//end set_paint_callbacks(&mut self, on_paint, on_paint_done)
+
+	/// # set_hover_callback(&mut self, callback)
+	///
+	/// sets the function to call with the cell under the mouse whenever it changes, or None once the mouse leaves the map
+	pub fn set_hover_callback<F: FnMut(Option<Coord>) + 'static>(&mut self, callback:F) {
+		self.state.borrow_mut().on_hover = Some(Box::new(callback));
+	}//end set_hover_callback(&mut self, callback)
+
+	/// # set_hover_text(&mut self, coord, text)
+	///
+	/// sets the text of the tooltip drawn next to the mouse while it's over the cell at coord.
+	/// The tooltip is cleared whenever the mouse moves to a different cell, and nothing happens if the mouse has already left coord.
+	pub fn set_hover_text(&mut self, coord:Coord, text:Option<String>) {
+		{
+			let mut state = self.state.borrow_mut();
+			if state.hover != Some(coord) {return;}
+			state.hover_text = text;
+		}
+		self.widget.redraw();
+	}//end set_hover_text(&mut self, coord, text)
+
+	/// # set_selection(&mut self, selection)
+	///
+	/// outlines the rectangle of cells from the top left to the bottom right coord of selection, or removes the outline if it's None
+	pub fn set_selection(&mut self, selection:Option<(Coord,Coord)>) {
+		self.state.borrow_mut().selection = selection;
+		self.widget.redraw();
+	}//end set_selection(&mut self, selection)
+
+	/// # hovered(&self)
+	///
+	/// returns the coordinate of the cell under the mouse, if any
+	pub fn hovered(&self) -> Option<Coord> {
+		self.state.borrow().hover
+	}//end hovered(&self)
+}//end impl for MapView
+
+widget_extends!(MapView, Widget, widget);
+
+/// # draw_map(wid, state)
+///
+/// draws all the visible cells of the map, along with labels and the hover highlight
+fn draw_map(wid:&Widget, state:&MapState) {
+	draw::push_clip(wid.x(), wid.y(), wid.w(), wid.h());
+	draw::draw_rect_fill(wid.x(), wid.y(), wid.w(), wid.h(), Color::Background);
+
+	let cell_size = state.cell_size(wid.w(), wid.h());
+	if cell_size > 0.0 {
+		// figure out which cells are actually visible
+		let first_col = (-state.offset_x / cell_size).floor().max(0.0) as usize;
+		let first_row = (-state.offset_y / cell_size).floor().max(0.0) as usize;
+		let last_col = (((wid.w() as f64 - state.offset_x) / cell_size).ceil().max(0.0) as usize).min(state.cells.cols());
+		let last_row = (((wid.h() as f64 - state.offset_y) / cell_size).ceil().max(0.0) as usize).min(state.cells.rows());
+		// level of detail decisions
+		let show_gap = cell_size >= get_min_gap_cell_size();
+		let show_label = cell_size > get_min_label_cell_width() && cell_size > get_min_label_cell_height();
+		let show_lock = cell_size >= get_min_lock_marker_cell_size();
+		draw::set_font(Font::Helvetica, get_label_font_size());
+
+		for row in first_row..last_row {
+			for col in first_col..last_col {
+				let (cell_x, cell_y, cell_w, cell_h) = cell_rect(wid, state, cell_size, row, col);
+				let (draw_w, draw_h) = if show_gap {(cell_w - 1, cell_h - 1)} else {(cell_w, cell_h)};
+				let c = state.cells.colors.get(row, col).copied().unwrap_or((0,0,0));
+				draw::draw_rect_fill(cell_x, cell_y, draw_w.max(1), draw_h.max(1), Color::from_rgb(c.0, c.1, c.2));
+				let label = state.cells.label(row, col);
+				if show_label && !label.is_empty() {
+					draw::set_draw_color(get_label_color(c));
+					let label = fit_label(label, draw_w - 4);
+					draw::draw_text2(&label, cell_x + 2, cell_y, draw_w - 4, draw_h, Align::Center);
+				}//end if we have room to show the label
+				if show_lock && state.cells.locked.get(row, col).copied().unwrap_or(false) {
+					draw_lock_marker(cell_x, cell_y, draw_w, c);
+				}//end if we should mark this cell as locked
+			}//end looping over visible columns
+		}//end looping over visible rows
+
+		// draw lines between regions
+		if state.show_borders {
+			draw_borders(wid, state, cell_size, (first_row, last_row), (first_col, last_col));
+		}//end if we should show borders
+
+		// outline the selected cells, dark then light so it shows up on any color
+		if let Some((first, last)) = state.selection {
+			let (first_x, first_y, _, _) = cell_rect(wid, state, cell_size, first.row, first.col);
+			let (last_x, last_y, last_w, last_h) = cell_rect(wid, state, cell_size, last.row, last.col);
+			let (sel_w, sel_h) = (last_x + last_w - first_x, last_y + last_h - first_y);
+			draw::set_draw_color(get_black());
+			draw::set_line_style(draw::LineStyle::Solid, 3);
+			draw::draw_rect(first_x - 1, first_y - 1, sel_w + 2, sel_h + 2);
+			draw::set_draw_color(get_white());
+			draw::set_line_style(draw::LineStyle::Dash, 1);
+			draw::draw_rect(first_x - 1, first_y - 1, sel_w + 2, sel_h + 2);
+			draw::set_line_style(draw::LineStyle::Solid, 0);
+		}//end if there's a selection to outline
+
+		// highlight the cell under the mouse
+		if let Some(hover) = state.hover {
+			let (cell_x, cell_y, cell_w, cell_h) = cell_rect(wid, state, cell_size, hover.row, hover.col);
+			let c = state.cells.colors.get(hover.row, hover.col).copied().unwrap_or((0,0,0));
+			draw::set_draw_color(get_label_color(c));
+			draw::set_line_style(draw::LineStyle::Solid, 2);
+			draw::draw_rect(cell_x + 1, cell_y + 1, (cell_w - 2).max(1), (cell_h - 2).max(1));
+			draw::set_line_style(draw::LineStyle::Solid, 0);
+			if let Some(text) = &state.hover_text {
+				draw_tooltip(wid, text, state.mouse_pos);
+			}//end if there's a tooltip to show
+		}//end if we have a cell to highlight
+	}//end if there is anything to draw
+
+	draw::pop_clip();
+}//end draw_map(wid, state)
+
+/// # draw_tooltip(wid, text, mouse_pos)
+///
+/// draws text in a box next to mouse_pos, moving it to the other side of the mouse if it would go past the edge of the widget
+fn draw_tooltip(wid:&Widget, text:&str, mouse_pos:(i32,i32)) {
+	draw::set_font(Font::Helvetica, get_label_font_size());
+	let (text_w, text_h) = draw::measure(text, false);
+	let padding = get_tooltip_padding();
+	let (box_w, box_h) = (text_w + 2 * padding, text_h + 2 * padding);
+	let (offset_x, offset_y) = get_tooltip_offset();
+	let mut box_x = mouse_pos.0 + offset_x;
+	let mut box_y = mouse_pos.1 + offset_y;
+	if box_x + box_w > wid.x() + wid.w() {box_x = (mouse_pos.0 - offset_x - box_w).max(wid.x());}
+	if box_y + box_h > wid.y() + wid.h() {box_y = (mouse_pos.1 - offset_y - box_h).max(wid.y());}
+	draw::draw_rect_fill(box_x, box_y, box_w, box_h, get_tooltip_color());
+	draw::set_draw_color(get_black());
+	draw::draw_rect(box_x, box_y, box_w, box_h);
+	draw::draw_text2(text, box_x + padding, box_y + padding, text_w, text_h, Align::Left | Align::Top);
+}//end draw_tooltip(wid, text, mouse_pos)
+
+/// # draw_borders(wid, state, cell_size, rows, cols)
+///
+/// draws a line along each edge between two visible cells that belong to different regions.
+/// rows and cols are the start (inclusive) and end (exclusive) of the visible cells.
+fn draw_borders(wid:&Widget, state:&MapState, cell_size:f64, rows:(usize,usize), cols:(usize,usize)) {
+	draw::set_draw_color(get_black());
+	let line_width = if cell_size >= get_min_thick_border_cell_size() {2} else {1};
+	draw::set_line_style(draw::LineStyle::Solid, line_width);
+	for row in rows.0..rows.1 {
+		for col in cols.0..cols.1 {
+			let region = state.cells.region(row, col);
+			let (cell_x, cell_y, cell_w, cell_h) = cell_rect(wid, state, cell_size, row, col);
+			// right edge
+			if col + 1 < state.cells.cols() && state.cells.region(row, col + 1) != region {
+				draw::draw_line(cell_x + cell_w, cell_y, cell_x + cell_w, cell_y + cell_h);
+			}//end if region changes to the right
+			// bottom edge
+			if row + 1 < state.cells.rows() && state.cells.region(row + 1, col) != region {
+				draw::draw_line(cell_x, cell_y + cell_h, cell_x + cell_w, cell_y + cell_h);
+			}//end if region changes below
+		}//end looping over visible columns
+	}//end looping over visible rows
+	draw::set_line_style(draw::LineStyle::Solid, 0);
+}//end draw_borders(wid, state, cell_size, rows, cols)
+
+/// # draw_lock_marker(cell_x, cell_y, cell_w, rgb)
+///
+/// draws a small triangle in the top right corner of a cell to show that it's locked, in a color that stands out against rgb
+fn draw_lock_marker(cell_x:i32, cell_y:i32, cell_w:i32, rgb:(u8,u8,u8)) {
+	let size = (cell_w / 4).clamp(3, 12);
+	let right = cell_x + cell_w;
+	draw::set_draw_color(get_label_color(rgb));
+	draw::draw_polygon(right - size, cell_y, right, cell_y, right, cell_y + size);
+}//end draw_lock_marker(cell_x, cell_y, cell_w, rgb)
+
+/// # cell_rect(wid, state, cell_size, row, col)
+///
+/// returns the x, y, width and height in window coordinates of the specified cell.
+/// Edges are rounded from the floating point position so that neighboring cells never leave gaps.
+fn cell_rect(wid:&Widget, state:&MapState, cell_size:f64, row:usize, col:usize) -> (i32,i32,i32,i32) {
+	let left = (wid.x() as f64 + state.offset_x + col as f64 * cell_size).round() as i32;
+	let top = (wid.y() as f64 + state.offset_y + row as f64 * cell_size).round() as i32;
+	let right = (wid.x() as f64 + state.offset_x + (col + 1) as f64 * cell_size).round() as i32;
+	let bottom = (wid.y() as f64 + state.offset_y + (row + 1) as f64 * cell_size).round() as i32;
+	(left, top, right - left, bottom - top)
+}//end cell_rect(wid, state, cell_size, row, col)
+
+/// # fit_label(label, max_width)
+///
+/// shortens label one character at a time until it fits within max_width pixels in the current font
+fn fit_label(label:&str, max_width:i32) -> String {
+	let mut shrunk = label.to_string();
+	while !shrunk.is_empty() && draw::width(&shrunk) > max_width as f64 {
+		shrunk.pop();
+	}//end removing characters until it fits
+	return shrunk;
+}//end fit_label(label, max_width)
+
+/// # paint_under_mouse(wid, state)
+///
+/// calls the paint callback for the cell under the mouse, unless it was already painted during this stroke
+fn paint_under_mouse(wid:&Widget, state:&Rc<RefCell<MapState>>) {
+	let under_mouse = state.borrow().cell_at(wid, app::event_x(), app::event_y());
+	update_hover(wid, state, under_mouse);
+	let to_paint = {
+		let mut state = state.borrow_mut();
+		if under_mouse.is_some() && under_mouse != state.last_painted {
+			state.last_painted = under_mouse;
+			under_mouse
+		}//end if this is a new cell
+		else {None}
+	};
+	// call the callback without holding the borrow, in case it updates the map
+	if let Some(coord) = to_paint {
+		let callback = state.borrow_mut().on_paint.take();
+		if let Some(mut callback) = callback {
+			callback(coord);
+			state.borrow_mut().on_paint = Some(callback);
+		}//end if we have something to call
+	}//end if we have a cell to paint
+}//end paint_under_mouse(wid, state)
+
+/// # update_hover(wid, state, new_hover)
+///
+/// Keeps track of the mouse position and the cell under it, redrawing if the highlight or tooltip needs to move.
+/// If the hovered cell changed, the tooltip is cleared and the hover callback is called.
+fn update_hover(wid:&Widget, state:&Rc<RefCell<MapState>>, new_hover:Option<Coord>) {
+	let changed = {
+		let mut state = state.borrow_mut();
+		state.mouse_pos = (app::event_x(), app::event_y());
+		let changed = new_hover != state.hover;
+		if changed {
+			state.hover = new_hover;
+			state.hover_text = None;
+		}//end if we're over a different cell now
+		if changed || state.hover_text.is_some() {
+			let mut wid = wid.clone();
+			wid.redraw();
+		}//end if something moved
+		changed
+	};
+	// call the callback without holding the borrow, in case it updates the map
+	if changed {
+		let callback = state.borrow_mut().on_hover.take();
+		if let Some(mut callback) = callback {
+			callback(new_hover);
+			state.borrow_mut().on_hover = Some(callback);
+		}//end if we have something to call
+	}//end if the hovered cell changed
+}//end update_hover(wid, state, new_hover)
+
+/// # handle_map_event(wid, state, ev)
+///
+/// handles zooming, panning, hovering and clicking for the map
+fn handle_map_event(wid:&mut Widget, state:&Rc<RefCell<MapState>>, ev:Event) -> bool {
+	match ev {
+		Event::Enter => true,
+		Event::Leave => {
+			update_hover(wid, state, None);
+			true
+		},
+		Event::Move => {
+			let new_hover = state.borrow().cell_at(wid, app::event_x(), app::event_y());
+			update_hover(wid, state, new_hover);
+			true
+		},
+		Event::Push => {
+			let start_painting = {
+				let mut state = state.borrow_mut();
+				state.painting = state.paint_mode && app::event_mouse_button() == MouseButton::Left;
+				if !state.painting {
+					state.drag_last = Some((app::event_x(), app::event_y()));
+					state.dragged = false;
+				}//end if we might be panning or clicking
+				state.painting
+			};
+			if start_painting {paint_under_mouse(wid, state);}
+			true
+		},
+		Event::Drag => {
+			if state.borrow().painting {
+				paint_under_mouse(wid, state);
+				return true;
+			}//end if we're painting instead of panning
+			let mut state = state.borrow_mut();
+			if let Some((last_x, last_y)) = state.drag_last {
+				let (dx, dy) = (app::event_x() - last_x, app::event_y() - last_y);
+				if state.dragged || dx.abs() > get_drag_threshold() || dy.abs() > get_drag_threshold() {
+					state.dragged = true;
+					state.offset_x += dx as f64;
+					state.offset_y += dy as f64;
+					state.drag_last = Some((app::event_x(), app::event_y()));
+					wid.redraw();
+				}//end if we've moved far enough to count as dragging
+			}//end if we know where the drag started
+			true
+		},
+		Event::Released => {
+			if state.borrow().painting {
+				{
+					let mut state = state.borrow_mut();
+					state.painting = false;
+					state.last_painted = None;
+				}
+				let callback = state.borrow_mut().on_paint_done.take();
+				if let Some(mut callback) = callback {
+					callback();
+					state.borrow_mut().on_paint_done = Some(callback);
+				}//end if we have something to call
+				return true;
+			}//end if we just finished painting
+			let clicked = {
+				let mut state = state.borrow_mut();
+				state.drag_last = None;
+				if state.dragged {None}
+				else {state.cell_at(wid, app::event_x(), app::event_y())}
+			};
+			// call the callback without holding the borrow, in case it updates the map
+			if let Some(coord) = clicked {
+				let callback = state.borrow_mut().on_click.take();
+				if let Some(mut callback) = callback {
+					callback(coord);
+					state.borrow_mut().on_click = Some(callback);
+				}//end if we have something to call
+			}//end if we clicked on a cell
+			true
+		},
+		Event::MouseWheel => {
+			let mut state = state.borrow_mut();
+			// fltk reports scrolling away from the user as Down
+			let factor = match app::event_dy() {
+				MouseWheel::Down => get_zoom_step(),
+				MouseWheel::Up => 1.0 / get_zoom_step(),
+				_ => return false,
+			};
+			let old_size = state.cell_size(wid.w(), wid.h());
+			if old_size <= 0.0 {return true;}
+			let max_zoom = state.zoom * get_max_cell_size() / old_size;
+			let new_zoom = (state.zoom * factor).clamp(get_min_zoom(), max_zoom.max(1.0));
+			// keep the point under the mouse in the same spot
+			let mouse_x = (app::event_x() - wid.x()) as f64;
+			let mouse_y = (app::event_y() - wid.y()) as f64;
+			let scale = new_zoom / state.zoom;
+			state.offset_x = mouse_x - (mouse_x - state.offset_x) * scale;
+			state.offset_y = mouse_y - (mouse_y - state.offset_y) * scale;
+			state.zoom = new_zoom;
+			wid.redraw();
+			true
+		},
+		Event::Resize => {
+			state.borrow_mut().refit(wid.w(), wid.h());
+			true
+		},
+		_ => false,
+	}//end matching event
+}//end handle_map_event(wid, state, ev)
+
+/// # OverviewState
+///
+/// The colors and highlighted cell of an OverviewMap, shared with its draw and handle callbacks
+struct OverviewState {
+	/// the color of each cell
+	colors: Grid<(u8,u8,u8)>,
+	/// the cell to outline, if any
+	current: Option<Coord>,
+	/// called with the coordinate of a cell when it's clicked
+	on_click: Option<Box<dyn FnMut(Coord)>>,
+}//end struct OverviewState
+
+impl OverviewState {
+	/// # layout(&self, wid)
+	///
+	/// returns the size of each cell along with the x and y of the top left corner, so the whole grid fits centered in wid.
+	/// Cells can be smaller than a pixel, so everything is kept as f64 until drawing.
+	fn layout(&self, wid:&Widget) -> (f64, f64, f64) {
+		if self.colors.rows() == 0 || self.colors.cols() == 0 {return (0.0, 0.0, 0.0);}
+		let cell_size = (wid.w() as f64 / self.colors.cols() as f64).min(wid.h() as f64 / self.colors.rows() as f64);
+		let x = wid.x() as f64 + (wid.w() as f64 - cell_size * self.colors.cols() as f64) / 2.0;
+		let y = wid.y() as f64 + (wid.h() as f64 - cell_size * self.colors.rows() as f64) / 2.0;
+		return (cell_size, x, y);
+	}//end layout(&self, wid)
+}//end impl for OverviewState
+
+/// # OverviewMap
+///
+/// A small, fixed view of a whole grid of colored cells with one cell outlined, for showing where something is at a glance.
+/// Unlike MapView, it can't be zoomed or panned, and it has no labels.
+#[derive(Clone)]
+pub struct OverviewMap {
+	/// # widget
+	/// The widget the overview is drawn in
+	pub widget: Widget,
+	/// # state
+	/// The cells and highlight, shared with the draw and handle callbacks
+	state: Rc<RefCell<OverviewState>>,
+}//end struct OverviewMap
+
+impl OverviewMap {
+	/// # new(x, y, w, h)
+	///
+	/// constructs an empty overview at the specified position and size
+	pub fn new(x:i32, y:i32, w:i32, h:i32) -> OverviewMap {
+		let mut widget = Widget::new(x, y, w, h, None);
+		let state = Rc::new(RefCell::new(OverviewState {
+			colors: Grid::new(0,0),
+			current: None,
+			on_click: None,
+		}));
+
+		let draw_state = state.clone();
+		widget.draw(move |wid| {
+			draw_overview(wid, &draw_state.borrow());
+		});
+
+		let handle_state = state.clone();
+		widget.handle(move |wid, ev| {
+			match ev {
+				Event::Push => app::event_mouse_button() == MouseButton::Left,
+				Event::Released => {
+					let mut state = handle_state.borrow_mut();
+					let (cell_size, x, y) = state.layout(wid);
+					if cell_size <= 0.0 {return true;}
+					let col = ((app::event_x() as f64 - x) / cell_size).floor();
+					let row = ((app::event_y() as f64 - y) / cell_size).floor();
+					if row < 0.0 || col < 0.0 || row as usize >= state.colors.rows() || col as usize >= state.colors.cols() {return true;}
+					let coord = Coord::new(row as usize, col as usize);
+					if let Some(on_click) = state.on_click.as_mut() {
+						on_click(coord);
+					}//end if someone wants to know about clicks
+					true
+				},
+				_ => false,
+			}//end matching event
+		});
+
+		OverviewMap {
+			widget,
+			state,
+		}//end struct construction
+	}//end new(x, y, w, h)
+
+	/// # set_colors(&mut self, colors, current)
+	///
+	/// replaces the cells being shown, and outlines the cell at current if there is one
+	pub fn set_colors(&mut self, colors:Grid<(u8,u8,u8)>, current:Option<Coord>) {
+		{
+			let mut state = self.state.borrow_mut();
+			state.colors = colors;
+			state.current = current;
+		}
+		self.widget.redraw();
+	}//end set_colors(&mut self, colors, current)
+
+	/// # set_click_callback(&mut self, callback)
+	///
+	/// sets what happens when a cell is clicked
+	pub fn set_click_callback<F: FnMut(Coord) + 'static>(&mut self, callback:F) {
+		self.state.borrow_mut().on_click = Some(Box::new(callback));
+	}//end set_click_callback(&mut self, callback)
+}//end impl for OverviewMap
+
+widget_extends!(OverviewMap, Widget, widget);
+
+/// # draw_overview(wid, state)
+///
+/// draws every cell of the overview, then outlines the current cell so it stands out even when cells are tiny
+fn draw_overview(wid:&Widget, state:&OverviewState) {
+	draw::push_clip(wid.x(), wid.y(), wid.w(), wid.h());
+	draw::draw_rect_fill(wid.x(), wid.y(), wid.w(), wid.h(), Color::Background);
+	let (cell_size, x, y) = state.layout(wid);
+	if cell_size > 0.0 {
+		// round the edges of each cell rather than the size, so cells line up without gaps
+		let edge = |start:f64, index:usize| (start + cell_size * index as f64).round() as i32;
+		for row in 0..state.colors.rows() {
+			for col in 0..state.colors.cols() {
+				let c = state.colors.get(row, col).copied().unwrap_or((0,0,0));
+				let (cell_x, cell_y) = (edge(x, col), edge(y, row));
+				let (cell_w, cell_h) = (edge(x, col + 1) - cell_x, edge(y, row + 1) - cell_y);
+				if cell_w > 0 && cell_h > 0 {
+					draw::draw_rect_fill(cell_x, cell_y, cell_w, cell_h, Color::from_rgb(c.0, c.1, c.2));
+				}//end if the cell covers any pixels
+			}//end looping over columns
+		}//end looping over rows
+
+		if let Some(current) = state.current {
+			// a dark outline with a light one inside shows up on any color, and is at least a few pixels even if cells are smaller
+			let (cell_x, cell_y) = (edge(x, current.col), edge(y, current.row));
+			let (cell_w, cell_h) = ((edge(x, current.col + 1) - cell_x).max(1), (edge(y, current.row + 1) - cell_y).max(1));
+			draw::set_line_style(draw::LineStyle::Solid, 2);
+			draw::set_draw_color(get_black());
+			draw::draw_rect(cell_x - 2, cell_y - 2, cell_w + 4, cell_h + 4);
+			draw::set_line_style(draw::LineStyle::Solid, 1);
+			draw::set_draw_color(get_white());
+			draw::draw_rect(cell_x - 1, cell_y - 1, cell_w + 2, cell_h + 2);
+			draw::set_line_style(draw::LineStyle::Solid, 0);
+		}//end if there's a cell to outline
+	}//end if there is anything to draw
+	draw::pop_clip();
+}//end draw_overview(wid, state)
@@ -0,0 +1,470 @@
+use grid::Grid;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use crate::grouping::Building;
+use crate::grouping::BuildingType;
+use crate::grouping::GroupInstance;
+
+/// # get_road_color()
+///
+/// The color used for every road, regardless of palette
+pub fn get_road_color() -> (u8,u8,u8) {(55,55,55)}
+/// # get_empty_color()
+///
+/// The color used for buildings that haven't been given a type yet
+pub fn get_empty_color() -> (u8,u8,u8) {(0,0,0)}
+
+/// # BuildingPalette
+///
+/// The city-wide list of colors used for building types, so that a building type is the same color in every neighborhood.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildingPalette {
+	/// # seed
+	///
+	/// The seed this palette was generated from, or None if it came from a preset or was edited by hand
+	pub seed: Option<u64>,
+	/// # colors
+	///
+	/// one color per building type, in the order of BuildingType::palette_types()
+	colors: Vec<(u8,u8,u8)>,
+}//end struct BuildingPalette
+
+#[allow(dead_code)]
+impl BuildingPalette {
+	/// # from_seed(seed)
+	///
+	/// Generates a palette of random colors. The same seed will always give the same palette.
+	pub fn from_seed(seed:u64) -> BuildingPalette {
+		let mut rng = StdRng::seed_from_u64(seed);
+		BuildingPalette {
+			seed: Some(seed),
+			colors: gen_distinct_colors(&mut rng, BuildingType::palette_types().len(), &[get_road_color(), get_empty_color()]),
+		}//end struct construction
+	}//end from_seed(seed)
+
+	/// # from_preset(preset)
+	///
+	/// Creates a palette from one of the curated presets, generating extra colors if the preset is too short for every building type.
+	pub fn from_preset(preset:&PalettePreset) -> BuildingPalette {
+		BuildingPalette {
+			seed: None,
+			colors: preset.extended_colors(BuildingType::palette_types().len(), &[get_road_color(), get_empty_color()]),
+		}//end struct construction
+	}//end from_preset(preset)
+
+	/// # default_preset()
+	///
+	/// A hand-picked palette where similar building types get similar colors.
+	pub fn default_preset() -> BuildingPalette {
+		BuildingPalette {
+			seed: None,
+			colors: vec![
+				(237,201,145),// Residence
+				(242,142,43),// Shop
+				(255,214,0),// School
+				(176,122,161),// Museum
+				(255,255,255),// Hospital
+				(200,230,255),// Pharmacy
+				(89,161,79),// Park
+				(255,105,180),// Arcade
+				(156,117,95),// Pound
+				(78,121,167),// GovOffice
+				(196,156,110),// Cafe
+				(118,183,178),// CorpOffice
+				(225,87,89),// ChainStore
+				(0,51,153),// Police
+				(255,157,167),// Market
+				(148,103,189),// Temple
+				(186,176,172),// Church
+				(210,180,255),// Shrine
+				(127,255,212),// Spa
+				(128,0,64),// Mansion
+				(110,90,40),// Landfill
+				(121,121,121),// Factory
+				(30,30,30),// Prison
+				(200,0,0),// FireDept
+			],
+		}//end struct construction
+	}//end default_preset()
+
+	/// # color_for(&self, build_type)
+	///
+	/// Returns the color that buildings of the specified type should use.
+	pub fn color_for(&self, build_type:&BuildingType) -> (u8,u8,u8) {
+		match build_type {
+			BuildingType::Empty => get_empty_color(),
+			BuildingType::Road => get_road_color(),
+			_ => {
+				let palette_index = build_type.palette_index().expect("Every other building type should be in palette_types()");
+				self.colors.get(palette_index).expect("Palette should have a color for every building type").to_owned()
+			}
+		}//end matching build_type to a color
+	}//end color_for(&self, build_type)
+
+	/// # set_color(&mut self, build_type, color)
+	///
+	/// Sets the color for a building type. Empty and Road have fixed colors, so they are ignored.
+	/// Since the palette no longer matches its seed after this, seed is cleared.
+	pub fn set_color(&mut self, build_type:&BuildingType, color:(u8,u8,u8)) {
+		if let Some(palette_index) = build_type.palette_index() {
+			self.colors[palette_index] = color;
+			self.seed = None;
+		}//end if this building type has a palette color
+	}//end set_color(&mut self, build_type, color)
+
+	/// # legend_entries(&self)
+	///
+	/// Returns a list of building type names and colors, suitable for showing a color key.
+	pub fn legend_entries(&self) -> Vec<(String, (u8,u8,u8))> {
+		let mut entries = vec![(BuildingType::Road.to_string(), get_road_color())];
+		for build_type in BuildingType::palette_types() {
+			entries.push((build_type.to_string(), self.color_for(&build_type)));
+		}//end adding each building type to the entries
+		return entries;
+	}//end legend_entries(&self)
+
+	/// # color_code_building(&self, building)
+	///
+	/// Sets the rgb_color of building based on its building type.
+	pub fn color_code_building(&self, building:&mut Building) {
+		building.rgb_color = self.color_for(&building.build_type);
+	}//end color_code_building(&self, building)
+
+	/// # recolor_grid(&self, grid)
+	///
+	/// Goes through every neighborhood in grid and recolors all the buildings with this palette.
+	/// This should be called whenever the palette changes so that already generated neighborhoods stay consistent.
+	pub fn recolor_grid(&self, grid:&mut Grid<GroupInstance>) {
+		for instance in grid.iter_mut() {
+			for building in instance.sub_grid.iter_mut() {
+				self.color_code_building(building);
+			}//end recoloring each building in neighborhood
+		}//end looping over each neighborhood in the grid
+	}//end recolor_grid(&self, grid)
+}//end impl for BuildingPalette
+
+/// # PalettePreset
+///
+/// A named, hand-picked list of colors that can be applied to buildings or districts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PalettePreset {
+	/// # name
+	///
+	/// the name shown to the user
+	pub name: &'static str,
+	/// # colorblind_safe
+	///
+	/// whether the colors stay distinguishable for the common forms of color blindness
+	pub colorblind_safe: bool,
+	/// # colors
+	///
+	/// the colors of the preset, in the order they should be used
+	pub colors: Vec<(u8,u8,u8)>,
+}//end struct PalettePreset
+
+impl PalettePreset {
+	/// # display_name(&self)
+	///
+	/// Returns the name of the preset, marking it if it's colorblind safe.
+	pub fn display_name(&self) -> String {
+		if self.colorblind_safe {
+			format!("{} (colorblind safe)", self.name)
+		}//end if we should mark this preset
+		else { self.name.to_string() }
+	}//end display_name(&self)
+
+	/// # extended_colors(&self, num_colors, avoid)
+	///
+	/// Returns num_colors colors, starting with the colors of the preset.
+	/// Preset colors that are in avoid are replaced, and if the preset doesn't have enough colors, the rest are added on the end.
+	/// Replacement and extra colors are generated to be as perceptually far as possible from the kept preset colors and from avoid.
+	/// They're generated with a fixed seed, so a preset always gives the same result.
+	/// Note that a colorblind safe preset is only fully colorblind safe within its own colors.
+	pub fn extended_colors(&self, num_colors:usize, avoid:&[(u8,u8,u8)]) -> Vec<(u8,u8,u8)> {
+		let kept: Vec<Option<(u8,u8,u8)>> = (0..num_colors).map(|index| self.colors.get(index).filter(|color| !avoid.contains(color)).copied()).collect();
+		let num_missing = kept.iter().filter(|color| color.is_none()).count();
+		let mut extra = Vec::new().into_iter();
+		if num_missing > 0 {
+			let mut rng = StdRng::seed_from_u64(get_preset_fill_seed());
+			let mut existing: Vec<(u8,u8,u8)> = kept.iter().flatten().copied().collect();
+			existing.extend_from_slice(avoid);
+			extra = gen_distinct_colors(&mut rng, num_missing, &existing).into_iter();
+		}//end if we need to generate some more colors
+		let colors = kept.into_iter().map(|color| color.or_else(|| extra.next()).expect("a color should be generated for every gap")).collect();
+		return colors;
+	}//end extended_colors(&self, num_colors, avoid)
+}//end impl for PalettePreset
+
+/// # get_preset_fill_seed()
+///
+/// The seed used to fill out presets that don't have enough colors
+fn get_preset_fill_seed() -> u64 {536}
+
+/// # get_palette_presets()
+///
+/// Returns the list of curated palettes that the user can pick from.
+pub fn get_palette_presets() -> Vec<PalettePreset> {
+	vec![
+		PalettePreset {
+			name: "Default",
+			colorblind_safe: false,
+			colors: BuildingPalette::default_preset().colors,
+		},
+		PalettePreset {
+			name: "Pastel",
+			colorblind_safe: false,
+			colors: vec![(255,179,186),(255,223,186),(255,255,186),(186,255,201),(186,225,255),(218,186,255),(255,186,242),(196,250,248),(251,231,198),(180,248,200),(160,231,229),(255,174,188)],
+		},
+		PalettePreset {
+			name: "Earth Tones",
+			colorblind_safe: false,
+			colors: vec![(141,110,99),(188,170,164),(85,107,47),(143,151,121),(204,119,34),(160,82,45),(222,184,135),(112,128,144),(189,183,107),(128,70,27),(46,80,60),(218,165,32)],
+		},
+		PalettePreset {
+			name: "Neon",
+			colorblind_safe: false,
+			colors: vec![(255,0,110),(251,86,7),(255,190,11),(131,56,236),(58,134,255),(0,245,212),(155,93,229),(241,91,181),(254,228,64),(0,187,249)],
+		},
+		PalettePreset {
+			name: "Ocean",
+			colorblind_safe: false,
+			colors: vec![(3,4,94),(2,62,138),(0,119,182),(0,150,199),(0,180,216),(72,202,228),(144,224,239),(173,232,244),(202,240,248),(38,70,83),(42,157,143),(233,196,106)],
+		},
+		PalettePreset {
+			name: "Okabe-Ito",
+			colorblind_safe: true,
+			colors: vec![(230,159,0),(86,180,233),(0,158,115),(240,228,66),(0,114,178),(213,94,0),(204,121,167),(0,0,0)],
+		},
+		PalettePreset {
+			name: "Tol Bright",
+			colorblind_safe: true,
+			colors: vec![(68,119,170),(102,204,238),(34,136,51),(204,187,68),(238,102,119),(170,51,119),(187,187,187)],
+		},
+		PalettePreset {
+			name: "Tol Muted",
+			colorblind_safe: true,
+			colors: vec![(51,34,136),(136,204,238),(68,170,153),(17,119,51),(153,153,51),(221,204,119),(204,102,119),(136,34,85),(170,68,153)],
+		},
+	]
+}//end get_palette_presets()
+
+/// # rgb_to_oklab(rgb)
+///
+/// Converts an sRGB color to the OKLab color space, where straight-line distance roughly matches how different two colors look.
+/// Returns (lightness, a, b).
+pub fn rgb_to_oklab(rgb:(u8,u8,u8)) -> (f32,f32,f32) {
+	// convert each channel from sRGB to linear light
+	let to_linear = |channel:u8| -> f32 {
+		let c = channel as f32 / 255.0;
+		if c <= 0.04045 { c / 12.92 }
+		else { ((c + 0.055) / 1.055).powf(2.4) }
+	};
+	let r = to_linear(rgb.0);
+	let g = to_linear(rgb.1);
+	let b = to_linear(rgb.2);
+
+	// linear rgb to cone responses
+	let l = 0.41222146 * r + 0.53633255 * g + 0.051445995 * b;
+	let m = 0.2119035 * r + 0.6806995 * g + 0.10739696 * b;
+	let s = 0.08830246 * r + 0.28171885 * g + 0.6299787 * b;
+	let l = l.cbrt();
+	let m = m.cbrt();
+	let s = s.cbrt();
+
+	// cone responses to lab
+	(
+		0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+		1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+		0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+	)
+}//end rgb_to_oklab(rgb)
+
+/// # perceptual_distance(color1, color2)
+///
+/// Returns how different two colors look, as the distance between them in OKLab.
+pub fn perceptual_distance(color1:(u8,u8,u8), color2:(u8,u8,u8)) -> f32 {
+	let lab1 = rgb_to_oklab(color1);
+	let lab2 = rgb_to_oklab(color2);
+	let l_diff = lab1.0 - lab2.0;
+	let a_diff = lab1.1 - lab2.1;
+	let b_diff = lab1.2 - lab2.2;
+	f32::sqrt(l_diff * l_diff + a_diff * a_diff + b_diff * b_diff)
+}//end perceptual_distance(color1, color2)
+
+/// # get_distinct_color_candidates()
+///
+/// The number of random candidates to consider for each generated color. More candidates means better spacing but slower generation.
+fn get_distinct_color_candidates() -> usize {200}
+/// # get_min_color_lightness()
+///
+/// Colors darker than this OKLab lightness are skipped, since they are hard to tell apart and hide labels
+fn get_min_color_lightness() -> f32 {0.45}
+/// # get_max_color_lightness()
+///
+/// Colors lighter than this OKLab lightness are skipped, since they look washed out
+fn get_max_color_lightness() -> f32 {0.92}
+/// # get_max_color_chroma()
+///
+/// Colors more saturated than this OKLab chroma are skipped, since they tend to look garish next to each other
+fn get_max_color_chroma() -> f32 {0.2}
+
+/// # gen_distinct_colors(rng, num_colors, existing)
+///
+/// Generates num_colors colors that are spread out as far as possible from each other and from the colors in existing.
+/// For each new color, a number of random candidates are tried, and the one whose nearest neighbor is farthest away is kept.
+/// Candidates that are very dark, very light or very saturated are skipped.
+///
+/// Returns a vector of (u8,u8,u8), representing rgb values. Colors in existing are not included.
+pub fn gen_distinct_colors(rng:&mut impl Rng, num_colors:usize, existing:&[(u8,u8,u8)]) -> Vec<(u8,u8,u8)> {
+	let mut taken: Vec<(u8,u8,u8)> = existing.to_vec();
+	let mut color_options: Vec<(u8,u8,u8)> = Vec::new();
+
+	while color_options.len() < num_colors {
+		let mut best_color = (rng.gen(), rng.gen(), rng.gen());
+		let mut best_distance = -1.0;
+		let mut num_tried = 0;
+		while num_tried < get_distinct_color_candidates() {
+			let candidate: (u8,u8,u8) = (rng.gen(), rng.gen(), rng.gen());
+			let lab = rgb_to_oklab(candidate);
+			let chroma = f32::sqrt(lab.1 * lab.1 + lab.2 * lab.2);
+			if lab.0 < get_min_color_lightness() || lab.0 > get_max_color_lightness() || chroma > get_max_color_chroma() {
+				continue;
+			}//end if this candidate is too dark, light or saturated
+			num_tried += 1;
+
+			// find distance to the closest color we've already got
+			let mut nearest = f32::MAX;
+			for other in &taken {
+				nearest = nearest.min(perceptual_distance(candidate, other.to_owned()));
+			}//end finding nearest taken color
+			if nearest > best_distance {
+				best_distance = nearest;
+				best_color = candidate;
+			}//end if this is our new best candidate
+		}//end trying each candidate
+
+		taken.push(best_color);
+		color_options.push(best_color);
+	}//end looping while we should still fill our list
+
+	return color_options;
+}//end gen_distinct_colors(rng, num_colors, existing)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn same_seed_gives_same_palette() {
+		assert_eq!(BuildingPalette::from_seed(42), BuildingPalette::from_seed(42));
+		assert_ne!(BuildingPalette::from_seed(42), BuildingPalette::from_seed(43));
+	}//end same_seed_gives_same_palette()
+
+	#[test]
+	fn every_building_type_has_a_color() {
+		for palette in [BuildingPalette::default_preset(), BuildingPalette::from_seed(7)] {
+			for build_type in BuildingType::palette_types() {
+				palette.color_for(&build_type);
+			}//end looking up each type
+			assert_eq!(palette.color_for(&BuildingType::Road), get_road_color());
+			assert_eq!(palette.color_for(&BuildingType::Empty), get_empty_color());
+			assert_eq!(palette.legend_entries().len(), BuildingType::palette_types().len() + 1);
+		}//end checking each palette
+	}//end every_building_type_has_a_color()
+
+	#[test]
+	fn set_color_forgets_seed_and_ignores_fixed_types() {
+		let mut palette = BuildingPalette::from_seed(3);
+		palette.set_color(&BuildingType::Park, (1,2,3));
+		assert_eq!(palette.color_for(&BuildingType::Park), (1,2,3));
+		assert_eq!(palette.seed, None);
+		palette.set_color(&BuildingType::Road, (9,9,9));
+		assert_eq!(palette.color_for(&BuildingType::Road), get_road_color());
+	}//end set_color_forgets_seed_and_ignores_fixed_types()
+
+	#[test]
+	fn recolor_grid_uses_palette() {
+		let mut grid = Grid::new(1, 1);
+		grid.fill(GroupInstance::default());
+		grid.get_mut(0, 0).unwrap().initialize_sub_grid(2, 2);
+		grid.get_mut(0, 0).unwrap().sub_grid.get_mut(1, 1).unwrap().build_type = BuildingType::Shop;
+		let palette = BuildingPalette::from_seed(11);
+		palette.recolor_grid(&mut grid);
+		let sub_grid = &grid.get(0, 0).unwrap().sub_grid;
+		assert_eq!(sub_grid.get(1, 1).unwrap().rgb_color, palette.color_for(&BuildingType::Shop));
+		assert_eq!(sub_grid.get(0, 0).unwrap().rgb_color, get_empty_color());
+	}//end recolor_grid_uses_palette()
+
+	#[test]
+	fn oklab_matches_reference_values() {
+		let (l, a, b) = rgb_to_oklab((255,255,255));
+		assert!((l - 1.0).abs() < 0.001 && a.abs() < 0.001 && b.abs() < 0.001);
+		let (l, a, b) = rgb_to_oklab((0,0,0));
+		assert!(l.abs() < 0.001 && a.abs() < 0.001 && b.abs() < 0.001);
+		// pure red is about (0.628, 0.225, 0.126) in OKLab
+		let (l, a, b) = rgb_to_oklab((255,0,0));
+		assert!((l - 0.628).abs() < 0.002 && (a - 0.225).abs() < 0.002 && (b - 0.126).abs() < 0.002);
+	}//end oklab_matches_reference_values()
+
+	#[test]
+	fn perceptual_distance_is_a_distance() {
+		let colors = [(0,0,0), (255,255,255), (200,30,30), (30,200,30), (120,120,120)];
+		for first in colors {
+			assert_eq!(perceptual_distance(first, first), 0.0);
+			for second in colors {
+				assert!((perceptual_distance(first, second) - perceptual_distance(second, first)).abs() < 1e-6);
+			}//end comparing against each other color
+		}//end checking each color
+		// lightness differences should count for more than tiny rgb nudges
+		assert!(perceptual_distance((100,100,100), (101,100,100)) < perceptual_distance((100,100,100), (180,180,180)));
+	}//end perceptual_distance_is_a_distance()
+
+	#[test]
+	fn distinct_colors_are_spread_out_and_in_range() {
+		let mut rng = StdRng::seed_from_u64(5);
+		let existing = [(255,0,0)];
+		let colors = gen_distinct_colors(&mut rng, 12, &existing);
+		assert_eq!(colors.len(), 12);
+		for (index, color) in colors.iter().enumerate() {
+			let (l, a, b) = rgb_to_oklab(*color);
+			assert!(l >= get_min_color_lightness() && l <= get_max_color_lightness());
+			assert!(f32::sqrt(a * a + b * b) <= get_max_color_chroma());
+			assert!(!existing.contains(color));
+			for other in &colors[index + 1..] {
+				assert!(perceptual_distance(*color, *other) > 0.02, "{:?} and {:?} look too alike", color, other);
+			}//end comparing against each later color
+		}//end checking each color
+	}//end distinct_colors_are_spread_out_and_in_range()
+
+	#[test]
+	fn presets_extend_the_same_way_every_time() {
+		for preset in get_palette_presets() {
+			let short = preset.extended_colors(3, &[]);
+			assert_eq!(short.as_slice(), &preset.colors[..3]);
+			let long = preset.extended_colors(40, &[get_road_color()]);
+			assert_eq!(long.len(), 40);
+			assert_eq!(&long[..preset.colors.len()], preset.colors.as_slice());
+			assert_eq!(long, preset.extended_colors(40, &[get_road_color()]));
+		}//end checking each preset
+	}//end presets_extend_the_same_way_every_time()
+
+	#[test]
+	fn preset_palettes_dont_reuse_fixed_colors() {
+		for preset in get_palette_presets() {
+			let palette = BuildingPalette::from_preset(&preset);
+			for build_type in BuildingType::palette_types() {
+				let color = palette.color_for(&build_type);
+				assert_ne!(color, get_road_color(), "{} uses the road color in {}", build_type, preset.name);
+				assert_ne!(color, get_empty_color(), "{} uses the empty color in {}", build_type, preset.name);
+			}//end checking each building type
+			// colors that don't collide stay where the preset put them
+			for (color, preset_color) in palette.colors.iter().zip(&preset.colors) {
+				if *preset_color != get_road_color() && *preset_color != get_empty_color() {
+					assert_eq!(color, preset_color);
+				}//end if this preset color should have been kept
+			}//end comparing with the preset
+		}//end checking each preset
+	}//end preset_palettes_dont_reuse_fixed_colors()
+}//end mod tests
@@ -0,0 +1,387 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use grid::Grid;
+
+use crate::editing::validate_district_name;
+use crate::error::CityGenError;
+use crate::error::get_max_district_len;
+use crate::error::get_max_neighborhood_len;
+use crate::error::get_min_neighborhood_len;
+use crate::generation::LazyNeighborhoods;
+use crate::grouping::Building;
+use crate::grouping::BuildingType;
+use crate::grouping::Coord;
+use crate::grouping::GroupInstance;
+use crate::grouping::Grouping;
+
+/// # get_save_header()
+///
+/// The first line of every save file, used to recognize save files and their format version
+fn get_save_header() -> &'static str {"simple-city-generator save 1"}
+
+/// # get_locked_marker()
+///
+/// The character added to the end of a building type in a save file to show the building is locked
+fn get_locked_marker() -> char {'*'}
+
+/// # LoadedCity
+///
+/// The districts, the city grid, and how to generate any neighborhoods which haven't been generated yet, as read from a save file
+pub type LoadedCity = (Vec<Grouping>, Grid<GroupInstance>, Option<LazyNeighborhoods>);
+
+/// # save_city(path, districts, city_grid, lazy_nhoods)
+///
+/// Writes the districts and every cell and building of city_grid to a text file at path.
+/// If some neighborhoods haven't been generated yet, lazy_nhoods is saved too, so they can still be generated the same way after loading.
+/// Cells refer to their district by id, so districts can share names or be renamed without getting mixed up.
+/// Building colors aren't saved, since they come from the building palette.
+///
+/// The format is one item per line:
+/// - the header line
+/// - `district <id> <r> <g> <b> <locked> <name>` for each district
+/// - `lazy <seed> <neighborhood rows> <neighborhood cols>`, only if there's lazy_nhoods
+/// - `grid <rows> <cols>`
+/// - `cell <row> <col> <district id or 0> <cell locked> <neighborhood locked> <neighborhood rows> <neighborhood cols>` for each cell, followed by a line of comma separated building types for each row of its neighborhood
+/// - `info <row> <col> <building row> <building col> <name>\t<notes>` after the cell for each of its buildings with a name or notes, escaped with escape_text()
+pub fn save_city(path:&Path, districts:&[Grouping], city_grid:&Grid<GroupInstance>, lazy_nhoods:Option<LazyNeighborhoods>) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", get_save_header())?;
+    for district in districts {
+        let (r, g, b) = district.rgb_color;
+        writeln!(writer, "district {} {} {} {} {} {}", district.id, r, g, b, district.locked as u8, district.name)?;
+    }//end writing each district
+    if let Some(lazy_nhoods) = lazy_nhoods {
+        writeln!(writer, "lazy {} {} {}", lazy_nhoods.seed, lazy_nhoods.dims.0, lazy_nhoods.dims.1)?;
+    }//end if some neighborhoods are left for later
+
+    writeln!(writer, "grid {} {}", city_grid.rows(), city_grid.cols())?;
+    for row in 0..city_grid.rows() {
+        for col in 0..city_grid.cols() {
+            let instance = city_grid.get(row, col).unwrap();
+            let district_id = instance.district_id.unwrap_or(0);
+            writeln!(writer, "cell {} {} {} {} {} {} {}", row, col, district_id, instance.cell_locked as u8, instance.nhood_locked as u8, instance.sub_grid.rows(), instance.sub_grid.cols())?;
+            for sub_row in instance.sub_grid.iter_rows() {
+                let types: Vec<String> = sub_row.map(|building| {
+                    if building.locked {format!("{}{}", building.build_type, get_locked_marker())}
+                    else {building.build_type.to_string()}
+                }).collect();
+                writeln!(writer, "{}", types.join(","))?;
+            }//end writing each row of buildings
+            for sub_row in 0..instance.sub_grid.rows() {
+                for sub_col in 0..instance.sub_grid.cols() {
+                    if let Some(info) = &instance.sub_grid.get(sub_row, sub_col).unwrap().info {
+                        writeln!(writer, "info {} {} {} {} {}\t{}", row, col, sub_row, sub_col, escape_text(&info.name), escape_text(&info.notes))?;
+                    }//end if this building has info to save
+                }//end looping over building columns
+            }//end looping over building rows
+        }//end looping over columns
+    }//end looping over rows
+    writer.flush()?;
+    return Ok(());
+}//end save_city(path, districts, city_grid, lazy_nhoods)
+
+/// # load_city(path)
+///
+/// Reads a city saved by save_city() from path.
+/// Everything is checked the same way it would be if it were entered in the gui, including district names and the sizes of the grid and neighborhoods, and each cell can only be listed once.
+/// The locations of each district are rebuilt from the cells that refer to it.
+/// Buildings are all colored black, so they should be recolored with a building palette afterwards.
+///
+/// ## Return
+/// Returns the loaded city, or an error describing the first problem found in the file.
+pub fn load_city(path:&Path) -> Result<LoadedCity, CityGenError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut lines = reader.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+    // make sure this is actually a save file
+    match lines.next() {
+        Some((_, Ok(line))) if line.trim_end() == get_save_header() => {},
+        Some((_, Err(e))) => return Err(e.into()),
+        _ => return Err(invalid_data(1, "this isn't a city save file, or it was saved by a newer version")),
+    }//end matching header line
+
+    let all_types = all_building_types();
+    let mut districts: Vec<Grouping> = Vec::new();
+    let mut city_grid: Option<Grid<GroupInstance>> = None;
+    let mut lazy_nhoods: Option<LazyNeighborhoods> = None;
+    // which cells of the grid have been read so far
+    let mut cells_read: Grid<bool> = Grid::new(0, 0);
+    while let Some((line_num, line)) = lines.next() {
+        let line = line?;
+        if line.trim().is_empty() {continue;}
+        let mut parts = line.splitn(7, ' ');
+        match parts.next() {
+            Some("district") => {
+                let id = parse_field(parts.next(), line_num, "district id")?;
+                let r = parse_field(parts.next(), line_num, "red value")?;
+                let g = parse_field(parts.next(), line_num, "green value")?;
+                let b = parse_field(parts.next(), line_num, "blue value")?;
+                let locked: u8 = parse_field(parts.next(), line_num, "lock flag")?;
+                let name = parts.next().ok_or_else(|| invalid_data(line_num, "missing district name"))?;
+                if id == 0 || districts.iter().any(|district| district.id == id) {
+                    return Err(invalid_data(line_num, "district ids must be unique and can't be 0"));
+                }//end if id is invalid
+                let name = validate_district_name(name, &districts, None).map_err(|msg| invalid_data(line_num, &msg))?;
+                let mut district = Grouping::new(name).with_id(id).with_color((r, g, b));
+                district.locked = locked != 0;
+                districts.push(district);
+            },
+            Some("lazy") => {
+                let seed = parse_field(parts.next(), line_num, "city seed")?;
+                let rows = parse_field(parts.next(), line_num, "neighborhood rows")?;
+                let cols = parse_field(parts.next(), line_num, "neighborhood columns")?;
+                let allowed = get_min_neighborhood_len()..=get_max_neighborhood_len();
+                if !allowed.contains(&rows) || !allowed.contains(&cols) {
+                    return Err(invalid_data(line_num, &format!("neighborhoods must be {} to {} buildings across", get_min_neighborhood_len(), get_max_neighborhood_len())));
+                }//end if the neighborhood size is out of range
+                lazy_nhoods = Some(LazyNeighborhoods { seed, dims: (rows, cols) });
+            },
+            Some("grid") => {
+                if city_grid.is_some() {
+                    return Err(invalid_data(line_num, "the grid size is listed more than once"));
+                }//end if we already have a grid
+                let rows = parse_field(parts.next(), line_num, "grid rows")?;
+                let cols = parse_field(parts.next(), line_num, "grid columns")?;
+                // check before allocating anything, since a broken file could ask for any size
+                let allowed = 1..=get_max_district_len();
+                if !allowed.contains(&rows) || !allowed.contains(&cols) {
+                    return Err(invalid_data(line_num, &format!("the grid must be 1 to {} cells across", get_max_district_len())));
+                }//end if the grid size is out of range
+                let mut new_grid = Grid::new(rows, cols);
+                new_grid.fill(GroupInstance::default());
+                city_grid = Some(new_grid);
+                cells_read = Grid::new(rows, cols);
+            },
+            Some("cell") => {
+                let grid = city_grid.as_mut().ok_or_else(|| invalid_data(line_num, "cell listed before the grid size"))?;
+                // cells have more fields than the other lines split off above
+                let mut parts = line.split(' ').skip(1);
+                let row: usize = parse_field(parts.next(), line_num, "cell row")?;
+                let col: usize = parse_field(parts.next(), line_num, "cell column")?;
+                let district_id: u32 = parse_field(parts.next(), line_num, "cell district id")?;
+                let cell_locked: u8 = parse_field(parts.next(), line_num, "cell lock flag")?;
+                let nhood_locked: u8 = parse_field(parts.next(), line_num, "neighborhood lock flag")?;
+                let sub_rows: usize = parse_field(parts.next(), line_num, "neighborhood rows")?;
+                let sub_cols: usize = parse_field(parts.next(), line_num, "neighborhood columns")?;
+                // neighborhoods that haven't been generated yet have no buildings at all
+                if sub_rows > get_max_neighborhood_len() || sub_cols > get_max_neighborhood_len() {
+                    return Err(invalid_data(line_num, &format!("neighborhoods can't be more than {} buildings across", get_max_neighborhood_len())));
+                }//end if the neighborhood is too big
+
+                let coord = Coord::new(row, col);
+                let instance = grid.get_mut(row, col).ok_or_else(|| invalid_data(line_num, "cell is outside the grid"))?;
+                let already_read = cells_read.get_mut(row, col).unwrap();
+                if *already_read {
+                    return Err(invalid_data(line_num, "cell is listed more than once"));
+                }//end if we've seen this cell before
+                *already_read = true;
+                if district_id != 0 {
+                    let district = districts.iter_mut().find(|district| district.id == district_id)
+                        .ok_or_else(|| invalid_data(line_num, "cell refers to a district that doesn't exist"))?;
+                    district.locations.push(coord);
+                    instance.district_id = Some(district.id);
+                    instance.coord = Some(coord);
+                }//end if this cell has a district
+                instance.cell_locked = cell_locked != 0;
+                instance.nhood_locked = nhood_locked != 0;
+
+                // read the buildings in the neighborhood
+                instance.initialize_sub_grid(sub_rows, sub_cols);
+                for sub_row in 0..sub_rows {
+                    let (row_line_num, row_line) = lines.next().ok_or_else(|| invalid_data(line_num, "file ended in the middle of a neighborhood"))?;
+                    let row_line = row_line?;
+                    let tokens: Vec<&str> = row_line.split(',').map(|token| token.trim()).collect();
+                    if tokens.len() != sub_cols {
+                        return Err(invalid_data(row_line_num, "wrong number of buildings in neighborhood row"));
+                    }//end if row is the wrong length
+                    for (sub_col, token) in tokens.iter().enumerate() {
+                        let locked = token.ends_with(get_locked_marker());
+                        let type_name = token.trim_end_matches(get_locked_marker());
+                        let build_type = all_types.iter().find(|build_type| build_type.to_string() == type_name)
+                            .ok_or_else(|| invalid_data(row_line_num, &format!("unknown building type {}", type_name)))?;
+                        *instance.sub_grid.get_mut(sub_row, sub_col).unwrap() = Building {
+                            build_type: build_type.clone(),
+                            rgb_color: (0,0,0),
+                            locked,
+                            info: None,
+                        };//end struct construction
+                    }//end reading each building
+                }//end reading each row of buildings
+            },
+            Some("info") => {
+                let grid = city_grid.as_mut().ok_or_else(|| invalid_data(line_num, "building info listed before the grid size"))?;
+                // names can have spaces in them, so only split off the numbers
+                let mut parts = line.splitn(6, ' ').skip(1);
+                let row: usize = parse_field(parts.next(), line_num, "cell row")?;
+                let col: usize = parse_field(parts.next(), line_num, "cell column")?;
+                let sub_row: usize = parse_field(parts.next(), line_num, "building row")?;
+                let sub_col: usize = parse_field(parts.next(), line_num, "building column")?;
+                let (name, notes) = parts.next().and_then(|text| text.split_once('\t'))
+                    .ok_or_else(|| invalid_data(line_num, "missing building name and notes"))?;
+                let building = grid.get_mut(row, col).and_then(|instance| instance.sub_grid.get_mut(sub_row, sub_col))
+                    .ok_or_else(|| invalid_data(line_num, "building info is for a building that doesn't exist"))?;
+                building.set_info(&unescape_text(name), &unescape_text(notes));
+            },
+            _ => return Err(invalid_data(line_num, "unrecognized line")),
+        }//end matching line type
+    }//end reading each line
+
+    let city_grid = city_grid.ok_or_else(|| invalid_data(1, "file doesn't have a grid"))?;
+    return Ok((districts, city_grid, lazy_nhoods));
+}//end load_city(path)
+
+/// # all_building_types()
+///
+/// returns every building type that can show up in a save file
+fn all_building_types() -> Vec<BuildingType> {
+    let mut types = vec![BuildingType::Empty];
+    types.extend(BuildingType::editable_types());
+    return types;
+}//end all_building_types()
+
+/// # escape_text(text)
+///
+/// escapes backslashes, tabs and line breaks in text written by the user, so it fits on one line of a save or settings file
+pub fn escape_text(text:&str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "")
+}//end escape_text(text)
+
+/// # unescape_text(text)
+///
+/// undoes escape_text()
+pub fn unescape_text(text:&str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }//end if this is a normal character
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {},
+        }//end matching escaped character
+    }//end looking at each character
+    return result;
+}//end unescape_text(text)
+
+/// # parse_field(field, line_num, description)
+///
+/// parses a single space separated field from a save or settings file, describing what was expected if it's missing or invalid
+pub fn parse_field<T: std::str::FromStr>(field:Option<&str>, line_num:usize, description:&str) -> Result<T, CityGenError> {
+    let field = field.ok_or_else(|| invalid_data(line_num, &format!("missing {}", description)))?;
+    return field.trim().parse::<T>().map_err(|_| invalid_data(line_num, &format!("invalid {} \"{}\"", description, field)));
+}//end parse_field(field, line_num, description)
+
+/// # invalid_data(line_num, message)
+///
+/// creates an error for a problem on line line_num of a save or settings file
+pub fn invalid_data(line_num:usize, message:&str) -> CityGenError {
+    CityGenError::BadConfig { line: line_num, message: message.to_string() }
+}//end invalid_data(line_num, message)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// # temp_path(name)
+    ///
+    /// returns a path in the temp directory for a test file, which won't clash with other tests running at the same time
+    fn temp_path(name:&str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("city-generator-test-{}-{}.city", std::process::id(), name))
+    }//end temp_path(name)
+
+    /// # load_text(name, text)
+    ///
+    /// writes text to a temporary file and tries to load it as a city
+    fn load_text(name:&str, text:&str) -> Result<LoadedCity, CityGenError> {
+        let path = temp_path(name);
+        std::fs::write(&path, text).unwrap();
+        let result = load_city(&path);
+        let _ = std::fs::remove_file(&path);
+        result
+    }//end load_text(name, text)
+
+    /// # sample_city()
+    ///
+    /// makes a small city with locks, building info, and one neighborhood that hasn't been generated yet
+    fn sample_city() -> (Vec<Grouping>, Grid<GroupInstance>) {
+        let mut districts = vec![
+            Grouping::new("Old Town".to_string()).with_id(1).with_color((200,10,10)),
+            Grouping::new("docks".to_string()).with_id(4).with_color((0,0,255)),
+        ];
+        districts[1].locked = true;
+        let types = all_building_types();
+        let mut grid: Grid<GroupInstance> = Grid::new(2, 2);
+        for row in 0..2 {
+            for col in 0..2 {
+                let coord = Coord::new(row, col);
+                let district = &mut districts[col];
+                district.locations.push(coord);
+                let instance = grid.get_mut(row, col).unwrap();
+                *instance = GroupInstance::new(district.id, coord);
+                // leave the last neighborhood ungenerated
+                if row == 1 && col == 1 {continue;}
+                instance.initialize_sub_grid(3, 4);
+                for (index, building) in instance.sub_grid.iter_mut().enumerate() {
+                    building.build_type = types[(index + row * 2 + col) % types.len()].clone();
+                }//end giving each building a type
+            }//end looping over columns
+        }//end looping over rows
+        let instance = grid.get_mut(0, 1).unwrap();
+        instance.cell_locked = true;
+        instance.nhood_locked = true;
+        let building = instance.sub_grid.get_mut(2, 3).unwrap();
+        building.locked = true;
+        building.set_info("The \"Anchor\"", "tabs\there\nand a second line");
+        (districts, grid)
+    }//end sample_city()
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let (districts, grid) = sample_city();
+        let lazy = Some(LazyNeighborhoods { seed: 1234, dims: (3, 4) });
+        let path = temp_path("round-trip");
+        save_city(&path, &districts, &grid, lazy).unwrap();
+        let (loaded_districts, loaded_grid, loaded_lazy) = load_city(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded_districts, districts);
+        assert_eq!(loaded_grid, grid);
+        assert_eq!(loaded_lazy, lazy);
+    }//end save_and_load_round_trip()
+
+    #[test]
+    fn load_rejects_bad_district_names() {
+        let header = get_save_header();
+        assert!(load_text("empty-name", &format!("{}\ndistrict 1 0 0 0 0  \ngrid 1 1\n", header)).is_err());
+        assert!(load_text("reserved-name", &format!("{}\ndistrict 1 0 0 0 0 Empty\ngrid 1 1\n", header)).is_err());
+        assert!(load_text("same-name", &format!("{}\ndistrict 1 0 0 0 0 north\ndistrict 2 0 0 0 0 North\ngrid 1 1\n", header)).is_err());
+        assert!(load_text("good-name", &format!("{}\ndistrict 1 0 0 0 0 north\ndistrict 2 0 0 0 0 south\ngrid 1 1\n", header)).is_ok());
+    }//end load_rejects_bad_district_names()
+
+    #[test]
+    fn load_rejects_repeated_cells() {
+        let text = format!("{}\ndistrict 1 0 0 0 0 north\ngrid 1 2\ncell 0 0 1 0 0 0 0\ncell 0 0 1 0 0 0 0\n", get_save_header());
+        match load_text("repeated-cell", &text) {
+            Err(CityGenError::BadConfig { line, .. }) => assert_eq!(line, 5),
+            other => panic!("expected an error on line 5, got {:?}", other.map(|_| ())),
+        }//end matching result
+    }//end load_rejects_repeated_cells()
+
+    #[test]
+    fn load_rejects_huge_sizes() {
+        let header = get_save_header();
+        assert!(load_text("huge-grid", &format!("{}\ngrid 100000000 100000000\n", header)).is_err());
+        assert!(load_text("empty-grid", &format!("{}\ngrid 0 5\n", header)).is_err());
+        assert!(load_text("huge-nhood", &format!("{}\ngrid 1 1\ncell 0 0 0 0 0 100000 100000\n", header)).is_err());
+        assert!(load_text("huge-lazy", &format!("{}\nlazy 1 100000 3\ngrid 1 1\n", header)).is_err());
+        assert!(load_text("two-grids", &format!("{}\ngrid 1 1\ngrid 2 2\n", header)).is_err());
+    }//end load_rejects_huge_sizes()
+}//end mod tests
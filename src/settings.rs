@@ -0,0 +1,304 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::error::CityGenError;
+use crate::grouping::Grouping;
+use crate::save::escape_text;
+use crate::save::invalid_data;
+use crate::save::parse_field;
+use crate::save::unescape_text;
+use crate::theme::get_default_color_theme_name;
+use crate::theme::get_default_scheme_name;
+
+/// # get_settings_header()
+///
+/// The first line of the settings file, used to recognize it and its format version
+fn get_settings_header() -> &'static str {"simple-city-generator settings 1"}
+
+/// # get_max_recent_files()
+///
+/// The most saved cities listed under File/Open Recent
+fn get_max_recent_files() -> usize {8}
+
+/// # get_settings_path()
+///
+/// Where the settings file lives, inside a folder for this program in the user's config directory, wherever each platform keeps that.
+/// Returns None if there's no way to tell where the config directory is.
+pub fn get_settings_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("simple-city-generator").join("settings.txt"))
+}//end get_settings_path()
+
+/// # LoadedSettings
+///
+/// The settings read from a file, along with a description of each line that was skipped because it couldn't be read
+pub type LoadedSettings = (AppSettings, Vec<String>);
+
+/// # AppSettings
+///
+/// Everything about the program that's remembered from one launch to the next
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppSettings {
+    /// the (rows, cols) of districts last typed in
+    pub district_dims: (usize,usize),
+    /// the (rows, cols) of each neighborhood last typed in
+    pub nhood_dims: (usize,usize),
+    /// the city seed last typed in, or None to generate a different city every time
+    pub city_seed: Option<u64>,
+    /// whether neighborhoods are left ungenerated until they're viewed or exported
+    pub lazy_neighborhoods: bool,
+    /// the name of the widget scheme last chosen from the View menu
+    pub scheme: String,
+    /// the name of the color theme last chosen from the View menu
+    pub color_theme: String,
+    /// the districts last in the list. Ids aren't saved, so they get new ones when loaded.
+    /// If this is empty, the built in districts are used instead.
+    pub districts: Vec<Grouping>,
+    /// the folder the last export was saved to
+    pub export_dir: Option<PathBuf>,
+    /// the (width, height) of the main window when it was last closed, or None for the default size
+    pub window_size: Option<(i32,i32)>,
+    /// the cities most recently saved or opened, most recent first
+    pub recent_files: Vec<PathBuf>,
+}//end struct AppSettings
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            district_dims: (10,10),
+            nhood_dims: (10,10),
+            city_seed: None,
+            lazy_neighborhoods: false,
+            scheme: get_default_scheme_name().to_string(),
+            color_theme: get_default_color_theme_name().to_string(),
+            districts: Vec::new(),
+            export_dir: None,
+            window_size: None,
+            recent_files: Vec::new(),
+        }//end struct construction
+    }//end default()
+}//end Default impl for AppSettings
+
+impl AppSettings {
+    /// # load()
+    ///
+    /// Reads the settings file from get_settings_path().
+    /// If there isn't a settings file yet, such as on the first launch, the default settings are returned.
+    ///
+    /// ## Return
+    /// Returns the settings and any skipped lines, or an error if the file couldn't be opened or isn't a settings file.
+    pub fn load() -> Result<LoadedSettings, CityGenError> {
+        match get_settings_path() {
+            Some(path) if path.exists() => AppSettings::load_from(&path),
+            _ => Ok((AppSettings::default(), Vec::new())),
+        }//end matching whether there are settings to load
+    }//end load()
+
+    /// # load_from(path)
+    ///
+    /// Reads settings written by save_to() from path.
+    /// Lines that aren't recognized or can't be read are skipped one at a time, so settings from newer versions or with a typo in them still mostly load.
+    /// A description of each skipped line is returned along with the settings, so they can all be shown to the user at once.
+    ///
+    /// The format is one setting per line:
+    /// - the header line
+    /// - `districts <rows> <cols>` and `neighborhoods <rows> <cols>`
+    /// - `seed <city seed>`, only if there is one
+    /// - `lazy <0 or 1>`
+    /// - `scheme <name>` and `colors <name>`
+    /// - `district <r> <g> <b> <locked> <name>` for each district in the list
+    /// - `export_dir <path>`, only if there is one
+    /// - `window <width> <height>`, only if there is one
+    /// - `recent <path>` for each recent file, most recent first
+    ///
+    /// Names and paths are escaped with escape_text().
+    pub fn load_from(path:&Path) -> Result<LoadedSettings, CityGenError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+        match lines.next() {
+            Some((_, Ok(line))) if line.trim_end() == get_settings_header() => {},
+            Some((_, Err(e))) => return Err(e.into()),
+            _ => return Err(invalid_data(1, "this isn't a settings file, or it was saved by a newer version")),
+        }//end matching header line
+
+        let mut settings = AppSettings::default();
+        let mut skipped: Vec<String> = Vec::new();
+        for (line_num, line) in lines {
+            match settings.read_line(&line?, line_num) {
+                Ok(()) => {},
+                Err(CityGenError::BadConfig { message, .. }) => skipped.push(format!("line {}: {}", line_num, message)),
+                Err(e) => skipped.push(format!("line {}: {}", line_num, e)),
+            }//end matching whether the line could be read
+        }//end reading each line
+        settings.recent_files.truncate(get_max_recent_files());
+        return Ok((settings, skipped));
+    }//end load_from(path)
+
+    /// # read_line(&mut self, line, line_num)
+    ///
+    /// Reads a single setting from line, which is line number line_num of the settings file, in the format described by load_from().
+    /// Nothing is changed if the line can't be read.
+    fn read_line(&mut self, line:&str, line_num:usize) -> Result<(), CityGenError> {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let mut parts = value.splitn(5, ' ');
+        match key {
+            "districts" => self.district_dims = (parse_field(parts.next(), line_num, "district rows")?, parse_field(parts.next(), line_num, "district columns")?),
+            "neighborhoods" => self.nhood_dims = (parse_field(parts.next(), line_num, "neighborhood rows")?, parse_field(parts.next(), line_num, "neighborhood columns")?),
+            "seed" => self.city_seed = Some(parse_field(parts.next(), line_num, "city seed")?),
+            "lazy" => self.lazy_neighborhoods = parse_field::<u8>(parts.next(), line_num, "lazy flag")? != 0,
+            "scheme" => self.scheme = value.trim().to_string(),
+            "colors" => self.color_theme = value.trim().to_string(),
+            "district" => {
+                let r = parse_field(parts.next(), line_num, "red value")?;
+                let g = parse_field(parts.next(), line_num, "green value")?;
+                let b = parse_field(parts.next(), line_num, "blue value")?;
+                let locked: u8 = parse_field(parts.next(), line_num, "lock flag")?;
+                let name = parts.next().ok_or_else(|| invalid_data(line_num, "missing district name"))?;
+                let mut district = Grouping::new(unescape_text(name)).with_color((r, g, b));
+                district.locked = locked != 0;
+                self.districts.push(district);
+            },
+            "export_dir" => self.export_dir = Some(PathBuf::from(unescape_text(value))),
+            "window" => self.window_size = Some((parse_field(parts.next(), line_num, "window width")?, parse_field(parts.next(), line_num, "window height")?)),
+            "recent" => self.recent_files.push(PathBuf::from(unescape_text(value))),
+            _ => return Err(invalid_data(line_num, "unrecognized setting")),
+        }//end matching setting name
+        return Ok(());
+    }//end read_line(&mut self, line, line_num)
+
+    /// # save(&self)
+    ///
+    /// Writes the settings to get_settings_path(), creating its folder if needed
+    pub fn save(&self) -> Result<(), CityGenError> {
+        let path = get_settings_path().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "couldn't find a config directory to save settings in"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }//end if the settings go in a folder
+        self.save_to(&path)?;
+        return Ok(());
+    }//end save(&self)
+
+    /// # save_to(&self, path)
+    ///
+    /// Writes the settings to a text file at path, in the format read by load_from()
+    pub fn save_to(&self, path:&Path) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", get_settings_header())?;
+        writeln!(writer, "districts {} {}", self.district_dims.0, self.district_dims.1)?;
+        writeln!(writer, "neighborhoods {} {}", self.nhood_dims.0, self.nhood_dims.1)?;
+        if let Some(seed) = self.city_seed {
+            writeln!(writer, "seed {}", seed)?;
+        }//end if there's a seed to remember
+        writeln!(writer, "lazy {}", self.lazy_neighborhoods as u8)?;
+        writeln!(writer, "scheme {}", self.scheme)?;
+        writeln!(writer, "colors {}", self.color_theme)?;
+        for district in &self.districts {
+            let (r, g, b) = district.rgb_color;
+            writeln!(writer, "district {} {} {} {} {}", r, g, b, district.locked as u8, escape_text(&district.name))?;
+        }//end writing each district
+        if let Some(dir) = &self.export_dir {
+            writeln!(writer, "export_dir {}", escape_text(&dir.to_string_lossy()))?;
+        }//end if there's an export folder to remember
+        if let Some((width, height)) = self.window_size {
+            writeln!(writer, "window {} {}", width, height)?;
+        }//end if there's a window size to remember
+        for path in &self.recent_files {
+            writeln!(writer, "recent {}", escape_text(&path.to_string_lossy()))?;
+        }//end writing each recent file
+        writer.flush()?;
+        return Ok(());
+    }//end save_to(&self, path)
+
+    /// # add_recent_file(&mut self, path)
+    ///
+    /// Moves path to the top of the recent files, dropping the oldest one if there are too many
+    pub fn add_recent_file(&mut self, path:&Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(get_max_recent_files());
+    }//end add_recent_file(&mut self, path)
+}//end impl for AppSettings
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// # temp_path(name)
+    ///
+    /// returns a path in the temp directory for a test settings file, which won't clash with other tests running at the same time
+    fn temp_path(name:&str) -> PathBuf {
+        std::env::temp_dir().join(format!("city-generator-test-{}-{}-settings.txt", std::process::id(), name))
+    }//end temp_path(name)
+
+    /// # load_text(name, text)
+    ///
+    /// writes text to a temporary file and tries to load it as settings
+    fn load_text(name:&str, text:&str) -> Result<LoadedSettings, CityGenError> {
+        let path = temp_path(name);
+        std::fs::write(&path, text).unwrap();
+        let result = AppSettings::load_from(&path);
+        let _ = std::fs::remove_file(&path);
+        result
+    }//end load_text(name, text)
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut locked = Grouping::new("Harbor \\ \"Docks\"\tside".to_string()).with_color((0,128,255));
+        locked.locked = true;
+        let settings = AppSettings {
+            district_dims: (40, 25),
+            nhood_dims: (6, 9),
+            city_seed: Some(u64::MAX),
+            lazy_neighborhoods: true,
+            scheme: "Gtk".to_string(),
+            color_theme: "Dark 2".to_string(),
+            districts: vec![Grouping::new("Old Town".to_string()).with_color((200,10,10)), locked],
+            export_dir: Some(PathBuf::from("/home/someone/city exports")),
+            window_size: Some((1280, 720)),
+            recent_files: vec![PathBuf::from("/tmp/a city.city"), PathBuf::from("relative/b.city")],
+        };
+        let path = temp_path("round-trip");
+        settings.save_to(&path).unwrap();
+        let loaded = AppSettings::load_from(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), (settings, Vec::new()));
+
+        // leaving out the optional settings still round trips
+        let defaults = AppSettings::default();
+        let path = temp_path("defaults");
+        defaults.save_to(&path).unwrap();
+        let loaded = AppSettings::load_from(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), (defaults, Vec::new()));
+    }//end save_and_load_round_trip()
+
+    #[test]
+    fn bad_lines_are_skipped_one_at_a_time() {
+        let text = format!("{}\ndistricts 12 x\nneighborhoods 7 8\nseed -4\nlazy 1\ndistrict 300 0 0 0 Too Red\ndistrict 1 2 3 1 Fine\nfuture_setting 5\nwindow 800\nrecent /tmp/kept.city\n", get_settings_header());
+        let (settings, skipped) = load_text("bad-lines", &text).unwrap();
+        assert_eq!(skipped.len(), 5, "{:?}", skipped);
+        assert!(skipped[0].starts_with("line 2:"));
+        assert!(skipped[3].contains("unrecognized"));
+        let defaults = AppSettings::default();
+        assert_eq!(settings.district_dims, defaults.district_dims);
+        assert_eq!(settings.nhood_dims, (7, 8));
+        assert_eq!(settings.city_seed, None);
+        assert!(settings.lazy_neighborhoods);
+        assert_eq!(settings.districts.len(), 1);
+        assert_eq!(settings.districts[0].name, "Fine");
+        assert!(settings.districts[0].locked);
+        assert_eq!(settings.window_size, None);
+        assert_eq!(settings.recent_files, vec![PathBuf::from("/tmp/kept.city")]);
+    }//end bad_lines_are_skipped_one_at_a_time()
+
+    #[test]
+    fn load_rejects_other_files() {
+        assert!(load_text("not-settings", "districts 3 3\n").is_err());
+        assert!(load_text("empty", "").is_err());
+    }//end load_rejects_other_files()
+}//end mod tests
@@ -0,0 +1,104 @@
+use fltk::app;
+use fltk::enums::Color;
+use fltk_theme::ColorMap;
+use fltk_theme::ColorTheme;
+use fltk_theme::SchemeType;
+use fltk_theme::WidgetScheme;
+use fltk_theme::color_themes;
+
+/// # get_default_scheme_name()
+///
+/// The widget scheme used when nothing else has been chosen
+pub fn get_default_scheme_name() -> &'static str {"Fluent"}
+
+/// # get_default_color_theme_name()
+///
+/// The color theme used when nothing else has been chosen, which is fltk's own colors
+pub fn get_default_color_theme_name() -> &'static str {"Light"}
+
+/// # get_schemes()
+///
+/// returns the name and type of each widget scheme that can be chosen from the View menu, which change how widgets are drawn without changing their colors
+pub fn get_schemes() -> Vec<(&'static str, SchemeType)> {
+	vec![
+		("Fluent", SchemeType::Fluent),
+		("Aqua", SchemeType::Aqua),
+		("Clean", SchemeType::Clean),
+		("Crystal", SchemeType::Crystal),
+		("Gleam", SchemeType::Gleam),
+		("Vector", SchemeType::SvgBased),
+	]
+}//end get_schemes()
+
+/// # get_color_theme_names()
+///
+/// returns the name of each color theme that can be chosen from the View menu, starting with the default
+pub fn get_color_theme_names() -> Vec<&'static str> {
+	vec![get_default_color_theme_name(), "Dark", "Black", "Gray", "Tan", "Shake"]
+}//end get_color_theme_names()
+
+/// # ThemeManager
+///
+/// Applies widget schemes and color themes by name.
+/// Color themes change entries of fltk's color map, so the original entries are kept to go back to the default colors.
+pub struct ThemeManager {
+	/// fltk's color map from before any color theme was applied
+	default_colors: ColorTheme,
+}//end struct ThemeManager
+
+impl ThemeManager {
+	/// # new()
+	///
+	/// remembers fltk's current colors as the default theme, so this should be created before any color theme is applied
+	pub fn new() -> ThemeManager {
+		let default_colors: Vec<ColorMap> = (0..=255u8).map(|index| {
+			let (r, g, b) = Color::by_index(index).to_rgb();
+			ColorMap { index, r, g, b }
+		}).collect();
+		ThemeManager {
+			default_colors: ColorTheme::new(&default_colors),
+		}//end struct construction
+	}//end new()
+
+	/// # apply_scheme(&self, name)
+	///
+	/// switches to the widget scheme called name, or to the default scheme if there isn't one by that name.
+	/// Returns the name of the scheme actually used.
+	pub fn apply_scheme(&self, name:&str) -> &'static str {
+		let schemes = get_schemes();
+		let (name, scheme) = schemes.iter()
+			.find(|(scheme_name, _)| *scheme_name == name)
+			.or_else(|| schemes.iter().find(|(scheme_name, _)| *scheme_name == get_default_scheme_name()))
+			.copied()
+			.unwrap();
+		WidgetScheme::new(scheme).apply();
+		app::redraw();
+		return name;
+	}//end apply_scheme(&self, name)
+
+	/// # apply_color_theme(&self, name)
+	///
+	/// switches to the color theme called name, or to the default colors if there isn't one by that name.
+	/// Returns the name of the theme actually used.
+	pub fn apply_color_theme(&self, name:&str) -> &'static str {
+		let colors: &[ColorMap] = match name {
+			"Dark" => color_themes::DARK_THEME,
+			"Black" => color_themes::BLACK_THEME,
+			"Gray" => color_themes::GRAY_THEME,
+			"Tan" => color_themes::TAN_THEME,
+			"Shake" => color_themes::SHAKE_THEME,
+			_ => &[],
+		};//end matching name
+		// themes only change some colors, so start over from the defaults to undo whatever the last theme changed
+		self.default_colors.apply();
+		let name = match get_color_theme_names().into_iter().find(|theme_name| *theme_name == name) {
+			Some(theme_name) if !colors.is_empty() => {
+				ColorTheme::new(colors).apply();
+				theme_name
+			},
+			_ => get_default_color_theme_name(),
+		};//end matching whether there's a theme to apply over the defaults
+		app::redraw();
+		return name;
+	}//end apply_color_theme(&self, name)
+}//end impl for ThemeManager